@@ -1,11 +1,21 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
 use cagents_telemetry::{TelemetryClient, CommandEvent};
+use std::path::{Path, PathBuf};
 use std::time::Instant;
 
 #[derive(Parser)]
 #[command(name = "cagents", version, about = "cAGENTS CLI")]
 struct Cli {
+    /// Run as if cagents was started in <DIR> (like `git -C`)
+    #[arg(short = 'C', global = true, value_name = "DIR")]
+    directory: Option<PathBuf>,
+    /// Project config file to use instead of .cAGENTS/config.toml
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Config profile to apply ([profiles.<name>] sections; also CAGENTS_PROFILE)
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
     #[command(subcommand)]
     cmd: Command,
 }
@@ -26,7 +36,15 @@ enum Command {
     /// Build AGENTS.md (and optional exports) for all targets
     Build {
         #[arg(long)] out: Option<String>,
+        /// Print unified diffs of what would change without writing anything
         #[arg(long)] dry_run: bool,
+        /// Fail if any output is stale, missing or extra (for CI); writes nothing
+        #[arg(long, conflicts_with = "dry_run")] check: bool,
+        /// With --check, print the report as JSON
+        #[arg(long, requires = "check")] json: bool,
+        /// Variables in key=value format (can be specified multiple times)
+        #[arg(long = "var", value_name = "KEY=VALUE")]
+        vars: Vec<String>,
     },
     /// Validate configuration and rules
     Lint,
@@ -61,6 +79,9 @@ enum Command {
         /// Variables in key=value format (can be specified multiple times)
         #[arg(long = "var", value_name = "KEY=VALUE")]
         vars: Vec<String>,
+        /// Output target to render for (defaults to the first configured target)
+        #[arg(long)]
+        target: Option<String>,
     },
     /// Show comprehensive context and metadata for a file
     Context {
@@ -69,10 +90,27 @@ enum Command {
         /// Variables in key=value format (can be specified multiple times)
         #[arg(long = "var", value_name = "KEY=VALUE")]
         vars: Vec<String>,
+        /// Output target to show context for (defaults to the first configured target)
+        #[arg(long)]
+        target: Option<String>,
         /// Output in JSON format instead of Markdown
         #[arg(long)]
         json: bool,
     },
+    /// Show resolved variables with their values and sources
+    Vars {
+        /// Variables in key=value format (can be specified multiple times)
+        #[arg(long = "var", value_name = "KEY=VALUE")]
+        vars: Vec<String>,
+        /// Output in JSON format instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Get and set configuration values across user, project and local config
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
     /// Manage telemetry settings
     Telemetry {
         #[command(subcommand)]
@@ -80,6 +118,44 @@ enum Command {
     },
 }
 
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the effective value of a key (e.g. paths.outputRoot)
+    Get {
+        key: String,
+        /// Read from a single layer instead of the merged config (user, project, local)
+        #[arg(long)]
+        layer: Option<String>,
+        /// Show which config file set the value
+        #[arg(long)]
+        origin: bool,
+    },
+    /// Set a key in a config layer, preserving formatting and comments
+    Set {
+        key: String,
+        value: String,
+        /// Layer to write to (user, project, local; default: project)
+        #[arg(long)]
+        layer: Option<String>,
+    },
+    /// Remove a key from a config layer
+    Unset {
+        key: String,
+        /// Layer to remove from (user, project, local; default: project)
+        #[arg(long)]
+        layer: Option<String>,
+    },
+    /// Print all config values
+    Show {
+        /// Show a single layer instead of the merged config (user, project, local)
+        #[arg(long)]
+        layer: Option<String>,
+        /// Show which config file set each value
+        #[arg(long)]
+        origin: bool,
+    },
+}
+
 #[derive(Subcommand)]
 enum TelemetryAction {
     /// Enable telemetry
@@ -100,7 +176,20 @@ enum GitAction {
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
+
+    if let Some(dir) = &cli.directory {
+        std::env::set_current_dir(dir)
+            .map_err(|e| anyhow::anyhow!("Cannot change to directory {}: {}", dir.display(), e))?;
+    }
+    let config = enter_project_root(&mut cli.cmd, cli.config.as_deref())?;
+
+    // Which config and profile every command loads (the profile defaults to CAGENTS_PROFILE)
+    let mut load = cagents_core::config::LoadOptions::from_env();
+    load.config = config;
+    if let Some(profile) = cli.profile {
+        load.profile = Some(profile);
+    }
 
     // Initialize telemetry client
     let mut telemetry = TelemetryClient::new().unwrap_or_default();
@@ -114,21 +203,37 @@ async fn main() -> Result<()> {
     // Execute command
     let result = match cli.cmd {
         Command::Init{preset, force, dry_run, backup} => cagents_core::cmd_init(&preset, force, dry_run, backup),
-        Command::Build{out,dry_run} => cagents_core::cmd_build(out, dry_run),
-        Command::Lint => cagents_core::cmd_lint(),
-        Command::Preview{path} => cagents_core::cmd_preview(&path),
+        Command::Build{out, dry_run, check, json, vars} => cagents_core::cmd_build_with_options(cagents_core::BuildOptions {
+            out,
+            dry_run,
+            check,
+            json,
+            vars,
+            load: load.clone(),
+        }),
+        Command::Lint => cagents_core::cmd_lint(&load),
+        Command::Preview{path} => cagents_core::cmd_preview(&path, &load),
         Command::Migrate{from, backup} => cagents_core::cmd_migrate(from.as_deref(), backup),
         Command::Git{action} => {
             match action {
-                GitAction::IgnoreOutputs => cagents_core::helpers::git::ignore_outputs(),
-                GitAction::UnignoreOutputs => cagents_core::helpers::git::unignore_outputs(),
+                GitAction::IgnoreOutputs => cagents_core::helpers::git::ignore_outputs(&load),
+                GitAction::UnignoreOutputs => cagents_core::helpers::git::unignore_outputs(&load),
             }
         }
         Command::Setup{manager} => cagents_core::cmd_setup(&manager),
-        Command::Status => cagents_core::cmd_status(),
-        Command::Render{file, vars} => cagents_core::cmd_render(&file, vars),
-        Command::Context{file, vars, json} => cagents_core::cmd_context(&file, vars, json),
-        Command::Telemetry{action} => handle_telemetry_command(action, &telemetry),
+        Command::Status => cagents_core::cmd_status(&load),
+        Command::Render{file, vars, target} => cagents_core::cmd_render(&file, vars, target.as_deref(), &load),
+        Command::Context{file, vars, target, json} => cagents_core::cmd_context(&file, vars, target.as_deref(), json, &load),
+        Command::Vars{vars, json} => cagents_core::cmd_vars(vars, json, &load),
+        Command::Config{action} => {
+            match action {
+                ConfigAction::Get{key, layer, origin} => cagents_core::cmd_config_get(&key, layer.as_deref(), origin, &load),
+                ConfigAction::Set{key, value, layer} => cagents_core::cmd_config_set(&key, &value, layer.as_deref(), &load),
+                ConfigAction::Unset{key, layer} => cagents_core::cmd_config_unset(&key, layer.as_deref(), &load),
+                ConfigAction::Show{layer, origin} => cagents_core::cmd_config_show(layer.as_deref(), origin, &load),
+            }
+        }
+        Command::Telemetry{action} => handle_telemetry_command(action, &telemetry, &load),
     };
 
     // Track command execution (non-blocking)
@@ -153,6 +258,53 @@ async fn main() -> Result<()> {
     result
 }
 
+/// Find the nearest project above the current directory and run from its root
+///
+/// Path arguments are resolved against the directory the command was started in
+/// before switching, so `cagents render main.rs` works from any subdirectory.
+/// `init` and `migrate` always work on the current directory.
+///
+/// Returns the `--config` file as an absolute path, so it still resolves from the root.
+fn enter_project_root(cmd: &mut Command, config: Option<&Path>) -> Result<Option<PathBuf>> {
+    if matches!(cmd, Command::Init{..} | Command::Migrate{..} | Command::Telemetry{..}) {
+        return Ok(None);
+    }
+
+    let cwd = std::env::current_dir()?;
+    let config = config.map(|path| cwd.join(path));
+    let root = match &config {
+        Some(path) => {
+            if !path.is_file() {
+                anyhow::bail!("Config file not found: {}", path.display());
+            }
+
+            // A config inside a .cAGENTS directory belongs to that project
+            match path.parent() {
+                Some(dir) if dir.file_name().is_some_and(|name| name == ".cAGENTS") => {
+                    dir.parent().map(Path::to_path_buf)
+                }
+                _ => cagents_core::config::find_project_root(&cwd),
+            }
+        }
+        None => cagents_core::config::find_project_root(&cwd),
+    };
+
+    let Some(root) = root else {
+        return Ok(config);
+    };
+
+    let absolute = |path: &str| cwd.join(path).to_string_lossy().to_string();
+    match cmd {
+        Command::Render{file, ..} | Command::Context{file, ..} => *file = absolute(file),
+        Command::Build{out: Some(out), ..} => *out = absolute(out),
+        _ => {}
+    }
+
+    std::env::set_current_dir(&root)
+        .map_err(|e| anyhow::anyhow!("Cannot change to project root {}: {}", root.display(), e))?;
+    Ok(config)
+}
+
 fn get_command_name(cmd: &Command) -> String {
     match cmd {
         Command::Init{..} => "init".to_string(),
@@ -165,66 +317,31 @@ fn get_command_name(cmd: &Command) -> String {
         Command::Status => "status".to_string(),
         Command::Render{..} => "render".to_string(),
         Command::Context{..} => "context".to_string(),
+        Command::Vars{..} => "vars".to_string(),
+        Command::Config{..} => "config".to_string(),
         Command::Telemetry{..} => "telemetry".to_string(),
     }
 }
 
-fn handle_telemetry_command(action: TelemetryAction, client: &TelemetryClient) -> Result<()> {
-    use std::fs;
+fn handle_telemetry_command(
+    action: TelemetryAction,
+    client: &TelemetryClient,
+    load: &cagents_core::config::LoadOptions,
+) -> Result<()> {
+    use cagents_core::config_edit;
+    use cagents_core::model::ConfigLayer;
 
     match action {
         TelemetryAction::Enable => {
-            // Update user config
-            if let Some(home_dir) = dirs::home_dir() {
-                let config_dir = home_dir.join(".cagents");
-                let config_path = config_dir.join("config.toml");
-
-                fs::create_dir_all(&config_dir)?;
-
-                let content = if config_path.exists() {
-                    fs::read_to_string(&config_path)?
-                } else {
-                    String::new()
-                };
-
-                // Simple TOML update (replace [telemetry] section)
-                let new_content = if content.contains("[telemetry]") {
-                    content.replace("enabled = false", "enabled = true")
-                } else {
-                    format!("{}\n[telemetry]\nenabled = true\n", content)
-                };
-
-                fs::write(&config_path, new_content)?;
-                println!("✓ Telemetry enabled");
-                println!("  Machine ID: {}...", &client.machine_id()[..8]);
-                println!("  Learn more: https://github.com/centralinc/cagents/blob/main/docs/TELEMETRY.md");
-            }
+            config_edit::set_value(ConfigLayer::User, "telemetry.enabled", "true", load)?;
+            println!("✓ Telemetry enabled");
+            println!("  Machine ID: {}...", &client.machine_id()[..8]);
+            println!("  Learn more: https://github.com/centralinc/cagents/blob/main/docs/TELEMETRY.md");
         }
         TelemetryAction::Disable => {
-            // Update user config
-            if let Some(home_dir) = dirs::home_dir() {
-                let config_dir = home_dir.join(".cagents");
-                let config_path = config_dir.join("config.toml");
-
-                fs::create_dir_all(&config_dir)?;
-
-                let content = if config_path.exists() {
-                    fs::read_to_string(&config_path)?
-                } else {
-                    String::new()
-                };
-
-                // Simple TOML update
-                let new_content = if content.contains("[telemetry]") {
-                    content.replace("enabled = true", "enabled = false")
-                } else {
-                    format!("{}\n[telemetry]\nenabled = false\n", content)
-                };
-
-                fs::write(&config_path, new_content)?;
-                println!("✓ Telemetry disabled");
-                println!("  You can re-enable with: cagents telemetry enable");
-            }
+            config_edit::set_value(ConfigLayer::User, "telemetry.enabled", "false", load)?;
+            println!("✓ Telemetry disabled");
+            println!("  You can re-enable with: cagents telemetry enable");
         }
         TelemetryAction::Status => {
             println!("Telemetry Status");
@@ -0,0 +1,83 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{cagents, write_config, write_template};
+use predicates::prelude::*;
+
+fn setup_project(temp: &assert_fs::TempDir) {
+    write_config(temp, r#"
+[output]
+targets = ["windsurf", "cline", "gemini-md"]
+"#);
+
+    write_template(temp, "base.md", r#"---
+name: base
+description: Project basics
+---
+Be concise.
+"#);
+    write_template(temp, "web.md", r#"---
+name: web
+description: Frontend conventions
+globs: ["web/src/**/*.tsx"]
+outputIn: parent
+---
+Use function components in {{cagents.outputDir}}.
+"#);
+    write_template(temp, "gemini-only.md", r#"---
+name: gemini-only
+when:
+  target: ["gemini-md"]
+---
+Only for Gemini
+"#);
+
+    temp.child("web/src/app/App.tsx").write_str("").unwrap();
+}
+
+#[test]
+fn test_build_writes_windsurf_cline_and_gemini() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    temp.child(".windsurf/rules/base.md")
+        .assert(predicate::str::starts_with("---\ntrigger: always_on\ndescription: \"Project basics\"\n---\n"));
+    temp.child(".windsurf/rules/web.md")
+        .assert(predicate::str::starts_with("---\ntrigger: glob\nglobs: web/src/**/*.tsx\n"))
+        .assert(predicate::str::contains("Use function components in web/src/app."));
+
+    temp.child(".clinerules/base.md").assert(predicate::str::starts_with("<!--"));
+    temp.child(".clinerules/web.md")
+        .assert(predicate::str::starts_with("---\npaths:\n  - \"web/src/**/*.tsx\"\n---\n"));
+
+    temp.child("GEMINI.md")
+        .assert(predicate::str::contains("Be concise."))
+        .assert(predicate::str::contains("Only for Gemini"));
+    temp.child("web/src/app/GEMINI.md").assert(predicate::str::contains("Use function components"));
+
+    // when.target keeps the rule out of the other targets
+    temp.child(".windsurf/rules/gemini-only.md").assert(predicate::path::missing());
+    temp.child(".clinerules/gemini-only.md").assert(predicate::path::missing());
+}
+
+#[test]
+fn test_removed_targets_are_cleaned_up() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    let config = temp.child(".cAGENTS/config.toml");
+    let content = std::fs::read_to_string(config.path()).unwrap();
+    config
+        .write_str(&content.replace(r#"["windsurf", "cline", "gemini-md"]"#, r#"["agents-md"]"#))
+        .unwrap();
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    temp.child(".windsurf/rules/base.md").assert(predicate::path::missing());
+    temp.child(".clinerules/web.md").assert(predicate::path::missing());
+    temp.child("GEMINI.md").assert(predicate::path::missing());
+    temp.child("web/src/app/GEMINI.md").assert(predicate::path::missing());
+    temp.child("AGENTS.md").assert(predicate::path::exists());
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{cagents, write_config, write_template};
+use predicates::prelude::*;
+
+fn setup_project(temp: &assert_fs::TempDir) {
+    write_config(temp, r#"
+[output]
+targets = ["agents-md", "claude-md"]
+"#);
+
+    write_template(temp, "base.md", r#"---
+name: base
+---
+Use tabs
+"#);
+    write_template(temp, "api.md", r#"---
+name: api
+globs: ["api/**"]
+outputIn: parent
+---
+API rules
+"#);
+    temp.child("api/main.go").write_str("").unwrap();
+}
+
+#[test]
+fn test_check_passes_after_build() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    cagents()
+        .current_dir(temp.path())
+        .args(["build", "--check"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("All 4 output file(s) are up to date"));
+}
+
+#[test]
+fn test_check_fails_on_stale_missing_and_extra_outputs() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+    let cache = std::fs::read_to_string(temp.child(".cAGENTS/.output-cache").path()).unwrap();
+
+    // Hand-edited output, a new output directory, and an output that's no longer generated
+    temp.child("api/CLAUDE.md").write_str("edited by hand").unwrap();
+    temp.child(".cAGENTS/templates/web.md").write_str("---\nname: web\nglobs: [\"web/**\"]\noutputIn: parent\n---\nWeb rules\n").unwrap();
+    temp.child("web/index.ts").write_str("").unwrap();
+    std::fs::remove_file(temp.child("AGENTS.md").path()).unwrap();
+
+    cagents()
+        .current_dir(temp.path())
+        .args(["build", "--check"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::is_match(r"stale.*api/CLAUDE.md").unwrap())
+        .stdout(predicate::str::is_match(r"missing.* AGENTS.md").unwrap())
+        .stdout(predicate::str::is_match(r"missing.*web/AGENTS.md").unwrap())
+        .stderr(predicate::str::contains("4 output file(s) are out of date"));
+
+    // Nothing was written
+    temp.child("api/CLAUDE.md").assert("edited by hand");
+    temp.child("web/AGENTS.md").assert(predicate::path::missing());
+    temp.child(".cAGENTS/.output-cache").assert(cache.as_str());
+}
+
+#[test]
+fn test_check_json_report() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+    std::fs::remove_file(temp.child(".cAGENTS/templates/api.md").path()).unwrap();
+
+    let output = cagents()
+        .current_dir(temp.path())
+        .args(["build", "--check", "--json"])
+        .assert()
+        .failure()
+        .get_output()
+        .stdout
+        .clone();
+
+    let report: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(report["up_to_date"], false);
+    assert_eq!(report["checked"], 4);
+    assert_eq!(report["stale"], serde_json::json!([]));
+    assert_eq!(report["missing"], serde_json::json!([]));
+    assert_eq!(report["extra"], serde_json::json!(["api/AGENTS.md", "api/CLAUDE.md"]));
+}
+
+#[test]
+fn test_check_writes_no_command_cache() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    temp.child(".cAGENTS/config.local.toml").write_str(r#"
+[variables.command]
+version = { command = "echo 1.2.3", ttl = "1h" }
+"#).unwrap();
+
+    cagents()
+        .current_dir(temp.path())
+        .args(["build", "--check"])
+        .assert()
+        .success();
+
+    temp.child(".cAGENTS/.command-cache").assert(predicate::path::missing());
+}
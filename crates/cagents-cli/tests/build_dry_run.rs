@@ -0,0 +1,115 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{cagents, write_config, write_template};
+use predicates::prelude::*;
+
+fn setup_project(temp: &assert_fs::TempDir) {
+    write_config(temp, r#"
+[output]
+targets = ["agents-md", "claude-md"]
+"#);
+
+    write_template(temp, "base.md", r#"---
+name: base
+---
+Use tabs
+"#);
+    write_template(temp, "api.md", r#"---
+name: api
+globs: ["api/**"]
+outputIn: parent
+---
+API rules
+"#);
+    temp.child("api/main.go").write_str("").unwrap();
+}
+
+#[test]
+fn test_dry_run_writes_nothing() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+
+    cagents()
+        .current_dir(temp.path())
+        .args(["build", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("--- /dev/null"))
+        .stdout(predicate::str::contains("+++ b/AGENTS.md"))
+        .stdout(predicate::str::contains("+Use tabs"))
+        .stdout(predicate::str::contains("Would create 4, change 0, remove 0 file(s)"));
+
+    temp.child("AGENTS.md").assert(predicate::path::missing());
+    temp.child("api/CLAUDE.md").assert(predicate::path::missing());
+    temp.child(".cAGENTS/.output-cache").assert(predicate::path::missing());
+}
+
+#[test]
+fn test_dry_run_diffs_changes_and_removals() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+    let cache = std::fs::read_to_string(temp.child(".cAGENTS/.output-cache").path()).unwrap();
+    assert!(cache.contains("\"api/AGENTS.md\""), "tracked paths are normalized: {}", cache);
+    assert!(!cache.contains("./"), "tracked paths are normalized: {}", cache);
+
+    temp.child(".cAGENTS/templates/base.md").write_str("---\nname: base\n---\nUse 2 spaces\n").unwrap();
+    std::fs::remove_file(temp.child(".cAGENTS/templates/api.md").path()).unwrap();
+
+    cagents()
+        .current_dir(temp.path())
+        .args(["build", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("-Use tabs"))
+        .stdout(predicate::str::contains("+Use 2 spaces"))
+        .stdout(predicate::str::contains("+++ /dev/null"))
+        .stdout(predicate::str::contains("-API rules"))
+        .stdout(predicate::str::contains("Would create 0, change 2, remove 2 file(s)"));
+
+    // Nothing changed on disk, including the output cache
+    temp.child("AGENTS.md").assert(predicate::str::contains("Use tabs"));
+    temp.child("api/AGENTS.md").assert(predicate::path::exists());
+    temp.child(".cAGENTS/.output-cache").assert(cache.as_str());
+}
+
+#[test]
+fn test_dry_run_when_up_to_date() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    cagents()
+        .current_dir(temp.path())
+        .args(["build", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("nothing would change"))
+        .stdout(predicate::str::contains("@@").not());
+}
+
+#[test]
+fn test_dry_run_leaves_command_cache_alone() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+    temp.child(".cAGENTS/config.toml").write_str(r#"
+[paths]
+templatesDir = "templates"
+outputRoot = "."
+
+[defaults]
+engine = "builtin:simple"
+
+[variables.command]
+version = { command = "echo 1.2.3", ttl = "1h" }
+"#).unwrap();
+
+    cagents()
+        .current_dir(temp.path())
+        .args(["build", "--dry-run"])
+        .assert()
+        .success();
+
+    temp.child(".cAGENTS/.command-cache").assert(predicate::path::missing());
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{cagents, write_config, write_template};
+use predicates::prelude::*;
+
+fn setup_project(temp: &assert_fs::TempDir, layout: &str) {
+    write_config(temp, &format!(r#"
+[output]
+targets = ["agents-md", "claude-md"]
+
+[matrix]
+layout = "{}"
+
+[matrix.variables]
+role = ["frontend", "backend"]
+"#, layout));
+
+    write_template(temp, "root.md", r#"---
+name: root
+---
+Rules for {{role}}
+"#);
+}
+
+#[test]
+fn test_pinned_suffix_build_keeps_other_variants() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, "suffix");
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    cagents()
+        .current_dir(temp.path())
+        .args(["build", "--var", "role=backend"])
+        .assert()
+        .success();
+    temp.child("AGENTS.frontend.md").assert(predicate::str::contains("Rules for frontend"));
+    temp.child("CLAUDE.frontend.md").assert(predicate::path::exists());
+    temp.child("AGENTS.backend.md").assert(predicate::str::contains("Rules for backend"));
+
+    // Still tracked, so the next full build is up to date and nothing is stale
+    cagents()
+        .current_dir(temp.path())
+        .args(["build", "--check"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_pinned_directory_build_keeps_other_variants() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, "directory");
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    cagents()
+        .current_dir(temp.path())
+        .args(["build", "--var", "role=frontend"])
+        .assert()
+        .success();
+    temp.child("backend/AGENTS.md").assert(predicate::str::contains("Rules for backend"));
+    temp.child("backend/CLAUDE.md").assert(predicate::path::exists());
+}
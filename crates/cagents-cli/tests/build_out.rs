@@ -0,0 +1,124 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{cagents, write_config, write_template};
+use predicates::prelude::*;
+
+fn setup_project(temp: &assert_fs::TempDir) {
+    write_config(temp, r#"
+[output]
+targets = ["agents-md", "claude-md", "cursor-rules"]
+"#);
+
+    write_template(temp, "base.md", r#"---
+name: base
+---
+Use tabs
+"#);
+    write_template(temp, "api.md", r#"---
+name: api
+globs: ["api/**"]
+outputIn: parent
+---
+API rules
+"#);
+    temp.child("api/main.go").write_str("").unwrap();
+}
+
+#[test]
+fn test_out_stages_full_tree() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+    let staging = assert_fs::TempDir::new().unwrap();
+
+    cagents()
+        .current_dir(temp.path())
+        .args(["build", "--out"])
+        .arg(staging.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Staged in"));
+
+    staging.child("AGENTS.md").assert(predicate::str::contains("Use tabs"));
+    staging.child("CLAUDE.md").assert(predicate::str::contains("Use tabs"));
+    staging.child("api/AGENTS.md").assert(predicate::str::contains("API rules"));
+    staging.child("api/CLAUDE.md").assert(predicate::str::contains("API rules"));
+    staging.child(".cursor/rules/api.mdc").assert(predicate::path::exists());
+
+    // The working tree and output cache are untouched
+    temp.child("AGENTS.md").assert(predicate::path::missing());
+    temp.child("api/AGENTS.md").assert(predicate::path::missing());
+    temp.child(".cursor").assert(predicate::path::missing());
+    temp.child(".cAGENTS/.output-cache").assert(predicate::path::missing());
+}
+
+#[test]
+fn test_out_is_relative_to_invocation_dir_and_skips_cleanup() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+    let cache = std::fs::read_to_string(temp.child(".cAGENTS/.output-cache").path()).unwrap();
+
+    // api/ outputs would be cleaned up by a normal build
+    std::fs::remove_file(temp.child(".cAGENTS/templates/api.md").path()).unwrap();
+    cagents()
+        .current_dir(temp.child("api").path())
+        .args(["build", "--out", "staged"])
+        .assert()
+        .success();
+
+    temp.child("api/staged/AGENTS.md").assert(predicate::str::contains("Use tabs"));
+    temp.child("api/staged/api/AGENTS.md").assert(predicate::path::missing());
+    temp.child("api/AGENTS.md").assert(predicate::str::contains("API rules"));
+    temp.child(".cAGENTS/.output-cache").assert(cache.as_str());
+}
+
+/// Every file under `dir` with its content
+fn snapshot(dir: &std::path::Path) -> std::collections::BTreeMap<std::path::PathBuf, Vec<u8>> {
+    let mut files = std::collections::BTreeMap::new();
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            files.extend(snapshot(&path));
+        } else {
+            files.insert(path.clone(), std::fs::read(&path).unwrap());
+        }
+    }
+    files
+}
+
+#[test]
+fn test_out_leaves_project_tree_identical() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    // A cached command and a required variable would normally write the command
+    // cache and prompt into config.local.toml
+    temp.child(".cAGENTS/config.local.toml").write_str(r#"
+[variables.command]
+version = { command = "echo 1.2.3", ttl = "1h" }
+
+[variables.schema.team]
+required = true
+"#).unwrap();
+    let before = snapshot(temp.path());
+    let staging = assert_fs::TempDir::new().unwrap();
+
+    cagents()
+        .current_dir(temp.path())
+        .args(["build", "--var", "team=web", "--out"])
+        .arg(staging.path())
+        .assert()
+        .success();
+    assert_eq!(snapshot(temp.path()), before);
+
+    cagents()
+        .current_dir(temp.path())
+        .args(["build", "--out"])
+        .arg(staging.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("team"));
+    assert_eq!(snapshot(temp.path()), before);
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{cagents, write_config, write_template};
+use predicates::prelude::*;
+
+fn setup_workspace(temp: &assert_fs::TempDir) {
+    write_config(temp, r#"
+[output]
+targets = ["agents-md", "claude-md"]
+"#);
+
+    write_template(temp, "root.md", r#"---
+name: root
+---
+Project {{cagents.projectName}} uses {{cagents.languages}} ({{cagents.packageManagers}}).
+Members: {{cagents.workspaceMembers}}
+Writing {{cagents.target}} in {{cagents.outputDir}}
+"#);
+    write_template(temp, "core.md", r#"---
+name: core
+globs: ["crates/core/**/*.rs"]
+---
+Crate rules for {{cagents.outputDir}}
+"#);
+
+    temp.child("Cargo.toml").write_str(r#"
+[workspace]
+members = ["crates/*"]
+
+[package]
+name = "facts-demo"
+"#).unwrap();
+    temp.child("crates/core/src/lib.rs").write_str("").unwrap();
+    temp.child("crates/cli/src/main.rs").write_str("").unwrap();
+}
+
+#[test]
+fn test_build_renders_builtin_facts_per_output() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_workspace(&temp);
+
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    temp.child("AGENTS.md")
+        .assert(predicate::str::contains("uses rust (cargo)"))
+        .assert(predicate::str::contains("Members: crates/cli, crates/core"))
+        .assert(predicate::str::contains("Writing agents-md in ."));
+    temp.child("CLAUDE.md").assert(predicate::str::contains("Writing claude-md in ."));
+    temp.child("crates/core/AGENTS.md")
+        .assert(predicate::str::contains("Crate rules for crates/core"));
+}
+
+#[test]
+fn test_vars_lists_builtin_namespace() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_workspace(&temp);
+
+    let output = cagents()
+        .current_dir(temp.path())
+        .args(["vars", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["cagents"]["kind"], "builtin");
+    assert_eq!(json["cagents"]["value"]["languages"], serde_json::json!(["rust"]));
+}
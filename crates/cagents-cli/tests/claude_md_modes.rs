@@ -0,0 +1,101 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{cagents, write_config, write_template};
+use predicates::prelude::*;
+
+fn setup_project(temp: &assert_fs::TempDir, mode: &str) {
+    write_config(temp, &format!(r#"
+[output]
+targets = ["agents-md", "claude-md"]
+
+[output.claude-md]
+mode = "{}"
+"#, mode));
+
+    write_template(temp, "base.md", r#"---
+name: base
+---
+Shared rules
+"#);
+    write_template(temp, "claude-only.md", r#"---
+name: claude-only
+when:
+  target: ["claude-md"]
+---
+Only for Claude
+"#);
+    write_template(temp, "api.md", r#"---
+name: api
+globs: ["api/**"]
+outputIn: parent
+---
+API rules
+"#);
+    temp.child("api/main.go").write_str("").unwrap();
+}
+
+#[test]
+fn test_import_mode_references_agents_md() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, "import");
+
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    temp.child("CLAUDE.md")
+        .assert(predicate::str::contains("@AGENTS.md\n\nOnly for Claude"))
+        .assert(predicate::str::contains("Shared rules").not());
+    temp.child("AGENTS.md")
+        .assert(predicate::str::contains("Shared rules"))
+        .assert(predicate::str::contains("Only for Claude").not());
+    temp.child("api/CLAUDE.md")
+        .assert(predicate::str::ends_with("@AGENTS.md\n"))
+        .assert(predicate::str::contains("API rules").not());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_symlink_mode_links_where_rules_match() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, "symlink");
+
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    let link = std::fs::read_link(temp.child("api/CLAUDE.md").path()).unwrap();
+    assert_eq!(link, std::path::PathBuf::from("AGENTS.md"));
+    temp.child("api/CLAUDE.md").assert(predicate::str::contains("API rules"));
+
+    // The root has a Claude-only rule, so it gets an importing file instead
+    assert!(!std::fs::symlink_metadata(temp.child("CLAUDE.md").path()).unwrap().file_type().is_symlink());
+    temp.child("CLAUDE.md").assert(predicate::str::contains("@AGENTS.md\n\nOnly for Claude"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_switching_from_symlink_mode_keeps_agents_md() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, "symlink");
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    // Writing the full CLAUDE.md must replace the link, not write through it
+    setup_project(&temp, "full");
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    assert!(!std::fs::symlink_metadata(temp.child("api/CLAUDE.md").path()).unwrap().file_type().is_symlink());
+    temp.child("api/AGENTS.md").assert(predicate::str::contains("API rules"));
+    temp.child("api/CLAUDE.md").assert(predicate::str::contains("API rules"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_dangling_symlink_is_cleaned_up() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, "symlink");
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    std::fs::remove_file(temp.child(".cAGENTS/templates/api.md").path()).unwrap();
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    assert!(std::fs::symlink_metadata(temp.child("api/CLAUDE.md").path()).is_err());
+    temp.child("api/AGENTS.md").assert(predicate::path::missing());
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{cagents, write_config, write_template};
+use predicates::prelude::*;
+
+fn setup_project(temp: &assert_fs::TempDir, output_extra: &str) {
+    write_config(temp, &format!(r#"
+[output]
+targets = ["agents-md", "claude-md"]
+{}
+"#, output_extra));
+
+    write_template(temp, "base.md", r#"---
+name: base
+---
+Be concise.
+"#);
+    write_template(temp, "release.md", r#"---
+name: release-process
+description: How to cut a release. Use when publishing a new version.
+skill: true
+---
+Run the release checklist for {{cagents.target}}.
+"#);
+}
+
+#[test]
+fn test_skill_rules_become_claude_skills() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, "");
+
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    temp.child(".claude/skills/release-process/SKILL.md")
+        .assert(predicate::str::starts_with(
+            "---\nname: release-process\ndescription: \"How to cut a release. Use when publishing a new version.\"\n---\n",
+        ))
+        .assert(predicate::str::contains("Run the release checklist for claude-md."));
+    temp.child("CLAUDE.md")
+        .assert(predicate::str::contains("Be concise."))
+        .assert(predicate::str::contains("release checklist").not())
+        .assert(predicate::str::contains("## Skills").not());
+    // Other targets still merge the rule
+    temp.child("AGENTS.md").assert(predicate::str::contains("Run the release checklist for agents-md."));
+}
+
+#[test]
+fn test_claude_md_lists_skills() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, "listSkills = true");
+
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    temp.child("CLAUDE.md").assert(predicate::str::contains(
+        "## Skills\n\nSkills in `.claude/skills/`:\n\n- `release-process`: How to cut a release. Use when publishing a new version.\n",
+    ));
+}
+
+#[test]
+fn test_skill_requires_description() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, "");
+    temp.child(".cAGENTS/templates/bare.md").write_str("---\nname: bare\nskill: true\n---\nBody\n").unwrap();
+
+    cagents()
+        .current_dir(temp.path())
+        .arg("build")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("skill: true must have a description"));
+}
+
+#[test]
+fn test_removed_skill_is_cleaned_up() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, "");
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+    temp.child(".claude/skills/release-process/SKILL.md").assert(predicate::path::exists());
+
+    std::fs::remove_file(temp.child(".cAGENTS/templates/release.md").path()).unwrap();
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    temp.child(".claude/skills/release-process/SKILL.md").assert(predicate::path::missing());
+    temp.child("CLAUDE.md").assert(predicate::path::exists());
+}
+
+#[test]
+fn test_skills_with_the_same_name_are_rejected() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, "");
+    // Skill names are lowercased, so this is the same skill directory
+    temp.child(".cAGENTS/templates/release-docs.md").write_str(r#"---
+name: Release-Process
+description: Release notes.
+skill: true
+---
+Write release notes.
+"#).unwrap();
+
+    cagents()
+        .current_dir(temp.path())
+        .arg("build")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("both write .claude/skills/release-process/SKILL.md"));
+}
@@ -0,0 +1,83 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{cagents, write_config};
+use predicates::prelude::*;
+
+fn setup_project(temp: &assert_fs::TempDir) {
+    // Each run appends a line, so the value counts how often the command ran
+    write_config(temp, r#"
+[variables.command]
+runs = { command = "echo x >> runs.log && wc -l < runs.log | tr -d ' '", ttl = "1h", cacheKey = "lock.txt" }
+uncached = "echo fresh"
+"#);
+    temp.child(".cAGENTS/templates").create_dir_all().unwrap();
+    temp.child("lock.txt").write_str("v1").unwrap();
+}
+
+fn vars_json(temp: &assert_fs::TempDir) -> serde_json::Value {
+    let output = cagents()
+        .current_dir(temp.path())
+        .args(["vars", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    serde_json::from_slice(&output.stdout).unwrap()
+}
+
+#[test]
+fn test_cached_command_is_reused() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+
+    let first = vars_json(&temp);
+    assert_eq!(first["runs"]["value"], "1");
+    assert!(first["runs"].get("cached").is_none());
+    temp.child(".cAGENTS/.command-cache").assert(predicate::path::exists());
+
+    let second = vars_json(&temp);
+    assert_eq!(second["runs"]["value"], "1");
+    assert_eq!(second["runs"]["cached"], true);
+    assert!(second["uncached"].get("cached").is_none());
+
+    cagents()
+        .current_dir(temp.path())
+        .arg("vars")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("cached"));
+}
+
+#[test]
+fn test_cache_key_change_reruns_command() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+
+    assert_eq!(vars_json(&temp)["runs"]["value"], "1");
+    temp.child("lock.txt").write_str("v2").unwrap();
+
+    let json = vars_json(&temp);
+    assert_eq!(json["runs"]["value"], "2");
+    assert!(json["runs"].get("cached").is_none());
+}
+
+#[test]
+fn test_invalid_ttl_is_rejected() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+    temp.child(".cAGENTS/config.toml").write_str(r#"
+[paths]
+templatesDir = "templates"
+outputRoot = "."
+
+[variables.command]
+slow = { command = "echo hi", ttl = "5 weeks" }
+"#).unwrap();
+
+    cagents()
+        .current_dir(temp.path())
+        .args(["vars"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid ttl for 'slow'"));
+}
@@ -0,0 +1,34 @@
+// helpers shared by the integration tests (each test binary uses a different subset)
+#![allow(dead_code)]
+
+use assert_cmd::Command;
+use assert_fs::prelude::*;
+
+/// `[paths]` for `.cAGENTS/templates` and outputs at the root, rendered with the builtin engine
+pub const BASE_CONFIG: &str = r#"
+[paths]
+templatesDir = "templates"
+outputRoot = "."
+
+[defaults]
+engine = "builtin:simple"
+"#;
+
+/// The `cagents` binary with telemetry disabled
+pub fn cagents() -> Command {
+    let mut cmd = Command::cargo_bin("cagents").unwrap();
+    cmd.env("CAGENTS_TELEMETRY_DISABLED", "1");
+    cmd
+}
+
+/// Write `.cAGENTS/config.toml`: [`BASE_CONFIG`] followed by `extra`
+pub fn write_config(temp: &assert_fs::TempDir, extra: &str) {
+    temp.child(".cAGENTS/config.toml")
+        .write_str(&format!("{}{}", BASE_CONFIG, extra))
+        .unwrap();
+}
+
+/// Write `.cAGENTS/templates/<file>`
+pub fn write_template(temp: &assert_fs::TempDir, file: &str, content: &str) {
+    temp.child(".cAGENTS/templates").child(file).write_str(content).unwrap();
+}
@@ -0,0 +1,181 @@
+use assert_cmd::Command;
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+
+fn setup_project(temp: &assert_fs::TempDir) {
+    let cagents_dir = temp.child(".cAGENTS");
+    cagents_dir.create_dir_all().unwrap();
+    cagents_dir.child("config.toml").write_str(r#"
+[paths]
+templatesDir = "templates"
+outputRoot = "."
+"#).unwrap();
+}
+
+fn cagents(temp: &assert_fs::TempDir) -> Command {
+    let mut cmd = Command::cargo_bin("cagents").unwrap();
+    cmd.current_dir(temp.path())
+        .env("HOME", temp.child("home").path())
+        .env("CAGENTS_TELEMETRY_DISABLED", "1");
+    cmd
+}
+
+#[test]
+fn test_config_set_get_with_origin() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+
+    cagents(&temp)
+        .args(["config", "set", "paths.outputRoot", "dist", "--layer", "local"])
+        .assert()
+        .success();
+
+    cagents(&temp)
+        .args(["config", "get", "paths.outputRoot"])
+        .assert()
+        .success()
+        .stdout("dist\n");
+
+    cagents(&temp)
+        .args(["config", "get", "paths.outputRoot", "--origin"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("local:"))
+        .stdout(predicate::str::contains("config.local.toml\tdist"));
+
+    cagents(&temp)
+        .args(["config", "get", "paths.outputRoot", "--layer", "project"])
+        .assert()
+        .success()
+        .stdout(".\n");
+
+    cagents(&temp)
+        .args(["config", "show", "--origin"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("paths.templatesDir = templates"))
+        .stdout(predicate::str::contains("paths.outputRoot = dist"));
+
+    cagents(&temp)
+        .args(["config", "unset", "paths.outputRoot", "--layer", "local"])
+        .assert()
+        .success();
+
+    cagents(&temp)
+        .args(["config", "get", "paths.outputRoot"])
+        .assert()
+        .success()
+        .stdout(".\n");
+}
+
+#[test]
+fn test_config_set_and_unset_default_to_project_layer() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+
+    cagents(&temp)
+        .args(["config", "set", "variables.static.owner", "\"Sam\""])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(".cAGENTS/config.toml"));
+    temp.child(".cAGENTS/config.toml").assert(predicate::str::contains("owner = \"Sam\""));
+    temp.child(".cAGENTS/config.local.toml").assert(predicate::path::missing());
+
+    cagents(&temp)
+        .args(["config", "unset", "variables.static.owner"])
+        .assert()
+        .success();
+    temp.child(".cAGENTS/config.toml").assert(predicate::str::contains("owner").not());
+}
+
+#[test]
+fn test_config_get_missing_key_fails() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+
+    cagents(&temp)
+        .args(["config", "get", "output.targets"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("not set"));
+}
+
+#[test]
+fn test_telemetry_toggle_edits_user_config() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+    let user_config = temp.child("home/.cagents/config.toml");
+    user_config.write_str("# my settings\n[telemetry]\nenabled = true # opt-out model\n").unwrap();
+
+    cagents(&temp)
+        .args(["telemetry", "disable"])
+        .assert()
+        .success();
+
+    let content = std::fs::read_to_string(user_config.path()).unwrap();
+    assert!(content.contains("# my settings"));
+    assert!(content.contains("enabled = false # opt-out model"));
+
+    cagents(&temp)
+        .args(["config", "get", "telemetry.enabled", "--origin"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("user:"))
+        .stdout(predicate::str::ends_with("\tfalse\n"));
+}
+
+#[test]
+fn test_profile_flag_selects_overlay() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+    temp.child(".cAGENTS/config.local.toml").write_str(r#"
+[profiles.ci.paths]
+outputRoot = "dist"
+"#).unwrap();
+
+    cagents(&temp)
+        .args(["config", "get", "paths.outputRoot"])
+        .assert()
+        .success()
+        .stdout(".\n");
+
+    cagents(&temp)
+        .args(["--profile", "ci", "config", "get", "paths.outputRoot", "--origin"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("local:"))
+        .stdout(predicate::str::ends_with("\tdist\n"));
+
+    cagents(&temp)
+        .args(["config", "get", "paths.outputRoot"])
+        .env("CAGENTS_PROFILE", "ci")
+        .assert()
+        .success()
+        .stdout("dist\n");
+
+    cagents(&temp)
+        .args(["config", "get", "paths.outputRoot", "--profile", "nope"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Profile 'nope' not found"));
+}
+
+#[test]
+fn test_env_override_origin() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+
+    cagents(&temp)
+        .args(["config", "get", "paths.outputRoot", "--origin"])
+        .env("CAGENTS_PATHS__OUTPUT_ROOT", "dist")
+        .assert()
+        .success()
+        .stdout("env:CAGENTS_PATHS__OUTPUT_ROOT\tdist\n");
+
+    cagents(&temp)
+        .args(["config", "show", "--layer", "env"])
+        .env("CAGENTS_OUTPUT__TARGETS", "agents-md,claude-md")
+        .assert()
+        .success()
+        .stdout("output.targets = [\"agents-md\", \"claude-md\"]\n");
+}
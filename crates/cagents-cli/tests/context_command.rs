@@ -207,3 +207,108 @@ name: "test"
         .stdout(predicate::str::contains("team"))
         .stdout(predicate::str::contains("backend"));
 }
+
+#[test]
+fn test_context_variable_provenance() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let cagents_dir = temp.child(".cAGENTS");
+    cagents_dir.create_dir_all().unwrap();
+
+    cagents_dir.child("config.toml").write_str(r#"
+[paths]
+templatesDir = "templates"
+outputRoot = "."
+
+[defaults]
+engine = "builtin:simple"
+
+[variables.static]
+owner = "Jordan"
+
+[variables.command]
+greeting = "echo hello"
+"#).unwrap();
+
+    cagents_dir.child("config.local.toml").write_str(r#"
+[variables.static]
+owner = "Sam"
+"#).unwrap();
+
+    let templates_dir = cagents_dir.child("templates");
+    templates_dir.create_dir_all().unwrap();
+    templates_dir.child("agents-root.md").write_str(r#"---
+name: "root"
+vars:
+  tone: "friendly"
+---
+# Rules for {{owner}}
+"#).unwrap();
+
+    temp.child("test.txt").write_str("test").unwrap();
+
+    let mut cmd = Command::cargo_bin("cagents").unwrap();
+    let output = cmd.current_dir(temp.path())
+        .arg("context")
+        .arg("test.txt")
+        .arg("--json")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let vars = &json["variables"];
+
+    assert_eq!(vars["owner"]["value"], "Sam");
+    assert_eq!(vars["owner"]["source"], "config.static");
+    assert_eq!(vars["owner"]["layer"], "local");
+    assert!(vars["owner"]["file"].as_str().unwrap().ends_with("config.local.toml"));
+
+    assert_eq!(vars["greeting"]["value"], "hello");
+    assert_eq!(vars["greeting"]["kind"], "command");
+    assert_eq!(vars["greeting"]["layer"], "project");
+    assert!(vars["greeting"]["duration_ms"].is_u64());
+
+    assert_eq!(vars["tone"]["source"], "frontmatter");
+    assert_eq!(vars["tone"]["rule"], "root");
+}
+
+#[test]
+fn test_vars_command() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let cagents_dir = temp.child(".cAGENTS");
+    cagents_dir.create_dir_all().unwrap();
+    cagents_dir.child("config.toml").write_str(r#"
+[paths]
+templatesDir = "templates"
+outputRoot = "."
+
+[variables.static]
+owner = "Jordan"
+role = "frontend"
+"#).unwrap();
+
+    let mut cmd = Command::cargo_bin("cagents").unwrap();
+    cmd.current_dir(temp.path())
+        .arg("vars")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("owner"))
+        .stdout(predicate::str::contains("static (project: "));
+
+    let mut cmd = Command::cargo_bin("cagents").unwrap();
+    let output = cmd.current_dir(temp.path())
+        .arg("vars")
+        .arg("--var")
+        .arg("role=backend")
+        .arg("--json")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["owner"]["source"], "config.static");
+    assert_eq!(json["role"]["value"], "backend");
+    assert_eq!(json["role"]["source"], "cli");
+}
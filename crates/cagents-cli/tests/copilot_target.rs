@@ -0,0 +1,67 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{cagents, write_config, write_template};
+use predicates::prelude::*;
+
+fn setup_project(temp: &assert_fs::TempDir) {
+    write_config(temp, r#"
+[output]
+targets = ["agents-md", "copilot"]
+"#);
+
+    write_template(temp, "base.md", r#"---
+name: base
+description: Project basics
+---
+Be concise.
+"#);
+    write_template(temp, "api.md", r#"---
+name: api
+description: API conventions
+globs: ["api/**/*.go", "cmd/**"]
+outputIn: parent
+---
+Validate requests in {{cagents.outputDir}}.
+"#);
+
+    temp.child("api/v1/main.go").write_str("").unwrap();
+}
+
+#[test]
+fn test_build_writes_copilot_instructions() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    temp.child(".github/copilot-instructions.md")
+        .assert(predicate::str::contains("Be concise."))
+        .assert(predicate::str::contains("Validate requests").not());
+    temp.child(".github/instructions/api.instructions.md")
+        .assert(predicate::str::starts_with(
+            "---\napplyTo: \"api/**/*.go,cmd/**\"\ndescription: \"API conventions\"\n---\n",
+        ))
+        .assert(predicate::str::contains("Validate requests in api/v1."));
+    temp.child("api/v1/.github").assert(predicate::path::missing());
+    temp.child("api/v1/AGENTS.md").assert(predicate::str::contains("Validate requests"));
+}
+
+#[test]
+fn test_removed_copilot_files_are_cleaned_up() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+    temp.child(".github/instructions/api.instructions.md").assert(predicate::path::exists());
+
+    std::fs::remove_file(temp.child(".cAGENTS/templates/api.md").path()).unwrap();
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+    temp.child(".github/instructions/api.instructions.md").assert(predicate::path::missing());
+    temp.child(".github/copilot-instructions.md").assert(predicate::path::exists());
+
+    let config = temp.child(".cAGENTS/config.toml");
+    let content = std::fs::read_to_string(config.path()).unwrap();
+    config.write_str(&content.replace(r#", "copilot""#, "")).unwrap();
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+    temp.child(".github/copilot-instructions.md").assert(predicate::path::missing());
+}
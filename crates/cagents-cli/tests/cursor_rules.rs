@@ -0,0 +1,117 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{cagents, write_template};
+use predicates::prelude::*;
+
+fn setup_project(temp: &assert_fs::TempDir, paths_extra: &str) {
+    // `paths_extra` goes into `[paths]`, so this config doesn't build on `BASE_CONFIG`
+    temp.child(".cAGENTS/config.toml").write_str(&format!(r#"
+[paths]
+templatesDir = "templates"
+outputRoot = "."
+{}
+
+[defaults]
+engine = "builtin:simple"
+
+[output]
+targets = ["agents-md", "cursor-rules"]
+"#, paths_extra)).unwrap();
+
+    write_template(temp, "base.md", r#"---
+name: base
+description: Project basics
+---
+Be concise.
+"#);
+    write_template(temp, "ts.md", r#"---
+name: typescript
+description: TypeScript conventions
+globs: ["packages/*/src/**/*.ts"]
+outputIn: parent
+---
+Use strict mode in {{cagents.outputDir}}.
+"#);
+    write_template(temp, "agents-only.md", r#"---
+name: agents-only
+when:
+  target: ["agents-md"]
+---
+Only for AGENTS.md
+"#);
+
+    temp.child("packages/a/src/index.ts").write_str("").unwrap();
+    temp.child("packages/b/src/index.ts").write_str("").unwrap();
+}
+
+#[test]
+fn test_build_writes_one_mdc_per_rule() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, "");
+
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    temp.child(".cursor/rules/base.mdc").assert(predicate::str::starts_with(
+        "---\ndescription: \"Project basics\"\nglobs: \nalwaysApply: true\n---\n",
+    ));
+    // A rule used by two directories is written once, rendered for the first
+    temp.child(".cursor/rules/typescript.mdc")
+        .assert(predicate::str::starts_with(
+            "---\ndescription: \"TypeScript conventions\"\nglobs: packages/*/src/**/*.ts\nalwaysApply: false\n---\n",
+        ))
+        .assert(predicate::str::contains("Use strict mode in packages/a/src."));
+    temp.child(".cursor/rules/agents-only.mdc").assert(predicate::path::missing());
+    temp.child("AGENTS.md").assert(predicate::str::contains("Only for AGENTS.md"));
+}
+
+#[test]
+fn test_cursor_rules_dir_is_configurable() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, "cursorRulesDir = \"tools/cursor\"");
+
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    temp.child("tools/cursor/base.mdc").assert(predicate::path::exists());
+    temp.child(".cursor").assert(predicate::path::missing());
+}
+
+#[test]
+fn test_removed_rule_file_is_cleaned_up() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, "");
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+    temp.child(".cursor/rules/typescript.mdc").assert(predicate::path::exists());
+
+    std::fs::remove_file(temp.child(".cAGENTS/templates/ts.md").path()).unwrap();
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    temp.child(".cursor/rules/typescript.mdc").assert(predicate::path::missing());
+    temp.child(".cursor/rules/base.mdc").assert(predicate::path::exists());
+}
+
+#[test]
+fn test_rules_with_the_same_file_name_are_rejected() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, "");
+    temp.child(".cAGENTS/templates/ts-strict.md").write_str(r#"---
+name: "typescript"
+description: More TypeScript conventions
+---
+No any.
+"#).unwrap();
+
+    cagents()
+        .current_dir(temp.path())
+        .arg("build")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("both write .cursor/rules/typescript.mdc"));
+
+    cagents()
+        .current_dir(temp.path())
+        .arg("lint")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("writes .cursor/rules/typescript.mdc for cursor-rules"));
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{cagents, write_config, write_template};
+use predicates::prelude::*;
+
+const CONFIG: &str = r##"
+[variables.static]
+project = "acme"
+
+[output]
+targets = ["agents-md", "guide", "handbook"]
+
+[output.custom.guide]
+filename = "AI_GUIDE.md"
+header = "# {{project}} AI guide\n\n"
+rootOnly = true
+
+[output.custom.handbook]
+filename = "docs/agents/README.md"
+wrapper = "<rules>\n{{content}}\n</rules>\n"
+footer = "Generated for {{cagents.outputDir}}\n"
+"##;
+
+fn setup_project(temp: &assert_fs::TempDir, config: &str) {
+    write_config(temp, config);
+
+    write_template(temp, "root.md", r#"---
+name: root
+---
+Shared rules
+"#);
+    write_template(temp, "guide-only.md", r#"---
+name: guide-only
+when:
+  target: ["guide"]
+---
+Only in the guide
+"#);
+    write_template(temp, "api.md", r#"---
+name: api
+globs: ["api/**"]
+outputIn: parent
+---
+API rules
+"#);
+    temp.child("api/main.go").write_str("").unwrap();
+}
+
+#[test]
+fn test_build_writes_custom_targets() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, CONFIG);
+
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    temp.child("AI_GUIDE.md")
+        .assert(predicate::str::starts_with("# acme AI guide\n\n"))
+        .assert(predicate::str::contains("Shared rules"))
+        .assert(predicate::str::contains("Only in the guide"));
+    temp.child("AGENTS.md").assert(predicate::str::contains("Only in the guide").not());
+    temp.child("docs/agents/README.md")
+        .assert(predicate::str::starts_with("<rules>\nShared rules"))
+        .assert(predicate::str::ends_with("</rules>\nGenerated for .\n"));
+
+    // Root-only targets skip nested output directories
+    temp.child("api/AGENTS.md").assert(predicate::path::exists());
+    temp.child("api/docs/agents/README.md").assert(predicate::str::contains("Generated for api"));
+    temp.child("api/AI_GUIDE.md").assert(predicate::path::missing());
+}
+
+#[test]
+fn test_removed_custom_target_is_cleaned_up() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, CONFIG);
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+    temp.child("AI_GUIDE.md").assert(predicate::path::exists());
+
+    let config = CONFIG.replace(r#"targets = ["agents-md", "guide", "handbook"]"#, r#"targets = ["agents-md"]"#);
+    write_config(&temp, &config);
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    temp.child("AI_GUIDE.md").assert(predicate::path::missing());
+    temp.child("docs/agents/README.md").assert(predicate::path::missing());
+}
+
+#[test]
+fn test_lint_accepts_custom_targets() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, CONFIG);
+
+    cagents()
+        .current_dir(temp.path())
+        .arg("lint")
+        .assert()
+        .stdout(predicate::str::contains("Unknown output target").not())
+        .stdout(predicate::str::contains("Invalid when.target").not());
+}
+
+#[test]
+fn test_custom_target_cannot_shadow_builtin() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, &format!("{}\n[output.custom.claude-md]\nfilename = \"CLAUDE.md\"\n", CONFIG));
+
+    cagents()
+        .current_dir(temp.path())
+        .arg("build")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("conflicts with the built-in 'claude-md' target"));
+}
@@ -0,0 +1,128 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{cagents, write_config, write_template};
+use predicates::prelude::*;
+
+fn setup_project(temp: &assert_fs::TempDir) {
+    write_config(temp, r#"
+[variables.files]
+package = "package.json"
+build_script = { path = "package.json", select = "$.scripts.build" }
+owners = { path = "services.yaml", select = "services[*].owner" }
+"#);
+
+    write_template(temp, "root.md", r#"---
+name: root
+---
+Build with `{{build_script}}`
+"#);
+
+    temp.child("package.json")
+        .write_str(r#"{"name": "web", "scripts": {"build": "tsc -b", "test": "vitest"}}"#)
+        .unwrap();
+    temp.child("services.yaml").write_str(r#"
+services:
+  - name: api
+    owner: platform
+  - name: web
+    owner: frontend
+"#).unwrap();
+}
+
+#[test]
+fn test_data_files_feed_template_data() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+
+    let output = cagents()
+        .current_dir(temp.path())
+        .args(["vars", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["package"]["value"]["name"], "web");
+    assert_eq!(json["package"]["kind"], "file");
+    assert_eq!(json["build_script"]["value"], "tsc -b");
+    assert_eq!(json["owners"]["value"], serde_json::json!(["platform", "frontend"]));
+}
+
+#[test]
+fn test_data_file_change_marks_outputs_stale() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+    temp.child("AGENTS.md").assert(predicate::str::contains("Build with `tsc -b`"));
+
+    cagents()
+        .current_dir(temp.path())
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 up to date"));
+
+    temp.child("package.json")
+        .write_str(r#"{"name": "web", "scripts": {"build": "vite build"}}"#)
+        .unwrap();
+
+    cagents()
+        .current_dir(temp.path())
+        .arg("status")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Changed since last build: package.json"));
+
+    // --check renders from the current data files, so the change shows up as a stale output
+    cagents()
+        .current_dir(temp.path())
+        .args(["build", "--check"])
+        .assert()
+        .failure()
+        .stdout(predicate::str::is_match(r"stale.*AGENTS.md").unwrap());
+
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+    temp.child("AGENTS.md").assert(predicate::str::contains("Build with `vite build`"));
+}
+
+#[test]
+fn test_invalid_data_file_entry_is_a_config_error() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+    temp.child(".cAGENTS/config.local.toml").write_str(r#"
+[variables.files]
+broken = { file = "package.json" }
+"#).unwrap();
+
+    cagents()
+        .current_dir(temp.path())
+        .arg("build")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown key 'file' in [variables.files] entry 'broken'"));
+}
+
+#[test]
+fn test_unreadable_data_file_fails_the_build() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+    std::fs::remove_file(temp.child("services.yaml").path()).unwrap();
+
+    cagents()
+        .current_dir(temp.path())
+        .arg("build")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to load data file services.yaml for variable 'owners'"));
+    temp.child("AGENTS.md").assert(predicate::path::missing());
+
+    temp.child("services.yaml").write_str("services: [").unwrap();
+    cagents()
+        .current_dir(temp.path())
+        .arg("build")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("for variable 'owners'"));
+}
@@ -0,0 +1,118 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{cagents, write_config, write_template};
+use predicates::prelude::*;
+
+fn setup_monorepo(temp: &assert_fs::TempDir) {
+    write_config(temp, r#"
+[variables.static]
+test_runner = "test"
+"#);
+
+    write_template(temp, "packages.md", r#"---
+name: packages
+globs: ["packages/*/src/**/*.ts"]
+outputIn: parent
+---
+Run `pnpm --filter {{cagents.package.name}} {{test_runner}}` ({{cagents.outputDir}})
+"#);
+    write_template(temp, "web-only.md", r#"---
+name: web-only
+globs: ["packages/*/src/**/*.ts"]
+outputIn: parent
+when:
+  frontend: "true"
+---
+Check the browser console.
+"#);
+
+    temp.child("packages/api/package.json").write_str(r#"{"name": "api"}"#).unwrap();
+    temp.child("packages/api/src/index.ts").write_str("").unwrap();
+    temp.child("packages/web/package.json").write_str(r#"{"name": "@acme/web"}"#).unwrap();
+    temp.child("packages/web/src/index.ts").write_str("").unwrap();
+
+    // Central per-directory vars, then a local file next to the code
+    temp.child(".cAGENTS/vars/packages/api.toml").write_str("test_runner = \"test:integration\"\n").unwrap();
+    temp.child("packages/web/agents.vars.toml").write_str("frontend = \"true\"\n").unwrap();
+}
+
+#[test]
+fn test_each_output_directory_gets_its_own_data() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_monorepo(&temp);
+
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    temp.child("packages/api/src/AGENTS.md")
+        .assert(predicate::str::contains("Run `pnpm --filter api test:integration` (packages/api/src)"))
+        .assert(predicate::str::contains("browser console").not());
+    temp.child("packages/web/src/AGENTS.md")
+        .assert(predicate::str::contains("Run `pnpm --filter @acme/web test` (packages/web/src)"))
+        .assert(predicate::str::contains("Check the browser console."));
+}
+
+#[test]
+fn test_cli_var_overrides_directory_vars() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_monorepo(&temp);
+
+    cagents()
+        .current_dir(temp.path())
+        .args(["build", "--var", "test_runner=check"])
+        .assert()
+        .success();
+
+    temp.child("packages/api/src/AGENTS.md")
+        .assert(predicate::str::contains("Run `pnpm --filter api check`"));
+}
+
+#[test]
+fn test_context_reports_directory_variable_source() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_monorepo(&temp);
+
+    let output = cagents()
+        .current_dir(temp.path())
+        .args(["context", "packages/api/src/index.ts", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let runner = &json["variables"]["test_runner"];
+    assert_eq!(runner["value"], "test:integration");
+    assert_eq!(runner["kind"], "directory");
+    assert_eq!(runner["file"], ".cAGENTS/vars/packages/api.toml");
+}
+
+#[test]
+fn test_render_matches_build_for_common_parent_rule() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_monorepo(&temp);
+    temp.child(".cAGENTS/templates/api.md").write_str(r#"---
+name: api
+globs: ["packages/api/**"]
+outputIn: common-parent
+---
+API rules for {{cagents.outputDir}} ({{test_runner}})
+"#).unwrap();
+    temp.child("packages/api/src/routes/users.json").write_str("{}").unwrap();
+
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+    let built = std::fs::read_to_string(temp.child("packages/api/AGENTS.md").path()).unwrap();
+
+    // The file is deeper than the output; the rule renders with the output's data
+    let rendered = cagents()
+        .current_dir(temp.path())
+        .args(["render", "packages/api/src/routes/users.json"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let rendered = String::from_utf8(rendered).unwrap();
+
+    assert_eq!(rendered.trim(), "API rules for packages/api (test:integration)");
+    assert!(built.contains(rendered.trim()), "{}", built);
+}
@@ -0,0 +1,101 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{cagents, write_config, write_template};
+use predicates::prelude::*;
+
+fn setup_services(temp: &assert_fs::TempDir) {
+    write_config(temp, "");
+
+    write_template(temp, "service.md", r#"---
+name: "service-{{service.name}}"
+globs: ["services/{{service.name}}/**"]
+foreach:
+  file: services.yaml
+  select: "$.services"
+  as: service
+---
+# {{service.name}}
+Owned by {{service.owner}}.
+"#);
+
+    temp.child("services.yaml").write_str(r#"
+services:
+  - name: api
+    owner: platform
+  - name: web
+    owner: frontend
+"#).unwrap();
+    temp.child("services/api/main.go").write_str("").unwrap();
+    temp.child("services/web/index.ts").write_str("").unwrap();
+}
+
+#[test]
+fn test_foreach_writes_one_instance_per_item() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_services(&temp);
+
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    temp.child("services/api/AGENTS.md")
+        .assert(predicate::str::contains("# api\nOwned by platform."))
+        .assert(predicate::str::contains("frontend").not());
+    temp.child("services/web/AGENTS.md")
+        .assert(predicate::str::contains("# web\nOwned by frontend."));
+}
+
+#[test]
+fn test_context_shows_expanded_rule() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_services(&temp);
+
+    let output = cagents()
+        .current_dir(temp.path())
+        .args(["context", "services/web/index.ts", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let names: Vec<&str> = json["matched_rules"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["service-web"]);
+}
+
+#[test]
+fn test_preview_lists_instances() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_services(&temp);
+
+    cagents()
+        .current_dir(temp.path())
+        .arg("preview")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("service-api"))
+        .stdout(predicate::str::contains("service-web"));
+}
+
+#[test]
+fn test_foreach_over_non_list_fails() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_services(&temp);
+    temp.child(".cAGENTS/templates/service.md").write_str(r#"---
+name: broken
+foreach:
+  file: services.yaml
+---
+body
+"#).unwrap();
+
+    cagents()
+        .current_dir(temp.path())
+        .arg("build")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("foreach expects a list"));
+}
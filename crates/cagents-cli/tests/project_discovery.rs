@@ -0,0 +1,172 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{cagents, write_config, write_template};
+use predicates::prelude::*;
+
+fn setup_project(temp: &assert_fs::TempDir) {
+    write_config(temp, "");
+
+    write_template(temp, "rust.md", r#"---
+name: rust
+globs: ["src/**/*.rs"]
+---
+# Rust Rules
+"#);
+    write_template(temp, "root.md", r#"---
+name: root
+---
+# Root Rules
+"#);
+
+    temp.child("src/nested/lib.rs").write_str("fn main() {}").unwrap();
+}
+
+#[test]
+fn test_render_from_subdirectory() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+
+    cagents()
+        .current_dir(temp.child("src/nested").path())
+        .args(["render", "lib.rs"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("# Rust Rules"));
+}
+
+#[test]
+fn test_build_from_subdirectory_writes_at_root() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+
+    cagents()
+        .current_dir(temp.child("src/nested").path())
+        .arg("build")
+        .assert()
+        .success();
+
+    temp.child("AGENTS.md").assert(predicate::str::contains("# Root Rules"));
+    temp.child(".cAGENTS/.output-cache").assert(predicate::path::exists());
+    temp.child("src/nested/AGENTS.md").assert(predicate::path::missing());
+}
+
+#[test]
+fn test_dash_c_runs_in_directory() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+    let elsewhere = assert_fs::TempDir::new().unwrap();
+
+    cagents()
+        .current_dir(elsewhere.path())
+        .arg("-C")
+        .arg(temp.child("src").path())
+        .args(["context", "nested/lib.rs", "--json"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"file\": \"src/nested/lib.rs\""));
+}
+
+#[test]
+fn test_config_flag_selects_config_file() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+    temp.child("ci.toml").write_str(r#"
+[paths]
+templatesDir = "templates"
+outputRoot = "."
+
+[defaults]
+engine = "builtin:simple"
+
+[output]
+targets = ["claude-md"]
+"#).unwrap();
+
+    cagents()
+        .current_dir(temp.child("src").path())
+        .args(["--config", "../ci.toml", "build"])
+        .assert()
+        .success();
+
+    temp.child("CLAUDE.md").assert(predicate::path::exists());
+    temp.child("AGENTS.md").assert(predicate::path::missing());
+
+    cagents()
+        .current_dir(temp.child("src").path())
+        .args(["--config", "../ci.toml", "status"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ci.toml"));
+
+    cagents()
+        .current_dir(temp.path())
+        .args(["--config", "missing.toml", "build"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Config file not found"));
+}
+
+#[test]
+fn test_outside_project_reports_missing_config() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    cagents()
+        .current_dir(temp.path())
+        .arg("build")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Project config not found"));
+}
+
+#[test]
+fn test_root_build_includes_nested_projects() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp);
+
+    let package = temp.child("packages/app");
+    package.child(".cAGENTS/config.toml").write_str(r#"
+[paths]
+templatesDir = "templates"
+outputRoot = "."
+
+[defaults]
+engine = "builtin:simple"
+
+[variables.static]
+team = "app"
+"#).unwrap();
+    package.child(".cAGENTS/templates/app.md").write_str(r#"---
+name: app
+---
+# App Rules for {{team}}
+"#).unwrap();
+    package.child("index.ts").write_str("export {}").unwrap();
+
+    cagents()
+        .current_dir(temp.path())
+        .args(["render", "packages/app/index.ts"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("# Root Rules"))
+        .stdout(predicate::str::contains("# App Rules for app"));
+
+    cagents()
+        .current_dir(temp.path())
+        .arg("build")
+        .assert()
+        .success();
+
+    temp.child("AGENTS.md").assert(predicate::str::contains("# Root Rules"));
+    package.child("AGENTS.md").assert(predicate::str::contains("# App Rules for app"));
+    package.child(".cAGENTS/.output-cache").assert(predicate::path::missing());
+
+    // Inside the package, the package is the nearest project and is used on its own
+    cagents()
+        .current_dir(package.path())
+        .args(["render", "index.ts"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("# App Rules for app"))
+        .stdout(predicate::str::contains("# Root Rules").not());
+}
@@ -233,3 +233,132 @@ These rules apply to all files.
         .stdout(predicate::str::contains("Global Rules"))
         .stdout(predicate::str::contains("apply to all files"));
 }
+
+#[test]
+fn test_render_var_applies_to_when_clause() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let cagents_dir = temp.child(".cAGENTS");
+    cagents_dir.create_dir_all().unwrap();
+
+    let config = cagents_dir.child("config.toml");
+    config.write_str(r#"
+[paths]
+templatesDir = "templates"
+outputRoot = "."
+
+[defaults]
+engine = "builtin:simple"
+
+[variables.static]
+role = "frontend"
+"#).unwrap();
+
+    let templates_dir = cagents_dir.child("templates");
+    templates_dir.create_dir_all().unwrap();
+
+    templates_dir.child("backend.md").write_str(r#"---
+name: "backend"
+globs:
+  - "src/**/*.rs"
+when:
+  role: ["backend"]
+---
+# Backend Rules
+"#).unwrap();
+
+    templates_dir.child("frontend.md").write_str(r#"---
+name: "frontend"
+when:
+  role: ["frontend"]
+---
+# Frontend Rules
+"#).unwrap();
+
+    let src_dir = temp.child("src");
+    src_dir.create_dir_all().unwrap();
+    src_dir.child("main.rs").write_str("fn main() {}").unwrap();
+
+    // Config variables participate in when matching
+    let mut cmd = Command::cargo_bin("cagents").unwrap();
+    cmd.current_dir(temp.path())
+        .arg("render")
+        .arg("src/main.rs")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Frontend Rules"))
+        .stdout(predicate::str::contains("Backend Rules").not());
+
+    // --var overrides config variables for when matching
+    let mut cmd = Command::cargo_bin("cagents").unwrap();
+    cmd.current_dir(temp.path())
+        .arg("render")
+        .arg("src/main.rs")
+        .arg("--var")
+        .arg("role=backend")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Backend Rules"))
+        .stdout(predicate::str::contains("Frontend Rules").not());
+}
+
+#[test]
+fn test_render_target_flag() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    let cagents_dir = temp.child(".cAGENTS");
+    cagents_dir.create_dir_all().unwrap();
+
+    let config = cagents_dir.child("config.toml");
+    config.write_str(r#"
+[paths]
+templatesDir = "templates"
+outputRoot = "."
+
+[defaults]
+engine = "builtin:simple"
+
+[output]
+targets = ["agents-md", "claude-md"]
+"#).unwrap();
+
+    let templates_dir = cagents_dir.child("templates");
+    templates_dir.create_dir_all().unwrap();
+
+    templates_dir.child("claude.md").write_str(r#"---
+name: "claude-only"
+when:
+  target: ["claude-md"]
+---
+# Claude Rules
+"#).unwrap();
+
+    templates_dir.child("common.md").write_str(r#"---
+name: "common"
+---
+# Common Rules
+"#).unwrap();
+
+    temp.child("test.txt").write_str("test").unwrap();
+
+    // Defaults to the first configured target (agents-md)
+    let mut cmd = Command::cargo_bin("cagents").unwrap();
+    cmd.current_dir(temp.path())
+        .arg("render")
+        .arg("test.txt")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Common Rules"))
+        .stdout(predicate::str::contains("Claude Rules").not());
+
+    let mut cmd = Command::cargo_bin("cagents").unwrap();
+    cmd.current_dir(temp.path())
+        .arg("render")
+        .arg("test.txt")
+        .arg("--target")
+        .arg("claude-md")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Common Rules"))
+        .stdout(predicate::str::contains("Claude Rules"));
+}
@@ -0,0 +1,82 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{cagents, write_config, write_template};
+use predicates::prelude::*;
+
+fn setup_project(temp: &assert_fs::TempDir, execution: &str) {
+    write_config(temp, execution);
+
+    // Renders for two output directories; the counter shows how often it ran
+    write_template(temp, "packages.md", r#"---
+name: packages
+globs: ["packages/*/src/**"]
+outputIn: parent
+commandVars:
+  runs: "echo x >> runs.log && wc -l < runs.log | tr -d ' '"
+---
+Command ran {{runs}} time(s)
+"#);
+    // Never applies, so its command must never run
+    write_template(temp, "docs.md", r#"---
+name: docs
+when:
+  publish_docs: "true"
+commandVars:
+  marker: "touch never-ran.txt && echo yes"
+---
+{{marker}}
+"#);
+
+    temp.child("packages/a/src/lib.ts").write_str("").unwrap();
+    temp.child("packages/b/src/lib.ts").write_str("").unwrap();
+}
+
+#[test]
+fn test_rule_commands_run_once_and_only_when_rendered() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, "");
+
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    temp.child("packages/a/src/AGENTS.md").assert(predicate::str::contains("Command ran 1 time(s)"));
+    temp.child("packages/b/src/AGENTS.md").assert(predicate::str::contains("Command ran 1 time(s)"));
+    temp.child("runs.log").assert("x\n");
+    temp.child("never-ran.txt").assert(predicate::path::missing());
+}
+
+#[test]
+fn test_context_shows_rule_command_source() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, "");
+
+    let output = cagents()
+        .current_dir(temp.path())
+        .args(["context", "packages/a/src/lib.ts", "--json"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let runs = &json["variables"]["runs"];
+    assert_eq!(runs["value"], "1");
+    assert_eq!(runs["kind"], "command");
+    assert_eq!(runs["source"], "frontmatter.command");
+    assert_eq!(runs["rule"], "packages");
+    assert!(json["variables"].get("marker").is_none());
+}
+
+#[test]
+fn test_allow_commands_false_skips_rule_commands() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, "[execution]\nallowCommands = false");
+
+    cagents()
+        .current_dir(temp.path())
+        .arg("build")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Skipping command variable 'runs'"));
+
+    temp.child("runs.log").assert(predicate::path::missing());
+}
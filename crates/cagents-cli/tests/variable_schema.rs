@@ -0,0 +1,145 @@
+mod common;
+
+use assert_fs::prelude::*;
+use common::{cagents, write_config, write_template};
+use predicates::prelude::*;
+
+fn setup_project(temp: &assert_fs::TempDir, schema: &str) {
+    write_config(temp, schema);
+
+    write_template(temp, "root.md", r#"---
+name: root
+---
+# Rules for {{team}} ({{tone}})
+"#);
+}
+
+const SCHEMA: &str = r#"
+[variables.schema.team]
+type = "string"
+allowed = ["web", "platform"]
+required = true
+description = "Team that owns this repository"
+
+[variables.schema.tone]
+default = "friendly"
+"#;
+
+#[test]
+fn test_build_fails_on_missing_required_variable() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, SCHEMA);
+
+    cagents()
+        .current_dir(temp.path())
+        .arg("build")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("team - Team that owns this repository"));
+
+    temp.child("AGENTS.md").assert(predicate::path::missing());
+}
+
+#[test]
+fn test_build_applies_schema_default() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, SCHEMA);
+
+    cagents()
+        .current_dir(temp.path())
+        .args(["build", "--var", "team=web"])
+        .assert()
+        .success();
+
+    temp.child("AGENTS.md").assert(predicate::str::contains("# Rules for web (friendly)"));
+}
+
+#[test]
+fn test_value_outside_allowed_is_rejected() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, SCHEMA);
+
+    cagents()
+        .current_dir(temp.path())
+        .args(["build", "--var", "team=sales"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected one of: web, platform"));
+}
+
+#[test]
+fn test_matrix_supplies_required_variable() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, &format!(r#"{}
+[matrix]
+outputRoot = "dist/{{team}}"
+
+[matrix.variables]
+team = ["web", "platform"]
+"#, SCHEMA));
+
+    cagents().current_dir(temp.path()).arg("build").assert().success();
+
+    temp.child("dist/web/AGENTS.md").assert(predicate::str::contains("# Rules for web (friendly)"));
+    temp.child("dist/platform/AGENTS.md").assert(predicate::str::contains("# Rules for platform (friendly)"));
+}
+
+#[test]
+fn test_matrix_values_are_checked() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, &format!(r#"{}
+[matrix]
+outputRoot = "dist/{{team}}"
+
+[matrix.variables]
+team = ["web", "sales"]
+"#, SCHEMA));
+
+    cagents()
+        .current_dir(temp.path())
+        .arg("build")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("expected one of: web, platform"))
+        .stderr(predicate::str::contains("from matrix"));
+
+    temp.child("dist/web/AGENTS.md").assert(predicate::path::missing());
+}
+
+#[test]
+fn test_vars_json_includes_declaration() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, SCHEMA);
+
+    let output = cagents()
+        .current_dir(temp.path())
+        .args(["vars", "--json", "--var", "team=platform"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["team"]["description"], "Team that owns this repository");
+    assert_eq!(json["tone"]["source"], "config.default");
+}
+
+#[test]
+fn test_lint_warns_on_undeclared_when_variable() {
+    let temp = assert_fs::TempDir::new().unwrap();
+    setup_project(&temp, SCHEMA);
+    temp.child(".cAGENTS/templates/web.md").write_str(r#"---
+name: web
+when:
+  team: ["sales"]
+  region: ["eu"]
+---
+# Web
+"#).unwrap();
+
+    cagents()
+        .current_dir(temp.path())
+        .arg("lint")
+        .assert()
+        .stdout(predicate::str::contains("when.region uses a variable that is not declared"))
+        .stdout(predicate::str::contains("when.team: Variable 'team' has value 'sales'"));
+}
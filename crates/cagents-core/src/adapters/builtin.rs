@@ -6,10 +6,10 @@ use regex::Regex;
 use serde_json::Value;
 
 /// Render template using built-in simple string interpolation
-/// Replaces {{variable}} with values from data
+/// Replaces {{variable}} (or a dotted path like {{cagents.git.branch}}) with values from data
 /// STRICT: Returns error if variable is not found in data
 pub fn render_simple(source: &str, data: &Value) -> Result<String> {
-    let var_pattern = Regex::new(r"\{\{(\w+)\}\}").unwrap();
+    let var_pattern = Regex::new(r"\{\{(\w+(?:\.\w+)*)\}\}").unwrap();
     let mut result = source.to_string();
     let mut undefined_vars = Vec::new();
 
@@ -19,15 +19,21 @@ pub fn render_simple(source: &str, data: &Value) -> Result<String> {
         let placeholder = &cap[0]; // Full {{var}} including braces
 
         // Look up variable in data
-        if let Some(value) = data.get(var_name) {
+        if let Some(value) = var_name.split('.').try_fold(data, |v, key| v.get(key)) {
             let replacement = match value {
                 Value::String(s) => s.clone(),
                 Value::Number(n) => n.to_string(),
                 Value::Bool(b) => b.to_string(),
                 Value::Null => String::new(),
+                // Lists of scalars (e.g. {{cagents.languages}}) are joined with ", "
+                Value::Array(items) if items.iter().all(|i| !i.is_array() && !i.is_object()) => items
+                    .iter()
+                    .map(|i| i.as_str().map(str::to_string).unwrap_or_else(|| i.to_string()))
+                    .collect::<Vec<_>>()
+                    .join(", "),
                 _ => {
                     anyhow::bail!(
-                        "Variable '{}' has unsupported type (expected string, number, or boolean)",
+                        "Variable '{}' has unsupported type (expected string, number, boolean, or a list of them)",
                         var_name
                     );
                 }
@@ -63,6 +69,22 @@ mod tests {
         assert_eq!(result, "Hello World!");
     }
 
+    #[test]
+    fn test_dotted_path_replacement() {
+        let template = "Branch: {{cagents.git.branch}}";
+        let data = json!({"cagents": {"git": {"branch": "main"}}});
+        let result = render_simple(template, &data).unwrap();
+        assert_eq!(result, "Branch: main");
+    }
+
+    #[test]
+    fn test_scalar_list_is_joined() {
+        let template = "Languages: {{languages}}";
+        let data = json!({"languages": ["rust", "typescript"]});
+        let result = render_simple(template, &data).unwrap();
+        assert_eq!(result, "Languages: rust, typescript");
+    }
+
     #[test]
     fn test_multiple_variables() {
         let template = "Project: {{project}}\nOwner: {{owner}}";
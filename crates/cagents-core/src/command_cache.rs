@@ -0,0 +1,354 @@
+// cache `[variables.command]` results under .cAGENTS/ (per-variable `ttl` / `cacheKey`)
+
+use crate::model::{ConfigOrigin, ProjectConfig};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Cache file, relative to the project root
+pub const CACHE_FILE: &str = ".cAGENTS/.command-cache";
+
+/// Entries that weren't read or written for this long are dropped when the cache is saved
+const UNUSED_AFTER: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// One `[variables.command]` (or frontmatter `commandVars`) entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandSpec {
+    pub command: String,
+    /// Cached results expire after this long
+    pub ttl: Option<Duration>,
+    /// Files whose content invalidates the cached result when it changes
+    pub cache_key: Vec<PathBuf>,
+    /// Project that declared the variable (`""` for the root project)
+    pub project_dir: PathBuf,
+}
+
+impl CommandSpec {
+    /// Parse `"cmd"` or `{ command = "...", ttl = "10m", cacheKey = ["file", ...] }`
+    ///
+    /// `cacheKey` paths are relative to the project that declared the variable.
+    pub fn from_spec(name: &str, spec: &Value, origin: Option<&ConfigOrigin>) -> Result<Self> {
+        Self::from_spec_in(name, spec, &crate::data_files::project_dir(origin))
+    }
+
+    /// Like [`CommandSpec::from_spec`], with `cacheKey` paths relative to `project_dir`
+    pub fn from_spec_in(name: &str, spec: &Value, project_dir: &Path) -> Result<Self> {
+        let obj = match spec {
+            Value::String(command) => {
+                return Ok(Self {
+                    command: command.clone(),
+                    ttl: None,
+                    cache_key: Vec::new(),
+                    project_dir: project_dir.to_path_buf(),
+                })
+            }
+            Value::Object(obj) => obj,
+            _ => anyhow::bail!(
+                "Command variable '{}' must be a command or {{ command, ttl, cacheKey }}",
+                name
+            ),
+        };
+
+        if let Some(key) = obj.keys().find(|k| !["command", "ttl", "cacheKey"].contains(&k.as_str())) {
+            anyhow::bail!("Unknown key '{}' in command variable '{}'", key, name);
+        }
+
+        let command = obj
+            .get("command")
+            .and_then(|c| c.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Command variable '{}' is missing 'command'", name))?
+            .to_string();
+
+        let ttl = obj
+            .get("ttl")
+            .map(|ttl| parse_ttl(ttl).with_context(|| format!("Invalid ttl for '{}'", name)))
+            .transpose()?;
+
+        let cache_key = match obj.get("cacheKey") {
+            None => Vec::new(),
+            Some(Value::String(file)) => vec![project_dir.join(file)],
+            Some(Value::Array(files)) => files
+                .iter()
+                .map(|f| {
+                    f.as_str()
+                        .map(|f| project_dir.join(f))
+                        .ok_or_else(|| anyhow::anyhow!("cacheKey for '{}' must list file paths", name))
+                })
+                .collect::<Result<_>>()?,
+            Some(_) => anyhow::bail!("cacheKey for '{}' must be a file path or a list of them", name),
+        };
+
+        Ok(Self {
+            command,
+            ttl,
+            cache_key,
+            project_dir: project_dir.to_path_buf(),
+        })
+    }
+
+    /// Whether results are cached at all (a TTL or input files are set)
+    pub fn is_cached(&self) -> bool {
+        self.ttl.is_some() || !self.cache_key.is_empty()
+    }
+
+    /// Fingerprint of the `cacheKey` files (missing files count as a distinct state)
+    fn inputs_fingerprint(&self) -> BTreeMap<PathBuf, String> {
+        self.cache_key
+            .iter()
+            .map(|path| {
+                let fingerprint = crate::data_files::file_fingerprint(path).unwrap_or_else(|| "missing".to_string());
+                (path.clone(), fingerprint)
+            })
+            .collect()
+    }
+}
+
+/// Every `[variables.command]` entry in the config, by variable name
+pub fn command_specs(config: &ProjectConfig) -> Result<BTreeMap<String, CommandSpec>> {
+    let mut specs = BTreeMap::new();
+    let Some(obj) = config
+        .variables
+        .as_ref()
+        .and_then(|v| v.command.as_ref())
+        .and_then(|c| c.as_object())
+    else {
+        return Ok(specs);
+    };
+
+    for (name, spec) in obj {
+        let origin = config.variable_origins.command.get(name);
+        specs.insert(name.clone(), CommandSpec::from_spec(name, spec, origin)?);
+    }
+    Ok(specs)
+}
+
+/// `600` (seconds) or a string with a unit: `"30s"`, `"10m"`, `"2h"`, `"1d"`
+pub fn parse_ttl(value: &Value) -> Result<Duration> {
+    if let Some(seconds) = value.as_u64() {
+        return Ok(Duration::from_secs(seconds));
+    }
+
+    let text = value
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Expected seconds or a duration like \"10m\", got {}", value))?
+        .trim();
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: u64 = number
+        .parse()
+        .with_context(|| format!("Expected a duration like \"10m\", got \"{}\"", text))?;
+
+    let multiplier = match unit.trim() {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        other => anyhow::bail!("Unknown duration unit '{}' (use s, m, h or d)", other),
+    };
+    Ok(Duration::from_secs(number * multiplier))
+}
+
+/// A cached command result
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CacheEntry {
+    pub value: String,
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+    /// When the `ttl` runs out (seconds since the Unix epoch); never without a `ttl`
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    /// Last time a build read or wrote the entry (seconds since the Unix epoch)
+    #[serde(default)]
+    pub used_at: u64,
+    #[serde(default)]
+    pub inputs: BTreeMap<PathBuf, String>,
+}
+
+/// Cached command results by shell, declaring project and command
+///
+/// Variables of one project running the same command with the same shell share a result;
+/// the cache file itself belongs to the directory the build runs in.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CommandCache {
+    entries: BTreeMap<String, CacheEntry>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl CommandCache {
+    /// Load the cache; a missing or unreadable cache is empty
+    pub fn load() -> Self {
+        fs::read_to_string(CACHE_FILE)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Cached value if it hasn't expired and its input files haven't changed
+    ///
+    /// A hit marks the entry as used, which is recorded the next time the cache is saved.
+    pub fn get(&mut self, shell: &str, spec: &CommandSpec) -> Option<&str> {
+        let entry = self.entries.get_mut(&entry_key(shell, spec))?;
+        if entry.inputs != spec.inputs_fingerprint() {
+            return None;
+        }
+        if let Some(ttl) = spec.ttl {
+            if now().saturating_sub(entry.created_at) >= ttl.as_secs() {
+                return None;
+            }
+        }
+        entry.used_at = now();
+        Some(&entry.value)
+    }
+
+    pub fn insert(&mut self, shell: &str, spec: &CommandSpec, value: &str) {
+        self.entries.insert(
+            entry_key(shell, spec),
+            CacheEntry {
+                value: value.to_string(),
+                created_at: now(),
+                expires_at: spec.ttl.map(|ttl| now() + ttl.as_secs()),
+                used_at: now(),
+                inputs: spec.inputs_fingerprint(),
+            },
+        );
+        self.dirty = true;
+    }
+
+    /// Write the cache if anything changed (skipped when there is no `.cAGENTS/` directory)
+    ///
+    /// Expired entries and entries unused for [`UNUSED_AFTER`] are dropped first, so
+    /// commands that were changed or removed from the config don't pile up.
+    pub fn save(&mut self) -> Result<()> {
+        let path = Path::new(CACHE_FILE);
+        if !self.dirty || !path.parent().is_some_and(|dir| dir.is_dir()) {
+            return Ok(());
+        }
+        self.evict(now());
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", CACHE_FILE))
+    }
+
+    /// Drop entries that expired or weren't used for [`UNUSED_AFTER`] as of `now`
+    fn evict(&mut self, now: u64) {
+        self.entries.retain(|_, entry| {
+            let expired = entry.expires_at.is_some_and(|at| at <= now);
+            let unused = now.saturating_sub(entry.used_at) >= UNUSED_AFTER.as_secs();
+            !expired && !unused
+        });
+    }
+}
+
+/// `<shell>|<project dir>|<command>`
+fn entry_key(shell: &str, spec: &CommandSpec) -> String {
+    format!("{}|{}|{}", shell, spec.project_dir.display(), spec.command)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_ttl() {
+        assert_eq!(parse_ttl(&json!(90)).unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_ttl(&json!("10m")).unwrap(), Duration::from_secs(600));
+        assert_eq!(parse_ttl(&json!("1d")).unwrap(), Duration::from_secs(86400));
+        assert!(parse_ttl(&json!("5 weeks")).is_err());
+    }
+
+    #[test]
+    fn test_spec_from_table() {
+        let origin = ConfigOrigin {
+            layer: crate::model::ConfigLayer::Project,
+            file: PathBuf::from("apps/web/.cAGENTS/config.toml"),
+        };
+        let spec = CommandSpec::from_spec(
+            "packages",
+            &json!({"command": "pnpm ls -r", "ttl": "1h", "cacheKey": "pnpm-lock.yaml"}),
+            Some(&origin),
+        )
+        .unwrap();
+        assert_eq!(spec.ttl, Some(Duration::from_secs(3600)));
+        assert_eq!(spec.cache_key, vec![PathBuf::from("apps/web/pnpm-lock.yaml")]);
+        assert!(spec.is_cached());
+
+        assert!(!CommandSpec::from_spec("x", &json!("echo hi"), None).unwrap().is_cached());
+        assert!(CommandSpec::from_spec("x", &json!({"command": "echo", "timeout": 5}), None).is_err());
+    }
+
+    #[test]
+    fn test_cache_entry_expires_and_tracks_command() {
+        let spec = CommandSpec {
+            command: "echo hi".to_string(),
+            ttl: Some(Duration::from_secs(60)),
+            cache_key: Vec::new(),
+            project_dir: PathBuf::new(),
+        };
+        let mut cache = CommandCache::default();
+        cache.insert("bash", &spec, "hi");
+        assert_eq!(cache.get("bash", &spec), Some("hi"));
+
+        let changed = CommandSpec {
+            command: "echo hello".to_string(),
+            ..spec.clone()
+        };
+        assert_eq!(cache.get("bash", &changed), None);
+
+        cache.entries.get_mut("bash||echo hi").unwrap().created_at -= 120;
+        assert_eq!(cache.get("bash", &spec), None);
+    }
+
+    #[test]
+    fn test_cache_entries_are_per_shell_and_project() {
+        let spec = CommandSpec {
+            command: "echo $0".to_string(),
+            ttl: Some(Duration::from_secs(60)),
+            cache_key: Vec::new(),
+            project_dir: PathBuf::new(),
+        };
+        let nested = CommandSpec {
+            project_dir: PathBuf::from("packages/web"),
+            ..spec.clone()
+        };
+        let mut cache = CommandCache::default();
+        cache.insert("bash", &spec, "bash");
+
+        assert_eq!(cache.get("sh", &spec), None);
+        assert_eq!(cache.get("bash", &nested), None);
+    }
+
+    #[test]
+    fn test_save_evicts_expired_and_unused_entries() {
+        let spec = |command: &str, ttl: Option<u64>| CommandSpec {
+            command: command.to_string(),
+            ttl: ttl.map(Duration::from_secs),
+            cache_key: vec![PathBuf::from("Cargo.lock")],
+            project_dir: PathBuf::new(),
+        };
+        let mut cache = CommandCache::default();
+        cache.insert("bash", &spec("short", Some(60)), "a");
+        cache.insert("bash", &spec("long", Some(90 * 24 * 60 * 60)), "b");
+        cache.insert("bash", &spec("keyed", None), "c");
+
+        // An hour later the short ttl has run out
+        cache.evict(now() + 3600);
+        let keys: Vec<&str> = cache.entries.keys().map(String::as_str).collect();
+        assert_eq!(keys, vec!["bash||keyed", "bash||long"]);
+
+        // Entries nobody used for UNUSED_AFTER go too, even before their ttl
+        cache.evict(now() + UNUSED_AFTER.as_secs());
+        assert!(cache.entries.is_empty());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::model::{ProjectConfig, PartialProjectConfig, Paths};
+use crate::model::{ConfigLayer, ConfigOrigin, PartialProjectConfig, Paths, ProjectConfig, VariableOrigins};
 use anyhow::{Context, Result};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -9,45 +9,282 @@ pub fn load_project_config(path: &str) -> Result<ProjectConfig> {
     Ok(cfg)
 }
 
+/// Which project config and profile to load (`--config` and `--profile`)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoadOptions {
+    /// Project config file to use instead of `.cAGENTS/config.toml`
+    pub config: Option<PathBuf>,
+    /// Profile whose `[profiles.<name>]` sections are applied
+    pub profile: Option<String>,
+}
+
+impl LoadOptions {
+    /// Default options, with the profile from the `CAGENTS_PROFILE` environment variable
+    pub fn from_env() -> Self {
+        Self {
+            config: None,
+            profile: std::env::var("CAGENTS_PROFILE").ok().filter(|p| !p.is_empty()),
+        }
+    }
+
+    /// Project config file: `--config <path>` or `.cAGENTS/config.toml`
+    pub fn project_config_path(&self) -> PathBuf {
+        self.config
+            .clone()
+            .unwrap_or_else(|| PathBuf::from(".cAGENTS/config.toml"))
+    }
+
+    /// Path of the config file for a layer (None for the env layer, or if the home directory is unknown)
+    pub fn layer_path(&self, layer: ConfigLayer) -> Option<PathBuf> {
+        match layer {
+            ConfigLayer::User => dirs::home_dir().map(|home| home.join(".cagents/config.toml")),
+            ConfigLayer::Project => Some(self.project_config_path()),
+            ConfigLayer::Local => Some(PathBuf::from(".cAGENTS/config.local.toml")),
+            ConfigLayer::Env => None,
+        }
+    }
+}
+
 /// Load config with precedence:
 /// 1. User config (~/.cagents/config.toml) - lowest priority
 /// 2. Project config (.cAGENTS/config.toml) - medium priority
 /// 3. Local config (.cAGENTS/config.local.toml) - highest priority
 ///
 /// Later configs override earlier ones (deep merge for nested objects)
+///
+/// If a profile is selected, its `[profiles.<name>]` sections are applied on top
+/// of all three layers. `CAGENTS_*` environment overrides are applied last.
+pub fn load_config(options: &LoadOptions) -> Result<ProjectConfig> {
+    resolve_layers(load_config_layers(options)?, options)
+}
+
+/// [`load_config`] with the default project config and the `CAGENTS_PROFILE` profile
 pub fn load_config_with_precedence() -> Result<ProjectConfig> {
+    load_config(&LoadOptions::from_env())
+}
+
+/// Config for a nested project: the root layers, then the `.cAGENTS/config.toml` and
+/// `config.local.toml` of each nested project on the path to it (outermost first)
+pub fn load_nested_config(chain: &[PathBuf], options: &LoadOptions) -> Result<ProjectConfig> {
+    let mut layers = load_config_layers(options)?;
+    for dir in chain {
+        for (layer, file) in [(ConfigLayer::Project, "config.toml"), (ConfigLayer::Local, "config.local.toml")] {
+            let path = dir.join(".cAGENTS").join(file);
+            if path.exists() {
+                layers.push((origin(layer, &path), load_single_config(&path)?));
+            }
+        }
+    }
+    resolve_layers(layers, options)
+}
+
+/// Apply the selected profile and environment overrides on top of file layers, then merge
+fn resolve_layers(
+    mut layers: Vec<(ConfigOrigin, PartialProjectConfig)>,
+    options: &LoadOptions,
+) -> Result<ProjectConfig> {
+    if let Some(profile) = &options.profile {
+        layers = apply_profile(layers, profile)?;
+    }
+    layers.extend(env_override_layers()?);
+
+    // Merge all configs (later overrides earlier)
+    merge_configs(layers)
+}
+
+/// Append a profile's overlays (user, project, local order) after the regular layers
+///
+/// Fails if no layer defines the profile.
+pub fn apply_profile(
+    layers: Vec<(ConfigOrigin, PartialProjectConfig)>,
+    profile: &str,
+) -> Result<Vec<(ConfigOrigin, PartialProjectConfig)>> {
+    let mut overlays = Vec::new();
+    let mut available = std::collections::BTreeSet::new();
+
+    for (origin, cfg) in &layers {
+        if let Some(profiles) = &cfg.profiles {
+            available.extend(profiles.keys().cloned());
+            if let Some(overlay) = profiles.get(profile) {
+                overlays.push((origin.clone(), PartialProjectConfig::from(overlay.clone())));
+            }
+        }
+    }
+
+    if overlays.is_empty() {
+        let available = if available.is_empty() {
+            "none defined".to_string()
+        } else {
+            available.into_iter().collect::<Vec<_>>().join(", ")
+        };
+        anyhow::bail!("Profile '{}' not found in config (available: {})", profile, available);
+    }
+
+    let mut layers = layers;
+    layers.extend(overlays);
+    Ok(layers)
+}
+
+/// Nearest directory at or above `start` containing `.cAGENTS/config.toml`
+///
+/// The home directory is skipped: on case-insensitive filesystems the user config
+/// directory `~/.cagents` would otherwise look like a project.
+pub fn find_project_root(start: &Path) -> Option<PathBuf> {
+    let home = dirs::home_dir();
+    start
+        .ancestors()
+        .filter(|dir| home.as_deref() != Some(*dir))
+        .find(|dir| dir.join(".cAGENTS/config.toml").is_file())
+        .map(Path::to_path_buf)
+}
+
+/// Load every existing config layer in precedence order (lowest first)
+///
+/// The project config is required; user and local configs are optional and
+/// only produce a warning if they fail to parse.
+pub fn load_config_layers(options: &LoadOptions) -> Result<Vec<(ConfigOrigin, PartialProjectConfig)>> {
     let mut configs = Vec::new();
 
     // 1. Try user config
-    if let Some(home_dir) = dirs::home_dir() {
-        let user_config = home_dir.join(".cagents/config.toml");
+    if let Some(user_config) = options.layer_path(ConfigLayer::User) {
         if user_config.exists() {
             match load_single_config(&user_config) {
-                Ok(cfg) => configs.push(cfg),
+                Ok(cfg) => configs.push((origin(ConfigLayer::User, &user_config), cfg)),
                 Err(e) => eprintln!("Warning: Failed to load user config: {}", e),
             }
         }
     }
 
     // 2. Load project config (required)
-    let project_config = PathBuf::from(".cAGENTS/config.toml");
+    let project_config = options.project_config_path();
     if !project_config.exists() {
-        anyhow::bail!("Project config not found at .cAGENTS/config.toml");
+        anyhow::bail!(
+            "Project config not found at {}. Run `cagents init`, or run from inside a project (or pass -C <dir>)",
+            project_config.display()
+        );
     }
     let cfg = load_single_config(&project_config)?;
-    configs.push(cfg);
+    configs.push((origin(ConfigLayer::Project, &project_config), cfg));
 
     // 3. Try local config (optional override)
     let local_config = PathBuf::from(".cAGENTS/config.local.toml");
     if local_config.exists() {
         match load_single_config(&local_config) {
-            Ok(cfg) => configs.push(cfg),
+            Ok(cfg) => configs.push((origin(ConfigLayer::Local, &local_config), cfg)),
             Err(e) => eprintln!("Warning: Failed to load local config: {}", e),
         }
     }
 
-    // Merge all configs (later overrides earlier)
-    merge_configs(configs)
+    Ok(configs)
+}
+
+/// One layer per `CAGENTS_*` override, sorted by variable name
+///
+/// - `CAGENTS_<SECTION>__<KEY>` sets `<section>.<key>` (`CAGENTS_PATHS__OUTPUT_ROOT` -> `paths.outputRoot`)
+/// - `CAGENTS_VAR_<NAME>` sets `variables.static.<name>`, lowercased like the section keys (`CAGENTS_VAR_ROLE` -> `role`)
+///
+/// Values are read as strings, then TOML scalars, then comma-separated lists,
+/// whichever the key accepts (`CAGENTS_OUTPUT__TARGETS=agents-md,claude-md`).
+pub fn env_override_layers() -> Result<Vec<(ConfigOrigin, PartialProjectConfig)>> {
+    let mut vars: Vec<(String, String)> = std::env::vars()
+        .filter(|(name, _)| name.starts_with("CAGENTS_"))
+        .collect();
+    vars.sort();
+
+    let mut layers = Vec::new();
+    for (name, value) in vars {
+        let Some(key) = env_override_key(&name) else {
+            continue;
+        };
+        // A stray variable in the environment shouldn't break every command
+        match env_override_config(&key, &value) {
+            Ok(cfg) => layers.push((origin(ConfigLayer::Env, Path::new(&name)), cfg)),
+            Err(e) => eprintln!("Warning: Ignoring invalid config override {}={}: {}", name, value, e),
+        }
+    }
+
+    Ok(layers)
+}
+
+/// Dotted config key set by a `CAGENTS_*` environment variable, if it is an override
+pub fn env_override_key(name: &str) -> Option<String> {
+    let rest = name.strip_prefix("CAGENTS_")?;
+
+    if let Some(var) = rest.strip_prefix("VAR_") {
+        return (!var.is_empty()).then(|| format!("variables.static.{}", var.to_lowercase()));
+    }
+
+    if !rest.contains("__") {
+        return None;
+    }
+
+    let segments: Vec<String> = rest.split("__").map(camel_case).collect();
+    if segments.iter().any(|s| s.is_empty()) || !ENV_OVERRIDE_SECTIONS.contains(&segments[0].as_str()) {
+        return None;
+    }
+    Some(segments.join("."))
+}
+
+/// Config sections `CAGENTS_<SECTION>__<KEY>` can set; other `CAGENTS_*__*` variables aren't overrides
+const ENV_OVERRIDE_SECTIONS: &[&str] = &["project", "paths", "defaults", "variables", "execution", "output", "matrix"];
+
+/// OUTPUT_ROOT -> outputRoot
+fn camel_case(segment: &str) -> String {
+    let mut result = String::new();
+    for (i, word) in segment.split('_').filter(|w| !w.is_empty()).enumerate() {
+        let word = word.to_lowercase();
+        if i == 0 {
+            result.push_str(&word);
+        } else {
+            let mut chars = word.chars();
+            if let Some(first) = chars.next() {
+                result.extend(first.to_uppercase());
+                result.push_str(chars.as_str());
+            }
+        }
+    }
+    result
+}
+
+/// Build a partial config setting one dotted key, picking the first value type the key accepts
+fn env_override_config(key: &str, raw: &str) -> Result<PartialProjectConfig> {
+    let mut candidates = vec![toml::Value::String(raw.to_string())];
+    if let Ok(table) = toml::from_str::<toml::Table>(&format!("v = {}", raw)) {
+        if let Some(value) = table.get("v") {
+            candidates.push(value.clone());
+        }
+    }
+    candidates.push(toml::Value::Array(
+        raw.split(',')
+            .map(|s| toml::Value::String(s.trim().to_string()))
+            .collect(),
+    ));
+
+    let mut first_error = None;
+    for candidate in candidates {
+        let mut value = candidate;
+        for segment in key.rsplit('.') {
+            let mut table = toml::Table::new();
+            table.insert(segment.to_string(), value);
+            value = toml::Value::Table(table);
+        }
+
+        match value.try_into::<PartialProjectConfig>() {
+            Ok(cfg) => return Ok(cfg),
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    Err(first_error.expect("at least one candidate").into())
+}
+
+fn origin(layer: ConfigLayer, file: &Path) -> ConfigOrigin {
+    ConfigOrigin {
+        layer,
+        file: file.to_path_buf(),
+    }
 }
 
 /// Load a single config file as a partial config
@@ -55,23 +292,37 @@ pub fn load_config_with_precedence() -> Result<ProjectConfig> {
 fn load_single_config(path: &Path) -> Result<PartialProjectConfig> {
     let content = fs::read_to_string(path)
         .with_context(|| format!("Failed to read config: {}", path.display()))?;
-    let config: PartialProjectConfig = toml::from_str(&content)
-        .with_context(|| format!("Failed to parse config: {}", path.display()))?;
-    Ok(config)
+    parse_partial_config(&content)
+        .with_context(|| format!("Failed to parse config: {}", path.display()))
+}
+
+/// Parse config file contents as a partial config (rejects unknown keys)
+pub fn parse_partial_config(content: &str) -> Result<PartialProjectConfig> {
+    Ok(toml::from_str(content)?)
 }
 
 /// Merge multiple partial configs and validate the result
 /// Later configs override earlier ones. Final config must have all required fields.
-fn merge_configs(configs: Vec<PartialProjectConfig>) -> Result<ProjectConfig> {
+/// Records which file each variable came from along the way.
+pub fn merge_configs(configs: Vec<(ConfigOrigin, PartialProjectConfig)>) -> Result<ProjectConfig> {
     if configs.is_empty() {
         anyhow::bail!("No configs to merge");
     }
 
     // Start with empty partial config
     let mut merged = PartialProjectConfig::default();
+    let mut origins = VariableOrigins::default();
 
     // Apply each config in order (later overrides earlier)
-    for cfg in configs {
+    for (origin, cfg) in configs {
+        // Record variable origins before the values are merged
+        if let Some(vars) = &cfg.variables {
+            record_origins(&mut origins.static_, vars.static_.as_ref(), &origin);
+            record_origins(&mut origins.env, vars.env.as_ref(), &origin);
+            record_origins(&mut origins.command, vars.command.as_ref(), &origin);
+            record_origins(&mut origins.files, vars.files.as_ref(), &origin);
+        }
+
         // Merge project metadata
         if cfg.project.is_some() {
             merged.project = cfg.project;
@@ -132,6 +383,16 @@ fn merge_configs(configs: Vec<PartialProjectConfig>) -> Result<ProjectConfig> {
                         new_vars.command.as_ref(),
                     );
                 }
+                if new_vars.files.is_some() {
+                    existing_vars.files = merge_json_values(
+                        existing_vars.files.as_ref(),
+                        new_vars.files.as_ref(),
+                    );
+                }
+                if let Some(new_schema) = new_vars.schema {
+                    // Later declarations replace earlier ones per variable
+                    existing_vars.schema.get_or_insert_with(Default::default).extend(new_schema);
+                }
             } else {
                 merged.variables = Some(new_vars);
             }
@@ -154,14 +415,53 @@ fn merge_configs(configs: Vec<PartialProjectConfig>) -> Result<ProjectConfig> {
             }
         }
 
-        // Merge output settings
-        if cfg.output.is_some() {
-            merged.output = cfg.output;
+        // Merge output settings (custom targets are merged by name)
+        if let Some(new_output) = cfg.output {
+            if let Some(ref mut existing_output) = merged.output {
+                if new_output.targets.is_some() {
+                    existing_output.targets = new_output.targets;
+                }
+                if let Some(custom) = new_output.custom {
+                    existing_output.custom.get_or_insert_with(Default::default).extend(custom);
+                }
+                if new_output.list_skills.is_some() {
+                    existing_output.list_skills = new_output.list_skills;
+                }
+                if new_output.claude_md.is_some() {
+                    existing_output.claude_md = new_output.claude_md;
+                }
+            } else {
+                merged.output = Some(new_output);
+            }
+        }
+
+        // Merge matrix (later config replaces the whole matrix)
+        if cfg.matrix.is_some() {
+            merged.matrix = cfg.matrix;
         }
     }
 
     // Validate and convert to full ProjectConfig
-    validate_and_convert(merged)
+    let mut config = validate_and_convert(merged)?;
+    config.variable_origins = origins;
+    crate::variables::validate_schema(&config)?;
+    crate::data_files::data_files(&config)?;
+    crate::command_cache::command_specs(&config)?;
+    crate::targets::validate_custom_targets(&config)?;
+    Ok(config)
+}
+
+/// Point every key of a variables table at the config file that set it
+fn record_origins(
+    origins: &mut std::collections::BTreeMap<String, ConfigOrigin>,
+    vars: Option<&serde_json::Value>,
+    origin: &ConfigOrigin,
+) {
+    if let Some(obj) = vars.and_then(|v| v.as_object()) {
+        for key in obj.keys() {
+            origins.insert(key.clone(), origin.clone());
+        }
+    }
 }
 
 /// Validate that a merged partial config has all required fields and convert to ProjectConfig
@@ -192,6 +492,8 @@ fn validate_and_convert(partial: PartialProjectConfig) -> Result<ProjectConfig>
         variables: partial.variables,
         execution: partial.execution,
         output: partial.output,
+        matrix: partial.matrix,
+        variable_origins: VariableOrigins::default(),
     })
 }
 
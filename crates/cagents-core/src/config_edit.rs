@@ -0,0 +1,382 @@
+// read and edit individual config layers, preserving formatting and comments
+
+use crate::config::{self, LoadOptions};
+use crate::model::{ConfigLayer, ConfigOrigin};
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item, Table, TableLike};
+
+/// A config value together with the file that set it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigEntry {
+    pub key: String,
+    pub value: Value,
+    pub origin: ConfigOrigin,
+}
+
+/// Config file for a layer, failing if the home directory is unknown
+pub fn layer_file(layer: ConfigLayer, options: &LoadOptions) -> Result<PathBuf> {
+    options.layer_path(layer).ok_or_else(|| match layer {
+        ConfigLayer::Env => anyhow::anyhow!("The env layer is set with CAGENTS_* environment variables, not a file"),
+        _ => anyhow::anyhow!("Could not determine home directory for the user config"),
+    })
+}
+
+/// Value of a dotted key (e.g. "paths.outputRoot") as set in a single layer
+pub fn get_layer_value(layer: ConfigLayer, key: &str, options: &LoadOptions) -> Result<Option<Value>> {
+    if layer == ConfigLayer::Env {
+        return Ok(env_entries()?
+            .into_iter()
+            .rev()
+            .find(|entry| entry.key == key)
+            .map(|entry| entry.value));
+    }
+
+    let path = layer_file(layer, options)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let table = read_layer_table(&path)?;
+    Ok(lookup(&table, key).cloned())
+}
+
+/// Effective value of a dotted key after merging all layers, and the file it came from
+///
+/// The value comes from `config::merge_configs` (including the selected profile); the
+/// origin is the highest-precedence layer that sets the key. Keys the project config
+/// model doesn't know about (e.g. `telemetry.*`) fall back to the highest layer that
+/// sets them.
+pub fn get_effective(key: &str, options: &LoadOptions) -> Result<Option<ConfigEntry>> {
+    EffectiveConfig::load(options)?.entry(key)
+}
+
+/// Every leaf value of the effective config, with the file that set it
+pub fn effective_entries(options: &LoadOptions) -> Result<Vec<ConfigEntry>> {
+    let effective = EffectiveConfig::load(options)?;
+
+    let mut keys = Vec::new();
+    for (_, _, table) in &effective.layers {
+        for (key, _) in flatten(table) {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+    }
+    for entry in &effective.env {
+        if !keys.contains(&entry.key) {
+            keys.push(entry.key.clone());
+        }
+    }
+    keys.sort();
+
+    let mut entries = Vec::new();
+    for key in keys {
+        if let Some(entry) = effective.entry(&key)? {
+            entries.push(entry);
+        }
+    }
+    Ok(entries)
+}
+
+/// The merged config and every layer's own values, loaded once for looking up many keys
+struct EffectiveConfig {
+    merged: Value,
+    variable_origins: crate::model::VariableOrigins,
+    /// Existing file layers and their files, lowest precedence first
+    layers: Vec<(ConfigLayer, PathBuf, Value)>,
+    profile: Option<String>,
+    env: Vec<ConfigEntry>,
+}
+
+impl EffectiveConfig {
+    fn load(options: &LoadOptions) -> Result<Self> {
+        let config = config::load_config(options)?;
+        let variable_origins = config.variable_origins.clone();
+
+        let mut layers = Vec::new();
+        for layer in [ConfigLayer::User, ConfigLayer::Project, ConfigLayer::Local] {
+            if let Ok(path) = layer_file(layer, options) {
+                if path.exists() {
+                    let table = read_layer_table(&path)?;
+                    layers.push((layer, path, table));
+                }
+            }
+        }
+
+        Ok(Self {
+            merged: serde_json::to_value(config)?,
+            variable_origins,
+            layers,
+            profile: options.profile.clone(),
+            env: env_entries()?,
+        })
+    }
+
+    fn entry(&self, key: &str) -> Result<Option<ConfigEntry>> {
+        let Some((origin, source_key)) = self.find_origin(key)? else {
+            return Ok(None);
+        };
+
+        let value = match lookup(&self.merged, key) {
+            Some(value) if !value.is_null() => value.clone(),
+            _ => match self.layer_value(origin.layer, &source_key) {
+                Some(value) => value,
+                None => return Ok(None),
+            },
+        };
+
+        Ok(Some(ConfigEntry {
+            key: key.to_string(),
+            value,
+            origin,
+        }))
+    }
+
+    fn layer_value(&self, layer: ConfigLayer, key: &str) -> Option<Value> {
+        if layer == ConfigLayer::Env {
+            return self.env.iter().rev().find(|entry| entry.key == key).map(|entry| entry.value.clone());
+        }
+        self.layers
+            .iter()
+            .find(|(l, _, _)| *l == layer)
+            .and_then(|(_, _, table)| lookup(table, key).cloned())
+    }
+
+    /// Highest-precedence layer that sets the key, and the key as written there
+    ///
+    /// Variables use the origins recorded while merging; other keys go to
+    /// `CAGENTS_*` environment overrides, then the selected profile's
+    /// `profiles.<name>.<key>`, then the plain layers.
+    fn find_origin(&self, key: &str) -> Result<Option<(ConfigOrigin, String)>> {
+        if let Some(origin) = self.variable_origin(key) {
+            return Ok(Some((origin.clone(), key.to_string())));
+        }
+
+        if let Some(entry) = self.env.iter().rev().find(|entry| entry.key == key) {
+            return Ok(Some((entry.origin.clone(), key.to_string())));
+        }
+
+        let mut candidates = Vec::new();
+        if let Some(profile) = &self.profile {
+            candidates.push(format!("profiles.{}.{}", profile, key));
+        }
+        candidates.push(key.to_string());
+
+        for candidate in candidates {
+            for (layer, file, table) in self.layers.iter().rev() {
+                if lookup(table, &candidate).is_some() {
+                    let origin = ConfigOrigin {
+                        layer: *layer,
+                        file: file.clone(),
+                    };
+                    return Ok(Some((origin, candidate)));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// `variables.<kind>.<name>[.<field>]` -> where `<name>` was set
+    fn variable_origin(&self, key: &str) -> Option<&ConfigOrigin> {
+        let mut segments = key.splitn(4, '.');
+        if segments.next() != Some("variables") {
+            return None;
+        }
+        let origins = match segments.next()? {
+            "static" => &self.variable_origins.static_,
+            "env" => &self.variable_origins.env,
+            "command" => &self.variable_origins.command,
+            "files" => &self.variable_origins.files,
+            _ => return None,
+        };
+        origins.get(segments.next()?)
+    }
+}
+
+/// Every leaf value set in a single layer
+pub fn layer_entries(layer: ConfigLayer, options: &LoadOptions) -> Result<Vec<ConfigEntry>> {
+    if layer == ConfigLayer::Env {
+        return env_entries();
+    }
+
+    let path = layer_file(layer, options)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let table = read_layer_table(&path)?;
+    let origin = ConfigOrigin { layer, file: path };
+    Ok(flatten(&table)
+        .into_iter()
+        .map(|(key, value)| ConfigEntry {
+            key,
+            value,
+            origin: origin.clone(),
+        })
+        .collect())
+}
+
+/// Values set by `CAGENTS_*` environment overrides, in the order they are applied
+fn env_entries() -> Result<Vec<ConfigEntry>> {
+    let mut entries = Vec::new();
+    for (origin, cfg) in config::env_override_layers()? {
+        for (key, value) in flatten(&serde_json::to_value(cfg)?) {
+            entries.push(ConfigEntry {
+                key,
+                value,
+                origin: origin.clone(),
+            });
+        }
+    }
+    Ok(entries)
+}
+
+/// Set a dotted key in a layer, keeping the rest of the file untouched
+///
+/// `raw` is parsed as a TOML value (`true`, `42`, `["a", "b"]`, `"text"`);
+/// anything that isn't valid TOML is stored as a string.
+pub fn set_value(layer: ConfigLayer, key: &str, raw: &str, options: &LoadOptions) -> Result<PathBuf> {
+    let path = layer_file(layer, options)?;
+    let mut doc = read_document(&path)?;
+    let segments = split_key(key)?;
+    let (last, parents) = segments.split_last().expect("split_key never returns empty");
+
+    let mut table: &mut dyn TableLike = doc.as_table_mut();
+    for segment in parents {
+        table = table
+            .entry(segment)
+            .or_insert(Item::Table(Table::new()))
+            .as_table_like_mut()
+            .ok_or_else(|| anyhow::anyhow!("'{}' in '{}' is not a table", segment, key))?;
+    }
+
+    // Keep an existing value's surrounding whitespace and trailing comment
+    let mut value = parse_value(raw);
+    if let Some(existing) = table.get(last).and_then(|item| item.as_value()) {
+        *value.decor_mut() = existing.decor().clone();
+    }
+    table.insert(last, toml_edit::value(value));
+
+    write_document(layer, &path, &doc)?;
+    Ok(path)
+}
+
+/// Remove a dotted key from a layer; empty parent tables are removed as well
+pub fn unset_value(layer: ConfigLayer, key: &str, options: &LoadOptions) -> Result<PathBuf> {
+    let path = layer_file(layer, options)?;
+    if !path.exists() {
+        anyhow::bail!("Key '{}' is not set in {}", key, path.display());
+    }
+
+    let mut doc = read_document(&path)?;
+    let segments = split_key(key)?;
+    if !remove_key(doc.as_table_mut(), &segments) {
+        anyhow::bail!("Key '{}' is not set in {}", key, path.display());
+    }
+
+    write_document(layer, &path, &doc)?;
+    Ok(path)
+}
+
+/// Render a config value for display: strings bare, everything else as TOML
+pub fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => toml::Value::try_from(other)
+            .map(|v| v.to_string())
+            .unwrap_or_else(|_| other.to_string()),
+    }
+}
+
+fn read_layer_table(path: &Path) -> Result<Value> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config: {}", path.display()))?;
+    let table: toml::Table = toml::from_str(&content)
+        .with_context(|| format!("Failed to parse config: {}", path.display()))?;
+    Ok(serde_json::to_value(table)?)
+}
+
+fn read_document(path: &Path) -> Result<DocumentMut> {
+    if !path.exists() {
+        return Ok(DocumentMut::new());
+    }
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config: {}", path.display()))?;
+    content
+        .parse::<DocumentMut>()
+        .with_context(|| format!("Failed to parse config: {}", path.display()))
+}
+
+/// Validate the edited document against the config schema before writing it
+fn write_document(layer: ConfigLayer, path: &Path, doc: &DocumentMut) -> Result<()> {
+    let content = doc.to_string();
+    config::parse_partial_config(&content)
+        .with_context(|| format!("Refusing to write invalid {} config", layer))?;
+
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            fs::create_dir_all(parent)?;
+        }
+    }
+    fs::write(path, content).with_context(|| format!("Failed to write config: {}", path.display()))
+}
+
+fn split_key(key: &str) -> Result<Vec<&str>> {
+    let segments: Vec<&str> = key.split('.').collect();
+    if segments.iter().any(|s| s.is_empty()) {
+        anyhow::bail!("Invalid config key '{}'", key);
+    }
+    Ok(segments)
+}
+
+fn parse_value(raw: &str) -> toml_edit::Value {
+    raw.parse::<toml_edit::Value>()
+        .unwrap_or_else(|_| toml_edit::Value::from(raw))
+}
+
+fn remove_key(table: &mut dyn TableLike, segments: &[&str]) -> bool {
+    let (first, rest) = segments.split_first().expect("segments is non-empty");
+    if rest.is_empty() {
+        return table.remove(first).is_some();
+    }
+
+    let Some(child) = table.get_mut(first).and_then(|item| item.as_table_like_mut()) else {
+        return false;
+    };
+    let removed = remove_key(child, rest);
+    if removed && child.is_empty() {
+        table.remove(first);
+    }
+    removed
+}
+
+fn lookup<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    key.split('.').try_fold(value, |current, segment| current.get(segment))
+}
+
+/// Flatten nested tables into dotted keys (arrays are leaves)
+fn flatten(value: &Value) -> Vec<(String, Value)> {
+    fn walk(prefix: &str, value: &Value, out: &mut Vec<(String, Value)>) {
+        match value {
+            Value::Object(obj) => {
+                for (k, v) in obj {
+                    let key = if prefix.is_empty() {
+                        k.clone()
+                    } else {
+                        format!("{}.{}", prefix, k)
+                    };
+                    walk(&key, v, out);
+                }
+            }
+            Value::Null => {}
+            other => out.push((prefix.to_string(), other.clone())),
+        }
+    }
+
+    let mut out = Vec::new();
+    walk("", value, &mut out);
+    out
+}
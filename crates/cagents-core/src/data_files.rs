@@ -0,0 +1,295 @@
+// load structured template data from JSON/YAML/TOML files (`[variables.files]`)
+
+use crate::model::{ConfigLayer, ConfigOrigin, ProjectConfig};
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// One `[variables.files]` entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataFile {
+    /// Path as read (relative to the project that declared it)
+    pub path: PathBuf,
+    /// JSONPath-like selector (`$.scripts`, `services[*].name`)
+    pub select: Option<String>,
+}
+
+impl DataFile {
+    /// Parse `"path"` or `{ path = "...", select = "..." }`, resolving the path
+    /// against the directory of the project that declared it
+    pub fn from_spec(name: &str, spec: &Value, origin: Option<&ConfigOrigin>) -> Result<Self> {
+        let (path, select) = match spec {
+            Value::String(path) => (path.as_str(), None),
+            Value::Object(obj) => {
+                if let Some(key) = obj.keys().find(|k| *k != "path" && *k != "select") {
+                    anyhow::bail!("Unknown key '{}' in [variables.files] entry '{}'", key, name);
+                }
+                let path = obj
+                    .get("path")
+                    .and_then(|p| p.as_str())
+                    .ok_or_else(|| anyhow::anyhow!("[variables.files] entry '{}' is missing 'path'", name))?;
+                let select = obj.get("select").and_then(|s| s.as_str()).map(str::to_string);
+                (path, select)
+            }
+            _ => anyhow::bail!(
+                "[variables.files] entry '{}' must be a path or {{ path, select }}",
+                name
+            ),
+        };
+
+        Ok(Self {
+            path: project_dir(origin).join(path),
+            select,
+        })
+    }
+
+    /// Read the file and apply the selector
+    pub fn load(&self) -> Result<Value> {
+        let data = load_file(&self.path)?;
+        match &self.select {
+            Some(selector) => select(&data, selector)
+                .with_context(|| format!("Failed to select '{}' in {}", selector, self.path.display())),
+            None => Ok(data),
+        }
+    }
+}
+
+/// Every `[variables.files]` entry in the config, by variable name
+pub fn data_files(config: &ProjectConfig) -> Result<BTreeMap<String, DataFile>> {
+    let mut files = BTreeMap::new();
+    let Some(obj) = config
+        .variables
+        .as_ref()
+        .and_then(|v| v.files.as_ref())
+        .and_then(|f| f.as_object())
+    else {
+        return Ok(files);
+    };
+
+    for (name, spec) in obj {
+        let origin = config.variable_origins.files.get(name);
+        files.insert(name.clone(), DataFile::from_spec(name, spec, origin)?);
+    }
+    Ok(files)
+}
+
+/// Directory a data file path is relative to: the project containing the config
+/// file's `.cAGENTS/` directory, otherwise the project root
+pub(crate) fn project_dir(origin: Option<&ConfigOrigin>) -> PathBuf {
+    origin
+        .filter(|o| matches!(o.layer, ConfigLayer::Project | ConfigLayer::Local))
+        .and_then(|o| o.file.parent())
+        .filter(|dir| dir.file_name().is_some_and(|n| n == ".cAGENTS"))
+        .and_then(|dir| dir.parent())
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
+/// Parse a JSON, YAML or TOML file (by extension) into a JSON value
+pub fn load_file(path: &Path) -> Result<Value> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read data file: {}", path.display()))?;
+
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    match extension.as_str() {
+        "json" => serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse JSON: {}", path.display())),
+        "yaml" | "yml" => serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse YAML: {}", path.display())),
+        "toml" => {
+            let table: toml::Table = toml::from_str(&content)
+                .with_context(|| format!("Failed to parse TOML: {}", path.display()))?;
+            Ok(serde_json::to_value(table)?)
+        }
+        _ => anyhow::bail!(
+            "Unsupported data file '{}'. Expected .json, .yaml, .yml or .toml",
+            path.display()
+        ),
+    }
+}
+
+/// A step in a selector path
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+/// Select part of a value with a JSONPath-like expression
+///
+/// Supports `$` (optional), `.key`, `['key']`, `[0]` and `*` / `[*]`. A wildcard
+/// makes the result an array of every match.
+pub fn select(value: &Value, selector: &str) -> Result<Value> {
+    let segments = parse_selector(selector)?;
+    let has_wildcard = segments.contains(&Segment::Wildcard);
+
+    let mut current = vec![value];
+    for segment in &segments {
+        let mut next = Vec::new();
+        for v in current {
+            match segment {
+                Segment::Key(key) => match v.get(key.as_str()) {
+                    Some(child) => next.push(child),
+                    None if has_wildcard => {}
+                    None => anyhow::bail!("Key '{}' not found", key),
+                },
+                Segment::Index(index) => match v.get(*index) {
+                    Some(child) => next.push(child),
+                    None if has_wildcard => {}
+                    None => anyhow::bail!("Index {} out of range", index),
+                },
+                Segment::Wildcard => match v {
+                    Value::Array(items) => next.extend(items.iter()),
+                    Value::Object(obj) => next.extend(obj.values()),
+                    _ => {}
+                },
+            }
+        }
+        current = next;
+    }
+
+    if has_wildcard {
+        Ok(Value::Array(current.into_iter().cloned().collect()))
+    } else {
+        Ok(current.first().map(|v| (*v).clone()).unwrap_or(Value::Null))
+    }
+}
+
+fn parse_selector(selector: &str) -> Result<Vec<Segment>> {
+    let rest = selector.trim();
+    let rest = rest.strip_prefix('$').unwrap_or(rest);
+    let chars: Vec<char> = rest.chars().collect();
+
+    let mut segments = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '.' => i += 1,
+            '[' => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|&c| c == ']')
+                    .map(|p| i + p)
+                    .ok_or_else(|| anyhow::anyhow!("Unclosed '[' in selector '{}'", selector))?;
+                let inner: String = chars[i + 1..end].iter().collect();
+                let inner = inner.trim();
+
+                segments.push(if inner == "*" {
+                    Segment::Wildcard
+                } else if let Ok(index) = inner.parse::<usize>() {
+                    Segment::Index(index)
+                } else {
+                    let key = inner.trim_matches(|c| c == '\'' || c == '"');
+                    if key.is_empty() {
+                        anyhow::bail!("Empty brackets in selector '{}'", selector);
+                    }
+                    Segment::Key(key.to_string())
+                });
+                i = end + 1;
+            }
+            _ => {
+                let start = i;
+                while i < chars.len() && chars[i] != '.' && chars[i] != '[' {
+                    i += 1;
+                }
+                let key: String = chars[start..i].iter().collect();
+                segments.push(if key == "*" { Segment::Wildcard } else { Segment::Key(key) });
+            }
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Content fingerprint of every data file the config reads (missing files are skipped)
+///
+/// Stored in `.cAGENTS/.output-cache` so `status` can tell when outputs are stale.
+pub fn fingerprints(config: &ProjectConfig) -> Result<BTreeMap<PathBuf, String>> {
+    let mut fingerprints = BTreeMap::new();
+    for file in data_files(config)?.into_values() {
+        if let Some(fingerprint) = file_fingerprint(&file.path) {
+            fingerprints.insert(file.path, fingerprint);
+        }
+    }
+    Ok(fingerprints)
+}
+
+/// Hash of a file's content, `None` if it can't be read
+///
+/// 64-bit FNV-1a: fingerprints are persisted, so the hash must not change between
+/// Rust releases the way `DefaultHasher` may.
+pub fn file_fingerprint(path: &Path) -> Option<String> {
+    let content = fs::read(path).ok()?;
+    let hash = content.iter().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    Some(format!("{:016x}", hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn services() -> Value {
+        json!({
+            "scripts": {"build": "tsc", "test": "vitest"},
+            "services": [
+                {"name": "api", "owner": "platform"},
+                {"name": "web", "owner": "frontend"}
+            ]
+        })
+    }
+
+    #[test]
+    fn test_select_keys_and_indexes() {
+        let data = services();
+        assert_eq!(select(&data, "$.scripts.build").unwrap(), json!("tsc"));
+        assert_eq!(select(&data, "services[1].name").unwrap(), json!("web"));
+        assert_eq!(select(&data, "$['scripts']['test']").unwrap(), json!("vitest"));
+        assert!(select(&data, "$.missing").is_err());
+    }
+
+    #[test]
+    fn test_select_wildcards() {
+        let data = services();
+        assert_eq!(select(&data, "$.services[*].owner").unwrap(), json!(["platform", "frontend"]));
+        assert_eq!(select(&data, "scripts.*").unwrap(), json!(["tsc", "vitest"]));
+    }
+
+    #[test]
+    fn test_spec_path_is_relative_to_declaring_project() {
+        let origin = ConfigOrigin {
+            layer: ConfigLayer::Project,
+            file: PathBuf::from("packages/app/.cAGENTS/config.toml"),
+        };
+        let file = DataFile::from_spec("pkg", &json!({"path": "package.json", "select": "$.name"}), Some(&origin)).unwrap();
+        assert_eq!(file.path, PathBuf::from("packages/app/package.json"));
+        assert_eq!(file.select.as_deref(), Some("$.name"));
+
+        let root = ConfigOrigin {
+            layer: ConfigLayer::Project,
+            file: PathBuf::from(".cAGENTS/config.toml"),
+        };
+        let file = DataFile::from_spec("svc", &json!("services.yaml"), Some(&root)).unwrap();
+        assert_eq!(file.path, PathBuf::from("services.yaml"));
+    }
+
+    #[test]
+    fn test_file_fingerprint_is_stable() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("data.json");
+        fs::write(&path, "a").unwrap();
+        // Published FNV-1a test vector, so fingerprints survive toolchain upgrades
+        assert_eq!(file_fingerprint(&path).as_deref(), Some("af63dc4c8601ec8c"));
+        assert_eq!(file_fingerprint(&dir.path().join("missing.json")), None);
+    }
+}
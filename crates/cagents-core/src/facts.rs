@@ -0,0 +1,383 @@
+// built-in project facts exposed to templates as the `cagents.*` namespace
+
+use crate::loader::Rule;
+use crate::model::ProjectConfig;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+
+/// Template variable holding the facts
+pub const NAMESPACE: &str = "cagents";
+
+/// Marker files for detected languages, in the order they are reported
+const LANGUAGE_MARKERS: &[(&str, &[&str])] = &[
+    ("rust", &["Cargo.toml"]),
+    ("typescript", &["tsconfig.json"]),
+    ("javascript", &["package.json"]),
+    ("python", &["pyproject.toml", "setup.py", "requirements.txt", "Pipfile"]),
+    ("go", &["go.mod"]),
+    ("ruby", &["Gemfile"]),
+    ("java", &["pom.xml", "build.gradle"]),
+    ("kotlin", &["build.gradle.kts"]),
+    ("php", &["composer.json"]),
+    ("swift", &["Package.swift"]),
+    ("elixir", &["mix.exs"]),
+];
+
+/// Marker files (usually lockfiles) for detected package managers
+const PACKAGE_MANAGER_MARKERS: &[(&str, &[&str])] = &[
+    ("cargo", &["Cargo.toml"]),
+    ("pnpm", &["pnpm-lock.yaml", "pnpm-workspace.yaml"]),
+    ("yarn", &["yarn.lock"]),
+    ("bun", &["bun.lockb", "bun.lock"]),
+    ("npm", &["package-lock.json"]),
+    ("uv", &["uv.lock"]),
+    ("poetry", &["poetry.lock"]),
+    ("pipenv", &["Pipfile"]),
+    ("go", &["go.mod"]),
+    ("bundler", &["Gemfile"]),
+    ("composer", &["composer.json"]),
+];
+
+/// Whether any rule or custom target template uses the facts (`{{cagents.…}}`)
+pub fn referenced(config: &ProjectConfig, rules: &[Rule]) -> bool {
+    let prefix = format!("{}.", NAMESPACE);
+    let mut custom_templates = config
+        .output
+        .as_ref()
+        .and_then(|o| o.custom.as_ref())
+        .into_iter()
+        .flat_map(|custom| custom.values())
+        .flat_map(|spec| [&spec.header, &spec.footer, &spec.wrapper])
+        .flatten();
+
+    rules.iter().any(|rule| rule.body.contains(&prefix))
+        || custom_templates.any(|text| text.contains(&prefix))
+}
+
+/// Facts about the project root (current directory)
+///
+/// Missing values (no git repo, no remote) are `null` so templates can still reference them.
+/// Detection runs git and walks the workspace, so it happens once per project per process.
+pub fn detect() -> Value {
+    static DETECTED: OnceLock<Mutex<HashMap<PathBuf, Value>>> = OnceLock::new();
+
+    let root = std::env::current_dir().unwrap_or_default();
+    let mut detected = DETECTED
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    detected.entry(root).or_insert_with(detect_uncached).clone()
+}
+
+fn detect_uncached() -> Value {
+    json!({
+        "projectName": crate::init::detect_project_name().ok(),
+        "git": {
+            "branch": git(&["rev-parse", "--abbrev-ref", "HEAD"]),
+            "remote": git(&["remote", "get-url", "origin"]),
+            "defaultBranch": default_branch(),
+        },
+        "languages": detect_languages(Path::new(".")),
+        "packageManagers": detect_package_managers(Path::new(".")),
+        "workspaceMembers": workspace_members(Path::new(".")),
+        "outputDir": ".",
+        "target": Value::Null,
+        "package": package_manifest(Path::new(".")),
+    })
+}
+
+/// Set the output directory, its package and the target being rendered in `data.cagents`
+pub fn set_output(data: &mut Map<String, Value>, output_dir: &Path, target: &str) {
+    if let Some(facts) = data.get_mut(NAMESPACE).and_then(|v| v.as_object_mut()) {
+        facts.insert("outputDir".to_string(), Value::String(display_dir(output_dir)));
+        facts.insert("target".to_string(), Value::String(target.to_string()));
+        facts.insert("package".to_string(), package_manifest(output_dir));
+    }
+}
+
+/// Fields of the nearest package manifest at or above `dir` (package.json, Cargo.toml,
+/// pyproject.toml), or `null` if there is none
+///
+/// `{ name, version, description, scripts, manifest }`; `scripts` is only set for package.json.
+pub fn package_manifest(dir: &Path) -> Value {
+    let mut current = Some(dir);
+    while let Some(candidate) = current {
+        let candidate_dir = if candidate.as_os_str().is_empty() { Path::new(".") } else { candidate };
+        if let Some(package) = read_manifest(candidate_dir) {
+            return package;
+        }
+        current = candidate.parent();
+    }
+    Value::Null
+}
+
+fn read_manifest(dir: &Path) -> Option<Value> {
+    let manifest_path = |name: &str| display_dir(&dir.join(name));
+
+    if let Some(pkg) = read_json(&dir.join("package.json")) {
+        return Some(json!({
+            "name": pkg.get("name"),
+            "version": pkg.get("version"),
+            "description": pkg.get("description"),
+            "scripts": pkg.get("scripts"),
+            "manifest": manifest_path("package.json"),
+        }));
+    }
+
+    let read_toml = |name: &str| {
+        fs::read_to_string(dir.join(name))
+            .ok()
+            .and_then(|c| toml::from_str::<toml::Value>(&c).ok())
+            .and_then(|t| serde_json::to_value(t).ok())
+    };
+
+    let package = read_toml("Cargo.toml")
+        .and_then(|cargo| cargo.get("package").cloned())
+        .map(|p| (p, "Cargo.toml"))
+        .or_else(|| {
+            let pyproject = read_toml("pyproject.toml")?;
+            pyproject
+                .get("project")
+                .or_else(|| pyproject.pointer("/tool/poetry"))
+                .cloned()
+                .map(|p| (p, "pyproject.toml"))
+        });
+
+    package.map(|(p, name)| {
+        json!({
+            "name": p.get("name"),
+            "version": p.get("version"),
+            "description": p.get("description"),
+            "scripts": Value::Null,
+            "manifest": manifest_path(name),
+        })
+    })
+}
+
+/// Relative directory with forward slashes, "." for the root
+fn display_dir(dir: &Path) -> String {
+    let dir = dir.to_string_lossy().replace('\\', "/");
+    let dir = dir.trim_start_matches("./").trim_end_matches('/');
+    if dir.is_empty() { ".".to_string() } else { dir.to_string() }
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
+
+/// `origin/HEAD` if the remote has one, otherwise a local main/master branch
+fn default_branch() -> Option<String> {
+    if let Some(head) = git(&["symbolic-ref", "--short", "refs/remotes/origin/HEAD"]) {
+        return Some(head.trim_start_matches("origin/").to_string());
+    }
+    ["main", "master"]
+        .into_iter()
+        .find(|branch| git(&["rev-parse", "--verify", "--quiet", &format!("refs/heads/{}", branch)]).is_some())
+        .map(str::to_string)
+}
+
+pub fn detect_languages(dir: &Path) -> Vec<String> {
+    detect_markers(dir, LANGUAGE_MARKERS)
+}
+
+pub fn detect_package_managers(dir: &Path) -> Vec<String> {
+    let mut managers = detect_markers(dir, PACKAGE_MANAGER_MARKERS);
+
+    // A package.json without a lockfile: use its packageManager field, or npm
+    let js_managers = ["pnpm", "yarn", "bun", "npm"];
+    if dir.join("package.json").exists() && !managers.iter().any(|m| js_managers.contains(&m.as_str())) {
+        let declared = read_json(&dir.join("package.json"))
+            .and_then(|pkg| pkg.get("packageManager")?.as_str().map(str::to_string))
+            .and_then(|spec| spec.split('@').next().map(str::to_string))
+            .filter(|name| js_managers.contains(&name.as_str()));
+        managers.push(declared.unwrap_or_else(|| "npm".to_string()));
+    }
+
+    managers
+}
+
+fn detect_markers(dir: &Path, markers: &[(&str, &[&str])]) -> Vec<String> {
+    markers
+        .iter()
+        .filter(|(_, files)| files.iter().any(|f| dir.join(f).exists()))
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// Workspace member directories from Cargo.toml, pnpm-workspace.yaml and package.json
+/// `workspaces`, with glob patterns expanded
+pub fn workspace_members(dir: &Path) -> Vec<String> {
+    let mut patterns: Vec<String> = Vec::new();
+
+    if let Some(cargo) = fs::read_to_string(dir.join("Cargo.toml"))
+        .ok()
+        .and_then(|c| toml::from_str::<toml::Value>(&c).ok())
+    {
+        if let Some(members) = cargo.get("workspace").and_then(|w| w.get("members")).and_then(|m| m.as_array()) {
+            patterns.extend(members.iter().filter_map(|m| m.as_str().map(str::to_string)));
+        }
+    }
+
+    if let Some(pnpm) = fs::read_to_string(dir.join("pnpm-workspace.yaml"))
+        .ok()
+        .and_then(|c| serde_yaml::from_str::<Value>(&c).ok())
+    {
+        if let Some(packages) = pnpm.get("packages").and_then(|p| p.as_array()) {
+            patterns.extend(packages.iter().filter_map(|p| p.as_str().map(str::to_string)));
+        }
+    }
+
+    if let Some(pkg) = read_json(&dir.join("package.json")) {
+        let workspaces = pkg.get("workspaces").and_then(|w| match w {
+            Value::Array(items) => Some(items.clone()),
+            Value::Object(obj) => obj.get("packages").and_then(|p| p.as_array()).cloned(),
+            _ => None,
+        });
+        for workspace in workspaces.unwrap_or_default() {
+            if let Some(pattern) = workspace.as_str() {
+                patterns.push(pattern.to_string());
+            }
+        }
+    }
+
+    let (excludes, includes): (Vec<String>, Vec<String>) =
+        patterns.into_iter().partition(|p| p.starts_with('!'));
+    let excludes: Vec<String> = excludes.iter().map(|p| p.trim_start_matches('!').to_string()).collect();
+
+    let mut members: Vec<String> = includes
+        .iter()
+        .flat_map(|pattern| expand_dir_pattern(dir, pattern))
+        .filter(|member| !excludes.iter().any(|ex| glob_matches(ex, member)))
+        .collect();
+    members.sort();
+    members.dedup();
+    members
+}
+
+/// Directories under `dir` matching a workspace pattern (`crates/*`, `packages/**`, `apps/web`)
+fn expand_dir_pattern(dir: &Path, pattern: &str) -> Vec<String> {
+    let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+    if !pattern.contains('*') {
+        return if dir.join(pattern).is_dir() { vec![pattern.to_string()] } else { Vec::new() };
+    }
+
+    let max_depth = if pattern.contains("**") { usize::MAX } else { pattern.split('/').count() };
+    walkdir::WalkDir::new(dir)
+        .min_depth(1)
+        .max_depth(max_depth)
+        .into_iter()
+        .filter_entry(|e| {
+            let name = e.file_name().to_string_lossy();
+            !name.starts_with('.') && name != "node_modules" && name != "target"
+        })
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_dir())
+        .filter_map(|e| {
+            let rel: PathBuf = e.path().strip_prefix(dir).ok()?.to_path_buf();
+            let rel = rel.to_string_lossy().replace('\\', "/");
+            glob_matches(pattern, &rel).then_some(rel)
+        })
+        .collect()
+}
+
+fn glob_matches(pattern: &str, path: &str) -> bool {
+    globset::GlobBuilder::new(pattern.trim_start_matches("./").trim_end_matches('/'))
+        .literal_separator(true)
+        .build()
+        .map(|glob| glob.compile_matcher().is_match(path))
+        .unwrap_or(false)
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    fs::read_to_string(path).ok().and_then(|c| serde_json::from_str(&c).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_workspace_members_expand_globs() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        fs::write(root.join("Cargo.toml"), "[workspace]\nmembers = [\"crates/*\"]\n").unwrap();
+        fs::write(root.join("pnpm-workspace.yaml"), "packages:\n  - 'apps/*'\n  - '!apps/legacy'\n").unwrap();
+        for dir in ["crates/core", "crates/cli", "apps/web", "apps/legacy"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+        }
+
+        assert_eq!(
+            workspace_members(root),
+            vec!["apps/web", "crates/cli", "crates/core"]
+        );
+    }
+
+    #[test]
+    fn test_languages_and_package_managers() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        fs::write(root.join("package.json"), r#"{"packageManager": "yarn@4.1.0"}"#).unwrap();
+        fs::write(root.join("tsconfig.json"), "{}").unwrap();
+
+        assert_eq!(detect_languages(root), vec!["typescript", "javascript"]);
+        assert_eq!(detect_package_managers(root), vec!["yarn"]);
+    }
+
+    #[test]
+    fn test_package_manifest_uses_nearest() {
+        let tmp = TempDir::new().unwrap();
+        let root = tmp.path();
+        fs::create_dir_all(root.join("packages/api/src")).unwrap();
+        fs::write(
+            root.join("packages/api/package.json"),
+            r#"{"name": "@acme/api", "version": "1.2.0", "scripts": {"test": "vitest"}}"#,
+        )
+        .unwrap();
+
+        let package = package_manifest(&root.join("packages/api/src"));
+        assert_eq!(package["name"], "@acme/api");
+        assert_eq!(package["scripts"]["test"], "vitest");
+        assert!(package["manifest"].as_str().unwrap().ends_with("packages/api/package.json"));
+
+        assert_eq!(package_manifest(&root.join("packages")), Value::Null);
+    }
+
+    #[test]
+    fn test_set_output() {
+        let mut data = Map::new();
+        data.insert(NAMESPACE.to_string(), json!({"outputDir": ".", "target": null}));
+
+        set_output(&mut data, Path::new("./packages/app"), "claude-md");
+        assert_eq!(data[NAMESPACE]["outputDir"], "packages/app");
+        assert_eq!(data[NAMESPACE]["target"], "claude-md");
+    }
+
+    #[test]
+    fn test_referenced_by_rules_or_custom_targets() {
+        let rule = |body: &str| Rule {
+            frontmatter: Default::default(),
+            body: body.to_string(),
+            path: PathBuf::from("rule.md"),
+        };
+        let config = |extra: &str| -> ProjectConfig {
+            toml::from_str(&format!("[paths]\ntemplatesDir = \"t\"\noutputRoot = \".\"\n{}", extra)).unwrap()
+        };
+
+        let plain = config("");
+        assert!(!referenced(&plain, &[rule("Run cagents build")]));
+        assert!(referenced(&plain, &[rule("Run cagents build"), rule("{{cagents.target}}")]));
+
+        let custom = config("[output.custom.guide]\nfilename = \"GUIDE.md\"\nfooter = \"In {{cagents.outputDir}}\"\n");
+        assert!(referenced(&custom, &[]));
+    }
+}
@@ -1,5 +1,7 @@
 // Git integration helpers
 
+use crate::config::LoadOptions;
+use crate::targets::TargetRegistry;
 use anyhow::Result;
 use owo_colors::OwoColorize;
 use std::fs::{self, OpenOptions};
@@ -7,37 +9,25 @@ use std::io::Write;
 use std::path::PathBuf;
 
 /// Add generated output files to .gitignore based on config
-pub fn ignore_outputs() -> Result<()> {
+pub fn ignore_outputs(options: &LoadOptions) -> Result<()> {
     let gitignore_path = PathBuf::from(".gitignore");
 
     // Load config to check output targets
-    let config = crate::config::load_config_with_precedence().ok();
+    let config = crate::config::load_config(options).ok();
+    let registry = config
+        .as_ref()
+        .map(TargetRegistry::for_config)
+        .unwrap_or_else(TargetRegistry::builtin);
     let output_targets = config
         .as_ref()
-        .and_then(|c| c.output.as_ref())
-        .and_then(|o| o.targets.as_ref())
-        .cloned()
-        .unwrap_or_else(|| vec!["agents-md".to_string()]);
+        .map(crate::targets::configured_targets)
+        .unwrap_or_else(|| vec![crate::targets::DEFAULT_TARGET.to_string()]);
 
     // Build patterns based on configured outputs
     let mut patterns = vec!["# cAGENTS generated files".to_string()];
 
-    for target in &output_targets {
-        match target.as_str() {
-            "agents-md" => {
-                patterns.push("AGENTS.md".to_string());
-                patterns.push("**/AGENTS.md".to_string());
-            }
-            "claude-md" => {
-                patterns.push("CLAUDE.md".to_string());
-                patterns.push("**/CLAUDE.md".to_string());
-            }
-            "cursorrules" => {
-                patterns.push(".cursorrules".to_string());
-                patterns.push("**/.cursorrules".to_string());
-            }
-            _ => {}
-        }
+    for target in output_targets.iter().filter_map(|t| registry.get(t)) {
+        patterns.extend(target.gitignore_patterns());
     }
 
     // Check if .gitignore exists
@@ -95,7 +85,7 @@ pub fn ignore_outputs() -> Result<()> {
 }
 
 /// Remove all cAGENTS output patterns from .gitignore
-pub fn unignore_outputs() -> Result<()> {
+pub fn unignore_outputs(options: &LoadOptions) -> Result<()> {
     let gitignore_path = PathBuf::from(".gitignore");
 
     if !gitignore_path.exists() {
@@ -106,13 +96,15 @@ pub fn unignore_outputs() -> Result<()> {
     let content = fs::read_to_string(&gitignore_path)?;
     let lines: Vec<&str> = content.lines().collect();
 
-    // Filter out all cAGENTS generated file patterns
+    // Filter out all cAGENTS generated file patterns (for any known target)
+    let registry = crate::config::load_config(options)
+        .map(|c| TargetRegistry::for_config(&c))
+        .unwrap_or_else(|_| TargetRegistry::builtin());
+    let file_names: Vec<&str> = registry.iter().map(|t| t.file_name()).collect();
     let filtered: Vec<&str> = lines
         .into_iter()
         .filter(|line| {
-            !line.contains("AGENTS.md")
-            && !line.contains("CLAUDE.md")
-            && !line.contains(".cursorrules")
+            !file_names.iter().any(|name| line.contains(name))
             && !line.contains("cAGENTS generated")
         })
         .collect();
@@ -143,7 +135,7 @@ mod tests {
         let tmp = TempDir::new().unwrap();
         let _guard = TestDirGuard::new(tmp.path());
 
-        ignore_outputs().unwrap();
+        ignore_outputs(&LoadOptions::default()).unwrap();
 
         let content = fs::read_to_string(".gitignore").unwrap();
         assert!(content.contains("AGENTS.md"));
@@ -159,7 +151,7 @@ mod tests {
         // Create existing .gitignore
         fs::write(".gitignore", "node_modules\n").unwrap();
 
-        ignore_outputs().unwrap();
+        ignore_outputs(&LoadOptions::default()).unwrap();
 
         let content = fs::read_to_string(".gitignore").unwrap();
         assert!(content.contains("node_modules"));
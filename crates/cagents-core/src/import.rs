@@ -1,5 +1,6 @@
 // Import from various rule formats (Cursor, Claude)
 
+use crate::init::CAGENTS_GITIGNORE;
 use anyhow::{Context, Result};
 use std::fs;
 use std::path::PathBuf;
@@ -88,7 +89,7 @@ project = "imported-from-cursor"
         println!("   ✓ Created template: {} (from {})", template_name, location.display());
     }
 
-    fs::write(cagents_dir.join(".gitignore"), "config.local.toml\n**.local.*\n.output-cache\n")?;
+    fs::write(cagents_dir.join(".gitignore"), CAGENTS_GITIGNORE)?;
 
     // Remove original files after successful import
     for location in &locations {
@@ -222,7 +223,7 @@ targets = ["agentsmd", "cursor"]
         println!("  This generates AGENTS.md at common parent directory.");
     }
 
-    fs::write(cagents_dir.join(".gitignore"), "config.local.toml\n**.local.*\n.output-cache\n")?;
+    fs::write(cagents_dir.join(".gitignore"), CAGENTS_GITIGNORE)?;
 
     // Remove original .cursor/rules directory after successful import
     if PathBuf::from(".cursor/rules").exists() {
@@ -412,7 +413,7 @@ project = "imported-from-agents-md"
         println!("   ✓ Created template: {} (from {})", template_name, location.display());
     }
 
-    fs::write(cagents_dir.join(".gitignore"), "config.local.toml\n**.local.*\n.output-cache\n")?;
+    fs::write(cagents_dir.join(".gitignore"), CAGENTS_GITIGNORE)?;
 
     // Remove original files after successful import
     for location in &locations {
@@ -535,7 +536,7 @@ project = "imported-from-claude-md"
         println!("   ✓ Created template: {} (from {})", template_name, location.display());
     }
 
-    fs::write(cagents_dir.join(".gitignore"), "config.local.toml\n**.local.*\n.output-cache\n")?;
+    fs::write(cagents_dir.join(".gitignore"), CAGENTS_GITIGNORE)?;
 
     // Remove original files after successful import
     for location in &locations {
@@ -803,7 +804,7 @@ order: {}
         }
     }
 
-    fs::write(cagents_dir.join(".gitignore"), "config.local.toml\n**.local.*\n.output-cache\n")?;
+    fs::write(cagents_dir.join(".gitignore"), CAGENTS_GITIGNORE)?;
 
     // Remove original files after successful import
     for format in formats {
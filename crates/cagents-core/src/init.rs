@@ -4,6 +4,9 @@ use anyhow::{Context, Result};
 use std::fs;
 use std::path::PathBuf;
 
+/// Contents of `.cAGENTS/.gitignore`: local config, the output tracking cache and the command cache
+pub const CAGENTS_GITIGNORE: &str = "config.local.toml\n**.local.*\n.output-cache\n.command-cache\n";
+
 /// Detect project information from git and filesystem
 #[derive(Debug, Clone)]
 pub struct ProjectInfo {
@@ -47,7 +50,7 @@ impl ProjectInfo {
 }
 
 /// Detect project name from various sources
-fn detect_project_name() -> Result<String> {
+pub fn detect_project_name() -> Result<String> {
     // 1. Try git remote
     if let Ok(output) = std::process::Command::new("git")
         .args(["remote", "get-url", "origin"])
@@ -144,7 +147,7 @@ pub fn init_basic(info: &ProjectInfo, force: bool, _backup: bool) -> Result<()>
         .context("Failed to write config.toml")?;
 
     // 4. Generate .gitignore
-    let gitignore_content = "# cAGENTS local config\nconfig.local.toml\n**.local.*\n.output-cache\n";
+    let gitignore_content = format!("# cAGENTS local config\n{}", CAGENTS_GITIGNORE);
     fs::write(cagents_dir.join(".gitignore"), gitignore_content)
         .context("Failed to write .gitignore")?;
 
@@ -363,7 +366,7 @@ order: 1
 "#, content);
 
     fs::write(templates_dir.join("agents-root.md"), template)?;
-    fs::write(cagents_dir.join(".gitignore"), "config.local.toml\n**.local.*\n.output-cache\n")?;
+    fs::write(cagents_dir.join(".gitignore"), CAGENTS_GITIGNORE)?;
 
     println!("✓ Migrated AGENTS.md to cAGENTS!");
     println!();
@@ -507,7 +510,7 @@ fn migrate_multiple_agents_md(info: &ProjectInfo, force: bool, backup: bool) ->
         println!("   ✓ Created template: {} (from {})", template_name, location.display());
     }
 
-    fs::write(cagents_dir.join(".gitignore"), "config.local.toml\n**.local.*\n.output-cache\n")?;
+    fs::write(cagents_dir.join(".gitignore"), CAGENTS_GITIGNORE)?;
 
     // Remove original AGENTS.md files after successful migration
     for location in &info.agents_md_locations {
@@ -1,9 +1,18 @@
 pub mod config;
+pub mod command_cache;
+pub mod config_edit;
+pub mod data_files;
+pub mod facts;
 pub mod model;
 pub mod loader;
 pub mod planner;
 pub mod render;
 pub mod merge;
+pub mod matrix;
+pub mod nested;
+pub mod variables;
+pub mod targets;
+pub mod output_diff;
 pub mod writers;
 pub mod adapters;
 pub mod init;
@@ -13,8 +22,7 @@ pub mod lint;
 pub mod helpers;
 
 use anyhow::{Context, Result};
-use std::path::PathBuf;
-use std::process::Command;
+use std::path::{Path, PathBuf};
 
 /// Initialize cAGENTS in the current project
 pub fn cmd_init(preset: &str, force: bool, dry_run: bool, backup: bool) -> Result<()> {
@@ -84,79 +92,38 @@ pub fn cmd_init(preset: &str, force: bool, dry_run: bool, backup: bool) -> Resul
     // Only basic preset supported now (preset parameter ignored)
     init::init_basic(&info, force, backup)?;
 
-    Ok(())
-}
-
-/// Execute a shell command and return its stdout as a trimmed string
-fn execute_command(shell: &str, command: &str) -> Result<String> {
-    let output = Command::new(shell)
-        .arg("-c")
-        .arg(command)
-        .output()
-        .with_context(|| format!("Failed to execute command: {}", command))?;
-
-    if !output.status.success() {
-        anyhow::bail!(
-            "Command failed with exit code {:?}: {}",
-            output.status.code(),
-            String::from_utf8_lossy(&output.stderr)
-        );
+    // Ask for required variables declared in [variables.schema] (e.g. by the user config)
+    if interactive::is_interactive() {
+        let load = config::LoadOptions::from_env();
+        let config = config::load_config(&load)?;
+        let mut resolved = variables::resolve_config_variables(&config)?;
+        let missing = resolved.apply_schema(&config)?;
+        if !missing.is_empty() {
+            println!();
+            interactive::print_section("▸", "Required variables");
+            prompt_required_variables(&config, &missing, &load)?;
+        }
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    Ok(stdout.trim().to_string())
+    Ok(())
 }
 
-fn build_template_data_map(config: &crate::model::ProjectConfig) -> serde_json::Map<String, serde_json::Value> {
-    let mut data = serde_json::Map::new();
-
-    if let Some(vars) = &config.variables {
-        if let Some(static_vars) = &vars.static_ {
-            if let Some(obj) = static_vars.as_object() {
-                for (key, value) in obj {
-                    data.insert(key.clone(), value.clone());
-                }
-            }
-        }
-
-        if let Some(command_vars) = &vars.command {
-            if let Some(obj) = command_vars.as_object() {
-                let shell = config
-                    .execution
-                    .as_ref()
-                    .and_then(|e| e.shell.as_deref())
-                    .unwrap_or("bash");
-
-                for (key, value) in obj {
-                    if let Some(command) = value.as_str() {
-                        match execute_command(shell, command) {
-                            Ok(output) => {
-                                data.insert(key.clone(), serde_json::Value::String(output));
-                            }
-                            Err(e) => {
-                                eprintln!(
-                                    "Warning: Failed to execute command '{}': {}",
-                                    command, e
-                                );
-                                data.insert(
-                                    key.clone(),
-                                    serde_json::Value::String(String::new()),
-                                );
-                            }
-                        }
-                    }
-                }
-            }
-        }
+fn build_template_data_map(
+    config: &crate::model::ProjectConfig,
+    rules: &[loader::Rule],
+) -> Result<serde_json::Map<String, serde_json::Value>> {
+    let mut resolved = variables::resolve_config_variables(config)?;
+    if facts::referenced(config, rules) {
+        insert_facts(&mut resolved);
     }
-
-    data
+    Ok(resolved.to_data())
 }
 
 fn merge_rule_data(
     base_data: &serde_json::Map<String, serde_json::Value>,
     rule: &loader::Rule,
-) -> serde_json::Value {
+    commands: &variables::RuleCommands,
+) -> Result<serde_json::Value> {
     let mut merged = base_data.clone();
 
     if let Some(vars) = &rule.frontmatter.vars {
@@ -167,7 +134,12 @@ fn merge_rule_data(
         }
     }
 
-    serde_json::Value::Object(merged)
+    // The rule's command variables only run now that the rule is rendering
+    for (key, var) in commands.resolve(rule)?.iter() {
+        merged.insert(key.clone(), var.value.clone());
+    }
+
+    Ok(serde_json::Value::Object(merged))
 }
 
 fn resolve_engine_spec<'a>(
@@ -193,9 +165,10 @@ fn render_rule_with_command(
     rule: &loader::Rule,
     base_data: &serde_json::Map<String, serde_json::Value>,
     defaults: Option<&crate::model::Defaults>,
+    commands: &variables::RuleCommands,
 ) -> Result<String> {
     let engine_spec = resolve_engine_spec(rule, defaults)?;
-    let data_value = merge_rule_data(base_data, rule);
+    let data_value = merge_rule_data(base_data, rule, commands)?;
 
     // Check if using builtin engine
     if engine_spec.starts_with("builtin:") {
@@ -247,162 +220,396 @@ fn render_rule_with_command(
     }
 }
 
-/// Build AGENTS.md and optional exports
-///
-/// M1 Slice 1 implementation:
-/// - Loads config from .cAGENTS/config.toml (no precedence yet)
-/// - Discovers templates in templatesDir
-/// - Filters rules: no when clause = always applies, otherwise filtered by globs
-/// - Renders with Handlebars using static variables only
-/// - Merges by simple concatenation (no per-section strategies)
-/// - Writes single root AGENTS.md
-///
-/// Not yet implemented:
-/// - env/role/language filtering (params ignored)
-/// - Command variable execution
-/// - Nested directory outputs
-/// - Config precedence (local/user configs)
-/// - Custom output path (--out param ignored)
-/// - Dry run mode
+/// Options for `cagents build`
+#[derive(Debug, Clone, Default)]
+pub struct BuildOptions {
+    /// Write the whole output tree under this directory instead of the project,
+    /// without cleanup or output tracking
+    pub out: Option<String>,
+    /// Print diffs of what would change instead of writing anything
+    pub dry_run: bool,
+    /// Fail if any output is stale, missing or extra, without writing anything
+    pub check: bool,
+    /// With `check`, print the report as JSON
+    pub json: bool,
+    /// Variables in KEY=VALUE format, overriding config variables
+    pub vars: Vec<String>,
+    /// Which config file and profile to load
+    pub load: config::LoadOptions,
+}
+
+/// Build with only `--out` and `--dry-run` set (see [`cmd_build_with_options`])
 pub fn cmd_build(
-    _out: Option<String>,
-    _dry_run: bool,
+    out: Option<String>,
+    dry_run: bool,
 ) -> Result<()> {
-    use owo_colors::OwoColorize;
+    cmd_build_with_options(BuildOptions {
+        out,
+        dry_run,
+        ..Default::default()
+    })
+}
 
-    // 1. Load config with precedence (user < project < local)
-    let config = config::load_config_with_precedence()?;
+/// A rendered output file waiting to be written
+struct RenderedFile {
+    path: PathBuf,
+    content: String,
+    /// Write a symlink to this path (relative to the file's directory) instead of `content`
+    link: Option<PathBuf>,
+}
 
-    // 2. Discover all rule templates
-    let base_dir = PathBuf::from(".cAGENTS");
-    let all_rules = loader::discover_rules(&config, &base_dir)?;
+impl RenderedFile {
+    fn new(path: PathBuf, content: String) -> Self {
+        Self { path, content, link: None }
+    }
+
+    /// The file as it would be on disk, with symlinks described like `output_diff` reads them
+    fn expected(&self) -> String {
+        match &self.link {
+            Some(target) => output_diff::describe_link(target),
+            None => self.content.clone(),
+        }
+    }
 
-    // 3. Build template data from config variables
-    let base_data = build_template_data_map(&config);
+    /// Write the file or symlink, replacing whatever is at its path
+    fn write(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
 
-    // 4. Build context from config variables (for use in when clauses)
-    let mut context_variables = std::collections::HashMap::new();
+        // Never write through an old symlink (e.g. CLAUDE.md -> AGENTS.md)
+        let is_link = std::fs::symlink_metadata(&self.path).is_ok_and(|m| m.file_type().is_symlink());
+        if is_link || (self.link.is_some() && self.path.exists()) {
+            std::fs::remove_file(&self.path)
+                .with_context(|| format!("Failed to replace {}", self.path.display()))?;
+        }
 
-    // Add config variables to context (for use in when clauses)
-    for (key, value) in &base_data {
-        if let Some(s) = value.as_str() {
-            context_variables.insert(key.clone(), s.to_string());
+        match &self.link {
+            Some(target) => symlink_file(target, &self.path)
+                .with_context(|| format!("Failed to link {} to {}", self.path.display(), target.display())),
+            None => std::fs::write(&self.path, &self.content)
+                .with_context(|| format!("Failed to write {}", self.path.display())),
         }
     }
+}
 
-    // Create context from variables
-    let context = planner::BuildContext::from_variables(context_variables);
+#[cfg(unix)]
+fn symlink_file(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
 
-    // 5. Plan outputs (group rules by target directories)
-    let project_root = PathBuf::from(&config.paths.output_root);
-    let outputs = planner::plan_outputs(&all_rules, &context, &project_root)?;
+#[cfg(windows)]
+fn symlink_file(target: &Path, link: &Path) -> std::io::Result<()> {
+    std::os::windows::fs::symlink_file(target, link)
+}
+
+/// Build with explicit options (`--var`, matrix variants)
+pub fn cmd_build_with_options(options: BuildOptions) -> Result<()> {
+    use owo_colors::OwoColorize;
+
+    // 1. Load config with precedence (user < project < local)
+    let config = config::load_config(&options.load)?;
+
+    // 2. Discover all rule templates
+    let all_rules = nested::discover_project_rules(&config, &options.load)?;
+
+    // 3. Resolve config variables, overridden by --var; matrix variables are checked
+    //    against the schema once each variant sets them
+    let cli_vars = parse_var_args(&options.vars)?;
+    let read_only = options.dry_run || options.check || options.out.is_some();
+    let matrix_vars: Vec<String> = config
+        .matrix
+        .as_ref()
+        .map(|m| m.variables.keys().cloned().collect())
+        .unwrap_or_default();
+    let mut base_vars = resolve_variables_except(&config, &cli_vars, read_only, &matrix_vars, &options.load)?;
+    if facts::referenced(&config, &all_rules) {
+        insert_facts(&mut base_vars);
+    }
+
+    // 4. Expand the variable matrix (a single variant without [matrix])
+    let pinned: std::collections::HashMap<String, String> = cli_vars
+        .iter()
+        .filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string())))
+        .collect();
+    let variants = matrix::expand(config.matrix.as_ref(), &config.paths.output_root, &pinned)?;
+    let unbuilt = matrix::unbuilt(config.matrix.as_ref(), &config.paths.output_root, &pinned)?;
 
     let defaults = config.defaults.as_ref();
+    let commands = match read_only {
+        true => variables::RuleCommands::with_runner(variables::CommandRunner::read_only(&config)),
+        false => variables::RuleCommands::new(&config),
+    };
 
-    // Get output targets from config (default to ["agents-md"])
-    let output_targets = config
+    let registry = targets::TargetRegistry::for_config(&config);
+    let output_targets = targets::configured_targets(&config);
+    let claude_mode = config
         .output
         .as_ref()
-        .and_then(|o| o.targets.as_ref())
-        .cloned()
-        .unwrap_or_else(|| vec!["agents-md".to_string()]);
+        .and_then(|o| o.claude_md.as_ref())
+        .and_then(|c| c.mode)
+        .unwrap_or_default();
+    let writes_agents_md = output_targets.iter().any(|t| t == "agents-md");
+
+    // 5. Plan and render every variant in memory before touching the filesystem
+    let project_root = PathBuf::from(&config.paths.output_root);
+    let mut current_output_paths: Vec<PathBuf> = Vec::new();
+    let mut rendered_dirs: Vec<(String, Vec<RenderedFile>)> = Vec::new();
+
+    for variant in &variants {
+        // Matrix values override config and CLI data for this variant
+        let mut variant_vars = base_vars.clone();
+        for (key, value) in &variant.variables {
+            let source = variables::VariableSource::new(variables::VariableKind::Matrix);
+            variant_vars.insert(
+                key.clone(),
+                variables::ResolvedVariable::new(serde_json::Value::String(value.clone()), source),
+            );
+        }
+        let missing = variant_vars.apply_schema(&config)?;
+        if !missing.is_empty() {
+            return Err(missing_variables_error(&config, &missing));
+        }
+        let data = variant_vars.to_data();
+
+        // Build context from variables (for use in when clauses)
+        let context = context_from_data(&data);
+
+        // Plan outputs (group rules by target directories)
+        let outputs = planner::plan_outputs(&all_rules, &context, &project_root)?;
+
+        // Per-rule files already written by an earlier directory of this variant;
+        // directories go in path order so "earlier" is the same on every build
+        let mut rule_files: std::collections::HashMap<PathBuf, &loader::Rule> = std::collections::HashMap::new();
+        let mut outputs: Vec<_> = outputs.iter().collect();
+        outputs.sort_by(|a, b| a.0.cmp(b.0));
+
+        for (target_dir, rules) in outputs {
+            if !current_output_paths.contains(target_dir) {
+                current_output_paths.push(target_dir.clone());
+            }
+
+            // Per-directory variables sit above config but below --var and matrix values
+            let mut dir_data = data.clone();
+            for (key, var) in variables::resolve_dir_variables(target_dir)?.iter() {
+                if !cli_vars.contains_key(key) && !variant.variables.contains_key(key) {
+                    dir_data.insert(key.clone(), var.value.clone());
+                }
+            }
+            let dir_context = context_from_data(&dir_data);
+
+            let output_dir = variant.output_root.join(target_dir);
+            let mut files = Vec::new();
+
+            // Rules in this directory's AGENTS.md, for claude-md's import and symlink modes
+            let agents_context = target_context(&dir_context, "agents-md");
+            let agents_rules: Vec<&loader::Rule> = rules
+                .iter()
+                .filter(|rule| agents_context.matches_rule(rule))
+                .collect();
+
+            for target in &output_targets {
+                let Some(writer) = registry.get(target) else {
+                    eprintln!("  Warning: Unknown output target '{}' - skipping", target);
+                    continue;
+                };
+                if writer.root_only() && target_dir != &PathBuf::from(".") {
+                    continue;
+                }
+
+                // Create context with current target for filtering
+                let target_context = target_context(&dir_context, target);
+
+                // Filter rules for this specific target
+                let target_rules: Vec<&loader::Rule> = rules
+                    .iter()
+                    .filter(|rule| target_context.matches_rule(rule))
+                    .collect();
+
+                if target_rules.is_empty() {
+                    continue; // Skip this target if no rules apply
+                }
+
+                // Render rules for this target
+                let mut target_data = dir_data.clone();
+                facts::set_output(&mut target_data, target_dir, target);
+
+                // Per-rule targets write each rule once, using the first directory it renders
+                // for; rules without their own file are merged as usual
+                let mut merged_rules = Vec::new();
+                let mut own_file_rules = Vec::new();
+                for rule in &target_rules {
+                    let Some(rule_file) = writer.per_rule().then(|| writer.rule_file(rule)).flatten() else {
+                        merged_rules.push(*rule);
+                        continue;
+                    };
+                    own_file_rules.push(*rule);
+                    let file_name = rule_file
+                        .file_name()
+                        .map(|n| variant.file_name(&n.to_string_lossy()))
+                        .unwrap_or_default();
+                    let path = variant.output_root.join(rule_file.with_file_name(file_name));
+                    if let Some(first) = rule_files.get(&path) {
+                        if !targets::same_rule(first, rule) {
+                            anyhow::bail!(
+                                "Rules {} and {} both write {}; rename one of them",
+                                targets::describe_rule(first),
+                                targets::describe_rule(rule),
+                                output_diff::normalize(&path).display()
+                            );
+                        }
+                        continue;
+                    }
+                    rule_files.insert(path.clone(), *rule);
+
+                    let rendered = render_rule_with_command(rule, &target_data, defaults, &commands)?;
+                    files.push(RenderedFile::new(path, writer.format_rule(rule, &rendered)));
+                }
+
+                // claude-md can point at this directory's AGENTS.md instead of repeating its rules
+                let mut import = None;
+                let imports_agents_md = target == "claude-md" && claude_mode != model::ClaudeMdMode::Full;
+                if imports_agents_md && writes_agents_md && !agents_rules.is_empty() {
+                    merged_rules.retain(|rule| !agents_rules.iter().any(|r| std::ptr::eq(*r, *rule)));
+                    import = Some(variant.file_name(writers::agents_md::FILE_NAME));
+                }
+
+                let index = writer.rule_index(&own_file_rules);
+                let output_path = output_dir.join(variant.file_name(writer.file_name()));
+                if let Some(agents_file) = &import {
+                    if claude_mode == model::ClaudeMdMode::Symlink && merged_rules.is_empty() && index.is_empty() {
+                        files.push(RenderedFile {
+                            path: output_path,
+                            content: String::new(),
+                            link: Some(PathBuf::from(agents_file)),
+                        });
+                        continue;
+                    }
+                } else if merged_rules.is_empty() && index.is_empty() {
+                    continue;
+                }
 
-    // 6. Cleanup old files before writing new ones
-    let current_output_paths: Vec<PathBuf> = outputs.keys().cloned().collect();
+                let mut target_rendered_bodies = Vec::new();
+                for rule in &merged_rules {
+                    let rendered = render_rule_with_command(rule, &target_data, defaults, &commands)?;
+                    target_rendered_bodies.push(rendered);
+                }
 
-    // Cleanup old AGENTS.md files from directories no longer in plan
-    let dir_cleaned_count = writers::agents_md::cleanup_old_outputs(&current_output_paths)?;
+                // Merge for this target
+                let mut target_merged = merge::merge_rule_bodies(&target_rendered_bodies)?;
+                target_merged.push_str(if target_merged.is_empty() { index.trim_start() } else { &index });
+                if let Some(agents_file) = &import {
+                    target_merged = writers::claude_md::format_import(agents_file, &target_merged);
+                }
 
-    // Cleanup output files for targets that were removed from config
-    let target_cleaned_count = writers::agents_md::cleanup_old_target_files(&output_targets, &project_root)?;
+                files.push(RenderedFile::new(
+                    output_path,
+                    writer.render(&target_merged, &serde_json::Value::Object(target_data))?,
+                ));
+            }
 
-    let total_cleaned = dir_cleaned_count + target_cleaned_count;
+            let label = match variant.label() {
+                Some(label) => format!("{} ({})", target_dir.display(), label),
+                None => target_dir.display().to_string(),
+            };
+            rendered_dirs.push((label, files));
+        }
+    }
+
+    // --out stages the whole tree in another directory, leaving the working tree
+    // and the output cache alone
+    let staging = options.out.as_deref().map(PathBuf::from);
+    if let Some(out) = &staging {
+        for file in rendered_dirs.iter_mut().flat_map(|(_, files)| files) {
+            file.path = staged_path(out, &file.path)?;
+        }
+    }
+
+    let current_files: Vec<PathBuf> = rendered_dirs
+        .iter()
+        .flat_map(|(_, files)| files.iter().map(|f| output_diff::normalize(&f.path)))
+        .collect();
+
+    // 6. Find old files to clean up: AGENTS.md in directories no longer in the plan,
+    //    files of targets removed from config, and anything else the previous build
+    //    wrote that this build won't
+    let mut removals = Vec::new();
+    if staging.is_none() {
+        removals.extend(writers::agents_md::old_outputs(&current_output_paths)?);
+        removals.extend(writers::agents_md::old_target_files(&output_targets, &project_root, &registry)?);
+        removals.extend(writers::agents_md::stale_files(&current_files)?);
+    }
+    let written: std::collections::HashSet<&PathBuf> = current_files.iter().collect();
+    let mut removals: Vec<PathBuf> = removals.iter().map(|p| output_diff::normalize(p)).collect();
+    // Outputs of matrix variants skipped by a pinned --var are kept
+    removals.retain(|path| !written.contains(path) && !unbuilt.iter().any(|v| v.owns(path)));
+    removals.sort();
+    removals.dedup();
+
+    if options.check {
+        return check_outputs(&rendered_dirs, &removals, options.json);
+    }
+    if options.dry_run {
+        return print_dry_run(&rendered_dirs, &removals);
+    }
+
+    let total_cleaned = writers::agents_md::remove_outputs(&removals);
     if total_cleaned > 0 {
         println!("  {} Removed {} old output file(s)", "✓".bright_green(), total_cleaned);
         println!();
     }
 
-    // 7. For each target directory, render and write target files
+    // 7. Write rendered files
     // M8: Enhanced output with progress
     let mut files_written = 0;
-    let mut target_files_created: std::collections::HashSet<String> = std::collections::HashSet::new();
-    let total_outputs = outputs.len();
+    let mut file_names_created: std::collections::BTreeSet<String> = std::collections::BTreeSet::new();
+    let total_outputs = rendered_dirs.len();
 
     if total_outputs > 0 {
         println!("{} {}", "▸".bright_cyan(), "Generating files...".bright_cyan());
         println!();
     }
 
-    for (idx, (target_dir, rules)) in outputs.iter().enumerate() {
+    for (idx, (label, files)) in rendered_dirs.iter().enumerate() {
         // Show progress
         if total_outputs > 1 {
             println!("   {} {} {}/{}",
                 "⠿".bright_black(),
-                target_dir.display().to_string().bright_white(),
+                label.bright_white(),
                 (idx + 1).to_string().bright_black(),
                 total_outputs.to_string().bright_black()
             );
         }
 
-        // Determine output path and whether this is root
-        let output_root = PathBuf::from(&config.paths.output_root);
-        let output_dir = output_root.join(target_dir);
-        let is_root = target_dir == &PathBuf::from(".");
-
-        // Write to all configured targets
-        for target in &output_targets {
-            // Create context with current target for filtering
-            // Clone all variables from the base context and add the target
-            let mut target_variables = context.variables.clone();
-            target_variables.insert("target".to_string(), target.clone());
-            let target_context = planner::BuildContext::from_variables(target_variables);
-
-            // Filter rules for this specific target
-            let target_rules: Vec<&loader::Rule> = rules
-                .iter()
-                .filter(|rule| target_context.matches_when(&rule.frontmatter.when))
-                .collect();
-
-            if target_rules.is_empty() {
-                continue; // Skip this target if no rules apply
-            }
+        for file in files {
+            file.write()?;
 
-            // Render rules for this target
-            let mut target_rendered_bodies = Vec::new();
-            for rule in &target_rules {
-                let rendered = render_rule_with_command(rule, &base_data, defaults)?;
-                target_rendered_bodies.push(rendered);
-            }
-
-            // Merge for this target
-            let target_merged = merge::merge_rule_bodies(&target_rendered_bodies)?;
-
-            // Write to appropriate file
-            match target.as_str() {
-                "agents-md" => {
-                    writers::agents_md::write_agents_md(&output_dir, &target_merged, is_root)?;
-                    target_files_created.insert(target.to_string());
-                }
-                "claude-md" => {
-                    writers::claude_md::write_claude_md(&output_dir, &target_merged, is_root)?;
-                    target_files_created.insert(target.to_string());
-                }
-                "cursorrules" => {
-                    writers::cursorrules::write_cursorrules(&output_dir, &target_merged)?;
-                    target_files_created.insert(target.to_string());
-                }
-                _ => {
-                    eprintln!("  Warning: Unknown output target '{}' - skipping", target);
-                }
+            if let Some(name) = file.path.file_name() {
+                file_names_created.insert(name.to_string_lossy().to_string());
             }
         }
 
         files_written += 1;
     }
 
-    // 8. Save output tracking for future cleanup (directories + targets)
-    if let Err(e) = writers::agents_md::save_full_tracking(&current_output_paths, &output_targets) {
-        eprintln!("  Warning: Could not save output tracking: {}", e);
+    // 8. Save output tracking for future cleanup (directories + targets + files),
+    //    plus data file fingerprints so `status` can tell when outputs are stale
+    //    Files of skipped matrix variants stay tracked for their next build
+    if staging.is_none() {
+        let mut tracked_files = current_files.clone();
+        if !unbuilt.is_empty() {
+            let previous = writers::agents_md::load_full_tracking()?.map(|t| t.files).unwrap_or_default();
+            tracked_files.extend(
+                previous
+                    .into_iter()
+                    .filter(|file| unbuilt.iter().any(|v| v.owns(file)) && !current_files.contains(file)),
+            );
+        }
+        let inputs = data_file_fingerprints(&config, &options.load).unwrap_or_default();
+        if let Err(e) = writers::agents_md::save_full_tracking(&current_output_paths, &output_targets, &tracked_files, inputs) {
+            eprintln!("  Warning: Could not save output tracking: {}", e);
+        }
     }
 
     // M4 Slice 5: Beautiful output
@@ -411,41 +618,426 @@ pub fn cmd_build(
         println!("{} {}", "▸ ".yellow(), "No rules matched - no files generated".yellow());
     } else {
         println!("{} {}", "✓".bright_green(), "Generated Successfully!".green().bold());
+        if let Some(out) = &staging {
+            println!("  Staged in {}", out.display().to_string().bright_white());
+        }
         println!();
 
         // Show which target files were created (sorted for consistent output)
-        let mut target_names: Vec<String> = target_files_created.iter()
-            .map(|t| match t.as_str() {
-                "agents-md" => "AGENTS.md",
-                "claude-md" => "CLAUDE.md",
-                "cursorrules" => ".cursorrules",
-                _ => t.as_str(),
-            })
-            .map(|s| s.to_string())
-            .collect();
+        for name in file_names_created {
+            println!("   {} {}", "▸".bright_white(), name.bright_white());
+        }
+    }
+    println!();
+
+    Ok(())
+}
+
+/// Where `--out <dir>` puts an output file, keeping its path relative to the project
+fn staged_path(out: &Path, path: &Path) -> Result<PathBuf> {
+    let relative = if path.is_absolute() {
+        let cwd = std::env::current_dir()?;
+        path.strip_prefix(&cwd)
+            .with_context(|| format!("Cannot stage {} outside the project with --out", path.display()))?
+            .to_path_buf()
+    } else {
+        path.to_path_buf()
+    };
+    Ok(out.join(output_diff::normalize(&relative)))
+}
+
+/// `build --dry-run`: unified diffs of every file the build would create, change or remove
+fn print_dry_run(rendered_dirs: &[(String, Vec<RenderedFile>)], removals: &[PathBuf]) -> Result<()> {
+    use output_diff::ChangeKind;
+    use owo_colors::OwoColorize;
+
+    println!("{} {}", "▸".bright_blue(), "Dry run - no files will be written".bright_blue());
+    println!();
+
+    let changes = output_changes(rendered_dirs, removals)?;
+    let count = |kind: ChangeKind| changes.iter().filter(|c| c.kind == kind).count();
+    for change in &changes {
+        output_diff::print_diff(&change.unified_diff());
+    }
 
-        target_names.sort(); // Deterministic order: AGENTS.md, CLAUDE.md, ...
+    if count(ChangeKind::Unchanged) == changes.len() {
+        println!("{} {}", "✓".bright_green(), "Outputs are up to date - nothing would change".green());
+    } else {
+        println!();
+        println!(
+            "{} Would create {}, change {}, remove {} file(s) ({} unchanged)",
+            "▸".bright_blue(),
+            count(ChangeKind::Added),
+            count(ChangeKind::Modified),
+            count(ChangeKind::Removed),
+            count(ChangeKind::Unchanged)
+        );
+    }
+    println!();
 
-        if !target_names.is_empty() {
-            for name in target_names {
-                println!("   {} {}", "▸".bright_white(), name.bright_white());
+    Ok(())
+}
+
+/// `build --check`: list outputs that differ from a fresh build and fail if there are any
+///
+/// Outputs are stale (changed since the last build, or edited by hand), missing
+/// (not written yet) or extra (would be removed by cleanup).
+fn check_outputs(rendered_dirs: &[(String, Vec<RenderedFile>)], removals: &[PathBuf], json: bool) -> Result<()> {
+    use output_diff::ChangeKind;
+    use owo_colors::OwoColorize;
+
+    let changes = output_changes(rendered_dirs, removals)?;
+    let paths = |kind: ChangeKind| -> Vec<String> {
+        changes
+            .iter()
+            .filter(|c| c.kind == kind)
+            .map(|c| output_diff::display_path(&c.path).display().to_string())
+            .collect()
+    };
+    let (stale, missing, extra) = (paths(ChangeKind::Modified), paths(ChangeKind::Added), paths(ChangeKind::Removed));
+    let out_of_date = stale.len() + missing.len() + extra.len();
+
+    if json {
+        let report = serde_json::json!({
+            "up_to_date": out_of_date == 0,
+            "checked": changes.len(),
+            "stale": stale,
+            "missing": missing,
+            "extra": extra,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if out_of_date == 0 {
+        println!("{} {}", "✓".bright_green(), format!("All {} output file(s) are up to date", changes.len()).green());
+    } else {
+        println!("{} {}", "✗".red(), format!("{} output file(s) are out of date:", out_of_date).red().bold());
+        println!();
+        for (label, files) in [("stale", &stale), ("missing", &missing), ("extra", &extra)] {
+            for file in files {
+                println!("   {:<8} {}", label.yellow(), file);
             }
         }
+        println!();
+        println!("Run {} to update them ({} shows the diffs)", "cagents build".bold(), "cagents build --dry-run".bold());
     }
-    println!();
 
+    if out_of_date > 0 {
+        anyhow::bail!("{} output file(s) are out of date", out_of_date);
+    }
     Ok(())
 }
 
+/// Every rendered file and every file cleanup would remove, compared with the disk
+fn output_changes(
+    rendered_dirs: &[(String, Vec<RenderedFile>)],
+    removals: &[PathBuf],
+) -> Result<Vec<output_diff::FileChange>> {
+    let mut changes = Vec::new();
+    for file in rendered_dirs.iter().flat_map(|(_, files)| files) {
+        changes.push(output_diff::FileChange::new(&file.path, Some(file.expected()))?);
+    }
+    for path in removals {
+        changes.push(output_diff::FileChange::new(path, None)?);
+    }
+    Ok(changes)
+}
+
+/// Parse `--var KEY=VALUE` arguments into template data
+fn parse_var_args(var_args: &[String]) -> Result<serde_json::Map<String, serde_json::Value>> {
+    let mut variables = serde_json::Map::new();
+    for var_arg in var_args {
+        if let Some((key, value)) = var_arg.split_once('=') {
+            variables.insert(key.to_string(), serde_json::Value::String(value.to_string()));
+        } else {
+            anyhow::bail!("Invalid variable format '{}'. Expected KEY=VALUE", var_arg);
+        }
+    }
+    Ok(variables)
+}
+
+/// Config variables overridden by `--var`, with where each value came from
+///
+/// Values are checked against `[variables.schema]`. Missing required variables are
+/// prompted for when interactive (and saved to the local config), otherwise an error.
+/// With `read_only`, nothing is written: missing variables are always an error and
+/// the command cache isn't saved.
+fn resolve_variables(
+    config: &crate::model::ProjectConfig,
+    cli_vars: &serde_json::Map<String, serde_json::Value>,
+    read_only: bool,
+    load: &config::LoadOptions,
+) -> Result<variables::ResolvedVariables> {
+    resolve_variables_except(config, cli_vars, read_only, &[], load)
+}
+
+/// Like [`resolve_variables`], but the `later` variables (set per matrix variant) are
+/// not required yet
+fn resolve_variables_except(
+    config: &crate::model::ProjectConfig,
+    cli_vars: &serde_json::Map<String, serde_json::Value>,
+    read_only: bool,
+    later: &[String],
+    load: &config::LoadOptions,
+) -> Result<variables::ResolvedVariables> {
+    let mut resolved = variables::resolve_config_variables_with(config, read_only)?;
+    resolved.apply_cli(cli_vars);
+
+    let mut missing = resolved.apply_schema(config)?;
+    missing.retain(|name| !later.contains(name));
+    if !missing.is_empty() {
+        if read_only {
+            return Err(missing_variables_error(config, &missing));
+        }
+        prompt_required_variables(config, &missing, load)?;
+        return resolve_variables_except(&config::load_config(load)?, cli_vars, read_only, later, load);
+    }
+
+    Ok(resolved)
+}
+
+/// Add the built-in `cagents.*` facts (reserved: they replace a variable of the same name)
+///
+/// Detecting them runs git, so builds only add them when [`facts::referenced`]; `vars` always shows them.
+fn insert_facts(resolved: &mut variables::ResolvedVariables) {
+    let source = variables::VariableSource::new(variables::VariableKind::Builtin);
+    resolved.insert(facts::NAMESPACE, variables::ResolvedVariable::new(facts::detect(), source));
+}
+
+/// Ask for required variables that have no value and save them to config.local.toml
+fn prompt_required_variables(
+    config: &crate::model::ProjectConfig,
+    missing: &[String],
+    load: &config::LoadOptions,
+) -> Result<()> {
+    let schema = config.variables.as_ref().and_then(|v| v.schema.as_ref());
+    let declaration = |name: &str| schema.and_then(|s| s.get(name)).cloned().unwrap_or_default();
+
+    if !interactive::is_interactive() {
+        return Err(missing_variables_error(config, missing));
+    }
+
+    for name in missing {
+        let declaration = declaration(name);
+        let message = match &declaration.description {
+            Some(description) => format!("{} ({})", name, description),
+            None => name.clone(),
+        };
+
+        let answer = match &declaration.allowed {
+            Some(allowed) => {
+                let options: Vec<String> = allowed
+                    .iter()
+                    .map(|v| v.as_str().map(str::to_string).unwrap_or_else(|| v.to_string()))
+                    .collect();
+                let options: Vec<&str> = options.iter().map(String::as_str).collect();
+                interactive::prompt_select(&message, &options)?
+            }
+            None => interactive::prompt_text(&message, None)?,
+        };
+
+        // Strings are quoted so they stay strings; other types are written as TOML
+        let raw = match declaration.type_.as_deref() {
+            None | Some("string") => serde_json::to_string(&answer)?,
+            Some(_) => answer.clone(),
+        };
+        variables::check_value(name, &declaration, &serde_json::Value::String(answer))?;
+        let path = config_edit::set_value(
+            crate::model::ConfigLayer::Local,
+            &format!("variables.static.{}", name),
+            &raw,
+            load,
+        )?;
+        interactive::print_file("Saved", &format!("{} to {}", name, path.display()));
+    }
+
+    Ok(())
+}
+
+/// The error for required variables that have no value, listing their descriptions
+fn missing_variables_error(config: &crate::model::ProjectConfig, missing: &[String]) -> anyhow::Error {
+    let schema = config.variables.as_ref().and_then(|v| v.schema.as_ref());
+    let details: Vec<String> = missing
+        .iter()
+        .map(|name| match schema.and_then(|s| s.get(name)).and_then(|d| d.description.as_ref()) {
+            Some(description) => format!("  {} - {}", name, description),
+            None => format!("  {}", name),
+        })
+        .collect();
+    anyhow::anyhow!(
+        "Required variables are not set:\n{}\nSet them in [variables.static], with --var NAME=VALUE, or CAGENTS_VAR_<NAME>",
+        details.join("\n")
+    )
+}
+
+/// Fingerprints of the data files read by the root project and every nested project
+fn data_file_fingerprints(
+    config: &crate::model::ProjectConfig,
+    load: &config::LoadOptions,
+) -> Result<std::collections::BTreeMap<PathBuf, String>> {
+    let mut inputs = data_files::fingerprints(config)?;
+    for project in nested::discover_nested_projects(load)? {
+        inputs.extend(data_files::fingerprints(&project.config)?);
+    }
+    Ok(inputs)
+}
+
+/// Build a when-clause context from the string values in template data
+fn context_from_data(data: &serde_json::Map<String, serde_json::Value>) -> planner::BuildContext {
+    let mut context_variables = std::collections::HashMap::new();
+
+    for (key, value) in data {
+        if let Some(s) = value.as_str() {
+            context_variables.insert(key.clone(), s.to_string());
+        }
+    }
+
+    planner::BuildContext::from_variables(context_variables)
+}
+
+/// Clone a context and set the output target being built
+fn target_context(context: &planner::BuildContext, target: &str) -> planner::BuildContext {
+    let mut variables = context.variables.clone();
+    variables.insert("target".to_string(), target.to_string());
+    planner::BuildContext::from_variables(variables)
+}
+
+/// Variables for a single file, as `cmd_build` would see them
+struct FileInputs {
+    /// Path relative to the project root (used for glob matching)
+    rel_path: PathBuf,
+    /// Target the rules were selected for
+    target: String,
+    /// Config variables overridden by `--var`
+    variables: variables::ResolvedVariables,
+    cli_vars: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Template data and when-clause context of one output directory
+#[derive(Clone)]
+struct OutputInputs {
+    /// Output directory, relative to the project root
+    dir: PathBuf,
+    data: serde_json::Map<String, serde_json::Value>,
+    /// Provenance of each value in `data`
+    variables: variables::ResolvedVariables,
+    context: planner::BuildContext,
+}
+
+impl FileInputs {
+    /// Inputs of the output in `dir`: its directory variables sit below `--var` (as in build)
+    fn output(&self, dir: &Path) -> Result<OutputInputs> {
+        let mut variables = self.variables.clone();
+        for (key, var) in variables::resolve_dir_variables(dir)?.iter() {
+            if !self.cli_vars.contains_key(key) {
+                variables.insert(key.clone(), var.clone());
+            }
+        }
+
+        let mut data = variables.to_data();
+        facts::set_output(&mut data, dir, &self.target);
+        let context = target_context(&context_from_data(&data), &self.target);
+        if let Some(facts) = data.get(facts::NAMESPACE) {
+            let source = variables::VariableSource::new(variables::VariableKind::Builtin);
+            variables.insert(facts::NAMESPACE, variables::ResolvedVariable::new(facts.clone(), source));
+        }
+
+        Ok(OutputInputs {
+            dir: dir.to_path_buf(),
+            data,
+            variables,
+            context,
+        })
+    }
+
+    /// Rules that apply to the file, each with the inputs of the output build writes it to
+    fn matching_rules(&self, rules: &[loader::Rule]) -> Result<Vec<(loader::Rule, OutputInputs)>> {
+        let mut outputs: std::collections::BTreeMap<PathBuf, OutputInputs> = std::collections::BTreeMap::new();
+        let mut matching = Vec::new();
+
+        for rule in rules {
+            if !planner::rule_matches_file(rule, &self.rel_path) {
+                continue;
+            }
+
+            let dir = planner::output_dir_for_file(rule, &self.rel_path);
+            let output = match outputs.get(&dir) {
+                Some(output) => output.clone(),
+                None => {
+                    let output = self.output(&dir)?;
+                    outputs.insert(dir, output.clone());
+                    output
+                }
+            };
+
+            if output.context.matches_rule(rule) {
+                matching.push((rule.clone(), output));
+            }
+        }
+
+        Ok(matching)
+    }
+}
+
+/// Resolve the inputs used by `render` and `context` for a file
+///
+/// Uses the same construction as `cmd_build` (config vars + CLI vars + target), so
+/// each rule selected here sees the data of the directory build writes it to.
+/// Without `--target`, the first configured output target is used.
+fn resolve_file_inputs(
+    config: &crate::model::ProjectConfig,
+    file_path: &str,
+    cli_vars: &serde_json::Map<String, serde_json::Value>,
+    target: Option<&str>,
+    rules: &[loader::Rule],
+    load: &config::LoadOptions,
+) -> Result<FileInputs> {
+    let target = match target {
+        Some(t) => t.to_string(),
+        None => targets::configured_targets(config)
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| targets::DEFAULT_TARGET.to_string()),
+    };
+
+    // Resolve file path (make absolute relative to cwd)
+    let cwd = std::env::current_dir()?;
+    let file_path = PathBuf::from(file_path);
+    let file_path = if file_path.is_absolute() {
+        file_path
+    } else {
+        cwd.join(&file_path)
+    };
+
+    // Convert to a path relative to the project root for glob matching
+    let project_root = cwd.join(&config.paths.output_root);
+    let project_root = project_root.canonicalize().unwrap_or(project_root);
+    let file_path = file_path.canonicalize().unwrap_or(file_path);
+    let rel_path = file_path
+        .strip_prefix(&project_root)
+        .map(PathBuf::from)
+        .unwrap_or(file_path);
+
+    let mut variables = resolve_variables(config, cli_vars, false, load)?;
+    if facts::referenced(config, rules) {
+        insert_facts(&mut variables);
+    }
+
+    Ok(FileInputs {
+        rel_path,
+        target,
+        variables,
+        cli_vars: cli_vars.clone(),
+    })
+}
+
 /// M6: Lint and validate configuration
-pub fn cmd_lint() -> Result<()> {
+pub fn cmd_lint(load: &config::LoadOptions) -> Result<()> {
     use owo_colors::OwoColorize;
 
     println!("{} {}", "▸".bright_cyan(), "Linting cAGENTS configuration...".bright_cyan().bold());
     println!();
 
     // Run all validations
-    let result = lint::lint_all()?;
+    let result = lint::lint_all(load)?;
 
     // Print results
     result.print();
@@ -459,7 +1051,7 @@ pub fn cmd_lint() -> Result<()> {
 }
 
 /// M7: Preview command - show build plan with rendered output preview
-pub fn cmd_preview(_path: &str) -> Result<()> {
+pub fn cmd_preview(_path: &str, load: &config::LoadOptions) -> Result<()> {
     use owo_colors::OwoColorize;
 
     println!();
@@ -467,9 +1059,8 @@ pub fn cmd_preview(_path: &str) -> Result<()> {
     println!();
 
     // Load config and rules
-    let config = config::load_config_with_precedence()?;
-    let base_dir = PathBuf::from(".cAGENTS");
-    let all_rules = loader::discover_rules(&config, &base_dir)?;
+    let config = config::load_config(load)?;
+    let all_rules = nested::discover_project_rules(&config, load)?;
 
     if all_rules.is_empty() {
         println!("{} {}", "ℹ️".bright_blue(), "No rules found".bright_blue());
@@ -477,7 +1068,7 @@ pub fn cmd_preview(_path: &str) -> Result<()> {
     }
 
     // Build template data from config variables (same as build)
-    let base_data = build_template_data_map(&config);
+    let base_data = build_template_data_map(&config, &all_rules)?;
 
     // Build context from config variables (for use in when clauses)
     let mut context_variables = std::collections::HashMap::new();
@@ -504,16 +1095,39 @@ pub fn cmd_preview(_path: &str) -> Result<()> {
     println!();
 
     let defaults = config.defaults.as_ref();
+    let commands = variables::RuleCommands::new(&config);
+
+    // Files written per output directory, for the configured targets
+    let registry = targets::TargetRegistry::for_config(&config);
+    let preview_targets: Vec<&dyn targets::Target> = targets::configured_targets(&config)
+        .iter()
+        .filter_map(|t| registry.get(t))
+        .collect();
+    let preview_target = preview_targets
+        .first()
+        .map(|t| t.name().to_string())
+        .unwrap_or_else(|| targets::DEFAULT_TARGET.to_string());
+    let output_label = |dir: &PathBuf| -> String {
+        let is_root = dir == &PathBuf::from(".");
+        preview_targets
+            .iter()
+            .filter(|t| {
+                // Nested directories get a per-rule target's merged file only for rules without their own
+                let merges_rules = || {
+                    outputs
+                        .get(dir)
+                        .is_some_and(|rules| rules.iter().any(|r| t.rule_file(r).is_none()))
+                };
+                is_root || !t.root_only() && (!t.per_rule() || merges_rules())
+            })
+            .map(|t| if is_root { t.file_name().to_string() } else { dir.join(t.file_name()).display().to_string() })
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
 
     // Show each output file
     for (idx, (target_dir, rules)) in outputs.iter().enumerate() {
-        let output_path = if target_dir == &PathBuf::from(".") {
-            PathBuf::from("AGENTS.md")
-        } else {
-            target_dir.join("AGENTS.md")
-        };
-
-        println!("{} {}", format!("{}.", idx + 1).bright_black(), output_path.display().to_string().bright_white().bold());
+        println!("{} {}", format!("{}.", idx + 1).bright_black(), output_label(target_dir).bright_white().bold());
         println!();
 
         // Show which rules contribute
@@ -538,9 +1152,11 @@ pub fn cmd_preview(_path: &str) -> Result<()> {
         println!("  {}", "─".repeat(70).bright_black());
 
         // Render each rule and merge
+        let mut dir_data = base_data.clone();
+        facts::set_output(&mut dir_data, target_dir, &preview_target);
         let mut rendered_bodies = Vec::new();
         for rule in rules {
-            match render_rule_with_command(rule, &base_data, defaults) {
+            match render_rule_with_command(rule, &dir_data, defaults, &commands) {
                 Ok(rendered) => rendered_bodies.push(rendered),
                 Err(e) => {
                     println!("  {} {}", "Error:".bright_red(), e.to_string().red());
@@ -576,13 +1192,7 @@ pub fn cmd_preview(_path: &str) -> Result<()> {
 
         loop {
             // Build menu options
-            let mut options: Vec<String> = outputs.keys().map(|dir| {
-                if dir == &PathBuf::from(".") {
-                    "AGENTS.md (root)".to_string()
-                } else {
-                    format!("{}/AGENTS.md", dir.display())
-                }
-            }).collect();
+            let mut options: Vec<String> = outputs.keys().map(&output_label).collect();
             options.push("Exit".to_string());
 
             let selection = Select::new("View full preview:", options)
@@ -595,33 +1205,23 @@ pub fn cmd_preview(_path: &str) -> Result<()> {
                 }
 
                 // Find the matching output
-                let selected_idx = outputs.iter().position(|(dir, _)| {
-                    let path_str = if dir == &PathBuf::from(".") {
-                        "AGENTS.md (root)".to_string()
-                    } else {
-                        format!("{}/AGENTS.md", dir.display())
-                    };
-                    path_str == selected
-                });
+                let selected_idx = outputs.keys().position(|dir| output_label(dir) == selected);
 
                 if let Some(idx) = selected_idx {
                     let (target_dir, rules) = outputs.iter().nth(idx).unwrap();
-                    let output_path = if target_dir == &PathBuf::from(".") {
-                        PathBuf::from("AGENTS.md")
-                    } else {
-                        target_dir.join("AGENTS.md")
-                    };
 
                     println!();
                     println!("{}", "═".repeat(70).bright_black());
-                    println!("{} {}", "▸".bright_cyan(), output_path.display().to_string().bright_cyan().bold());
+                    println!("{} {}", "▸".bright_cyan(), selected.bright_cyan().bold());
                     println!("{}", "═".repeat(70).bright_black());
                     println!();
 
                     // Render full content
+                    let mut dir_data = base_data.clone();
+                    facts::set_output(&mut dir_data, target_dir, &preview_target);
                     let mut rendered_bodies = Vec::new();
                     for rule in rules {
-                        match render_rule_with_command(rule, &base_data, defaults) {
+                        match render_rule_with_command(rule, &dir_data, defaults, &commands) {
                             Ok(rendered) => rendered_bodies.push(rendered),
                             Err(e) => {
                                 println!("{} {}", "Error:".bright_red(), e.to_string().red());
@@ -648,7 +1248,7 @@ pub fn cmd_preview(_path: &str) -> Result<()> {
 }
 
 /// M8: Status command - show project stats
-pub fn cmd_status() -> Result<()> {
+pub fn cmd_status(load: &config::LoadOptions) -> Result<()> {
     use owo_colors::OwoColorize;
     use comfy_table::{Table, Row, Cell};
     use comfy_table::presets::UTF8_FULL;
@@ -657,8 +1257,9 @@ pub fn cmd_status() -> Result<()> {
     println!("{} {}", "▸".bright_cyan(), "cAGENTS Status".bright_cyan().bold());
     println!();
 
-    // Check if cAGENTS exists
-    if !PathBuf::from(".cAGENTS").exists() {
+    // Check if the project config exists (`--config`, or found from a subdirectory)
+    let config_path = load.project_config_path();
+    if !config_path.exists() {
         println!("{} {}", "✗".bright_red(), "Not initialized".red());
         println!();
         println!("   Run {} to get started", "cagents init".bright_white());
@@ -666,18 +1267,18 @@ pub fn cmd_status() -> Result<()> {
     }
 
     // Load config
-    let config = config::load_config_with_precedence()?;
-    let base_dir = PathBuf::from(".cAGENTS");
+    let config = config::load_config(load)?;
 
-    // Count templates
-    let templates = loader::discover_rules(&config, &base_dir)?;
+    // Count templates (including nested projects)
+    let templates = nested::discover_project_rules(&config, load)?;
+    let nested_dirs = nested::discover_nested_dirs()?;
 
     let mut table = Table::new();
     table.load_preset(UTF8_FULL);
 
     table.add_row(Row::from(vec![
         Cell::new("Configuration").fg(comfy_table::Color::Cyan),
-        Cell::new(".cAGENTS/config.toml").fg(comfy_table::Color::Green),
+        Cell::new(config_path.display().to_string()).fg(comfy_table::Color::Green),
     ]));
 
     table.add_row(Row::from(vec![
@@ -685,19 +1286,54 @@ pub fn cmd_status() -> Result<()> {
         Cell::new(format!("{} found", templates.len())).fg(comfy_table::Color::White),
     ]));
 
+    if !nested_dirs.is_empty() {
+        let dirs: Vec<String> = nested_dirs.iter().map(|d| d.display().to_string()).collect();
+        table.add_row(Row::from(vec![
+            Cell::new("Nested projects").fg(comfy_table::Color::Cyan),
+            Cell::new(dirs.join(", ")).fg(comfy_table::Color::White),
+        ]));
+    }
+
     table.add_row(Row::from(vec![
         Cell::new("Output").fg(comfy_table::Color::Cyan),
         Cell::new(&config.paths.output_root).fg(comfy_table::Color::White),
     ]));
 
-    // Check if AGENTS.md exists
-    if PathBuf::from("AGENTS.md").exists() {
+    // Data files changed since the last build mean the outputs are stale
+    let current_inputs = data_file_fingerprints(&config, load)?;
+    if !current_inputs.is_empty() {
+        let built_inputs = writers::agents_md::load_full_tracking()?
+            .map(|t| t.inputs)
+            .unwrap_or_default();
+        let changed: Vec<String> = current_inputs
+            .iter()
+            .filter(|(path, fingerprint)| built_inputs.get(*path) != Some(fingerprint))
+            .map(|(path, _)| path.display().to_string())
+            .collect();
+
+        let cell = if changed.is_empty() {
+            Cell::new(format!("{} up to date", current_inputs.len())).fg(comfy_table::Color::Green)
+        } else {
+            Cell::new(format!("Changed since last build: {} (run cagents build)", changed.join(", ")))
+                .fg(comfy_table::Color::Yellow)
+        };
         table.add_row(Row::from(vec![
-            Cell::new("AGENTS.md").fg(comfy_table::Color::Cyan),
-            Cell::new("Generated").fg(comfy_table::Color::Green),
+            Cell::new("Data files").fg(comfy_table::Color::Cyan),
+            cell,
         ]));
     }
 
+    // Root output file of each configured target
+    let registry = targets::TargetRegistry::for_config(&config);
+    for target in targets::configured_targets(&config).iter().filter_map(|t| registry.get(t)) {
+        if PathBuf::from(&config.paths.output_root).join(target.file_name()).exists() {
+            table.add_row(Row::from(vec![
+                Cell::new(target.display_name()).fg(comfy_table::Color::Cyan),
+                Cell::new("Generated").fg(comfy_table::Color::Green),
+            ]));
+        }
+    }
+
     println!("{}", table);
     println!();
 
@@ -833,44 +1469,22 @@ pub fn cmd_migrate(from: Option<&str>, backup: bool) -> Result<()> {
 }
 
 /// Render AGENTS.md for a specific file
-pub fn cmd_render(file_path: &str, var_args: Vec<String>) -> Result<()> {
+pub fn cmd_render(file_path: &str, var_args: Vec<String>, target: Option<&str>, load: &config::LoadOptions) -> Result<()> {
     // 1. Load config with precedence
-    let config = config::load_config_with_precedence()?;
+    let config = config::load_config(load)?;
 
     // 2. Discover all rule templates
-    let base_dir = PathBuf::from(".cAGENTS");
-    let all_rules = loader::discover_rules(&config, &base_dir)?;
+    let all_rules = nested::discover_project_rules(&config, load)?;
 
     // 3. Parse variables from CLI args
-    let mut variables = serde_json::Map::new();
-    for var_arg in var_args {
-        if let Some((key, value)) = var_arg.split_once('=') {
-            variables.insert(key.to_string(), serde_json::Value::String(value.to_string()));
-        } else {
-            anyhow::bail!("Invalid variable format '{}'. Expected KEY=VALUE", var_arg);
-        }
-    }
+    let variables = parse_var_args(&var_args)?;
 
-    // 4. Build context with generic variables (no hardcoded env/role/language)
-    // For now, BuildContext still expects the old fields, but we inject variables into template data
-    let context = planner::BuildContext::new(None, None, None);
+    // 4. Build data and context exactly like build (config vars + CLI vars + target)
+    let inputs = resolve_file_inputs(&config, file_path, &variables, target, &all_rules, load)?;
+    let rel_file_path = inputs.rel_path.as_path();
 
-    // 5. Resolve file path (make absolute or relative to cwd)
-    let file_path = PathBuf::from(file_path);
-    let file_path = if file_path.is_absolute() {
-        file_path
-    } else {
-        std::env::current_dir()?.join(&file_path)
-    };
-
-    // Convert to relative path for glob matching
-    let project_root = PathBuf::from(&config.paths.output_root);
-    let rel_file_path = file_path
-        .strip_prefix(&project_root)
-        .unwrap_or(&file_path);
-
-    // 6. Filter rules for this specific file
-    let matching_rules = planner::filter_rules_for_file(&all_rules, rel_file_path, &context)?;
+    // 5. Filter rules for this specific file
+    let matching_rules = inputs.matching_rules(&all_rules)?;
 
     if matching_rules.is_empty() {
         // No matching rules - output empty or warning to stderr
@@ -878,108 +1492,91 @@ pub fn cmd_render(file_path: &str, var_args: Vec<String>) -> Result<()> {
         return Ok(());
     }
 
-    // 7. Build template data from config variables + CLI variables
-    let mut base_data = build_template_data_map(&config);
-
-    // Override/add CLI variables
-    for (key, value) in variables {
-        base_data.insert(key, value);
-    }
-
     let defaults = config.defaults.as_ref();
+    let commands = variables::RuleCommands::new(&config);
 
-    // 8. Render each matching rule
+    // 6. Render each matching rule with the data of the output it's written to
     let mut rendered_bodies = Vec::new();
-    for rule in &matching_rules {
-        let rendered = render_rule_with_command(rule, &base_data, defaults)?;
+    for (rule, output) in &matching_rules {
+        let rendered = render_rule_with_command(rule, &output.data, defaults, &commands)?;
         rendered_bodies.push(rendered);
     }
 
-    // 9. Merge rendered bodies
+    // 7. Merge rendered bodies
     let merged = merge::merge_rule_bodies(&rendered_bodies)?;
 
-    // 10. Output to stdout (no extra formatting, just the content)
+    // 8. Output to stdout (no extra formatting, just the content)
     print!("{}", merged);
 
     Ok(())
 }
 
 /// Show comprehensive context and metadata for a file
-pub fn cmd_context(file_path: &str, var_args: Vec<String>, json_output: bool) -> Result<()> {
+pub fn cmd_context(
+    file_path: &str,
+    var_args: Vec<String>,
+    target: Option<&str>,
+    json_output: bool,
+    load: &config::LoadOptions,
+) -> Result<()> {
     // 1. Load config with precedence
-    let config = config::load_config_with_precedence()?;
+    let config = config::load_config(load)?;
 
     // 2. Discover all rule templates
-    let base_dir = PathBuf::from(".cAGENTS");
-    let all_rules = loader::discover_rules(&config, &base_dir)?;
+    let all_rules = nested::discover_project_rules(&config, load)?;
 
     // 3. Parse variables from CLI args
-    let mut variables = serde_json::Map::new();
-    for var_arg in var_args {
-        if let Some((key, value)) = var_arg.split_once('=') {
-            variables.insert(key.to_string(), serde_json::Value::String(value.to_string()));
-        } else {
-            anyhow::bail!("Invalid variable format '{}'. Expected KEY=VALUE", var_arg);
-        }
-    }
-
-    // 4. Build context
-    let context = planner::BuildContext::new(None, None, None);
+    let variables = parse_var_args(&var_args)?;
 
-    // 5. Resolve file path
-    let file_path = PathBuf::from(file_path);
-    let file_path = if file_path.is_absolute() {
-        file_path
-    } else {
-        std::env::current_dir()?.join(&file_path)
-    };
+    // 4. Build data and context exactly like build (config vars + CLI vars + target)
+    let inputs = resolve_file_inputs(&config, file_path, &variables, target, &all_rules, load)?;
+    let rel_file_path = inputs.rel_path.as_path();
 
-    let project_root = PathBuf::from(&config.paths.output_root);
-    let rel_file_path = file_path
-        .strip_prefix(&project_root)
-        .unwrap_or(&file_path);
-
-    // 6. Filter rules for this specific file
-    let matching_rules = planner::filter_rules_for_file(&all_rules, rel_file_path, &context)?;
+    // 5. Filter rules for this specific file
+    let matching_rules = inputs.matching_rules(&all_rules)?;
 
     if matching_rules.is_empty() {
         eprintln!("No rules match file: {}", rel_file_path.display());
         return Ok(());
     }
 
-    // 7. Build template data from config variables + CLI variables
-    let mut base_data = build_template_data_map(&config);
-
-    // Track variable sources
-    let mut var_sources = serde_json::Map::new();
-    for (key, value) in &base_data {
-        var_sources.insert(
-            key.clone(),
-            serde_json::json!({
-                "value": value,
-                "source": "config.static"
-            })
-        );
+    let defaults = config.defaults.as_ref();
+    let commands = variables::RuleCommands::new(&config);
+
+    // 6. Track variable sources (config layer/file, CLI, or the rule's frontmatter),
+    //    starting from the output nearest the file
+    let mut resolved = matching_rules
+        .iter()
+        .map(|(_, output)| output)
+        .max_by_key(|output| output_diff::normalize(&output.dir).components().count())
+        .map(|output| output.variables.clone())
+        .unwrap_or_default();
+    for (rule, _) in &matching_rules {
+        if let Some(obj) = rule.frontmatter.vars.as_ref().and_then(|v| v.as_object()) {
+            let rule_name = rule.frontmatter.name.clone().unwrap_or_else(|| "unnamed".to_string());
+            for (key, value) in obj {
+                let mut source = variables::VariableSource::new(variables::VariableKind::Frontmatter);
+                source.rule = Some(rule_name.clone());
+                resolved.insert(
+                    key.clone(),
+                    variables::ResolvedVariable::new(value.clone(), source),
+                );
+            }
+        }
+        for (key, var) in commands.resolve(rule)?.iter() {
+            resolved.insert(key.clone(), var.clone());
+        }
     }
 
-    // Override/add CLI variables
-    for (key, value) in variables {
-        var_sources.insert(
-            key.clone(),
-            serde_json::json!({
-                "value": value.clone(),
-                "source": "cli"
-            })
-        );
-        base_data.insert(key, value);
+    let mut var_sources = serde_json::Map::new();
+    for (key, var) in resolved.iter() {
+        var_sources.insert(key.clone(), var.to_json());
     }
 
-    let defaults = config.defaults.as_ref();
-
     // 8. Render each matching rule
     let mut rendered_bodies = Vec::new();
-    for rule in &matching_rules {
-        let rendered = render_rule_with_command(rule, &base_data, defaults)?;
+    for (rule, output) in &matching_rules {
+        let rendered = render_rule_with_command(rule, &output.data, defaults, &commands)?;
         rendered_bodies.push(rendered);
     }
 
@@ -988,7 +1585,7 @@ pub fn cmd_context(file_path: &str, var_args: Vec<String>, json_output: bool) ->
 
     // 10. Collect metadata about matched rules
     let mut rules_metadata = Vec::new();
-    for rule in &matching_rules {
+    for (rule, _) in &matching_rules {
         let reason = if rule.frontmatter.when.is_none() {
             "always (no when clause)".to_string()
         } else if let Some(globs) = &rule.frontmatter.globs {
@@ -1021,6 +1618,7 @@ pub fn cmd_context(file_path: &str, var_args: Vec<String>, json_output: bool) ->
         // JSON format
         let output = serde_json::json!({
             "file": rel_file_path.to_string_lossy(),
+            "target": inputs.target,
             "matched_rules": rules_metadata,
             "variables": var_sources,
             "file_info": file_info,
@@ -1032,7 +1630,7 @@ pub fn cmd_context(file_path: &str, var_args: Vec<String>, json_output: bool) ->
         println!("# Context for {}\n", rel_file_path.display());
 
         println!("## Matched Rules ({})", matching_rules.len());
-        for rule in &matching_rules {
+        for (rule, _) in &matching_rules {
             let name = rule.frontmatter.name.as_deref().unwrap_or("unnamed");
             let reason = if rule.frontmatter.when.is_none() {
                 "always (no when clause)".to_string()
@@ -1047,11 +1645,11 @@ pub fn cmd_context(file_path: &str, var_args: Vec<String>, json_output: bool) ->
         println!();
 
         println!("## Available Variables ({})", var_sources.len());
-        for (key, value) in &var_sources {
-            if let Some(obj) = value.as_object() {
-                let val = obj.get("value").and_then(|v| v.as_str()).unwrap_or("");
-                let src = obj.get("source").and_then(|v| v.as_str()).unwrap_or("");
-                println!("- `{}` = \"{}\" ({})", key, val, src);
+        for (key, var) in resolved.iter() {
+            let val = var.value.as_str().map(|s| s.to_string()).unwrap_or_else(|| var.value.to_string());
+            match var.schema.as_ref().and_then(|s| s.description.as_ref()) {
+                Some(description) => println!("- `{}` = \"{}\" ({}) - {}", key, val, var.source.describe(), description),
+                None => println!("- `{}` = \"{}\" ({})", key, val, var.source.describe()),
             }
         }
         println!();
@@ -1060,6 +1658,7 @@ pub fn cmd_context(file_path: &str, var_args: Vec<String>, json_output: bool) ->
         println!("- Extension: {}", file_info["extension"].as_str().unwrap_or(""));
         println!("- Directory: {}", file_info["directory"].as_str().unwrap_or(""));
         println!("- Path: {}", file_info["relative_path"].as_str().unwrap_or(""));
+        println!("- Target: {}", inputs.target);
         println!();
 
         println!("---\n");
@@ -1068,3 +1667,126 @@ pub fn cmd_context(file_path: &str, var_args: Vec<String>, json_output: bool) ->
 
     Ok(())
 }
+
+/// Show every resolved variable with its value, source and resolution time
+pub fn cmd_vars(var_args: Vec<String>, json_output: bool, load: &config::LoadOptions) -> Result<()> {
+    use comfy_table::{Table, Row, Cell};
+    use comfy_table::presets::UTF8_FULL;
+
+    let config = config::load_config(load)?;
+    let cli_vars = parse_var_args(&var_args)?;
+    let mut resolved = resolve_variables(&config, &cli_vars, false, load)?;
+    insert_facts(&mut resolved);
+
+    if json_output {
+        let mut output = serde_json::Map::new();
+        for (key, var) in resolved.iter() {
+            output.insert(key.clone(), var.to_json());
+        }
+        println!("{}", serde_json::to_string_pretty(&output)?);
+        return Ok(());
+    }
+
+    if resolved.is_empty() {
+        println!("No variables defined");
+        return Ok(());
+    }
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec!["Variable", "Value", "Source", "Time"]);
+
+    for (key, var) in resolved.iter() {
+        let value = var.value.as_str().map(|s| s.to_string()).unwrap_or_else(|| var.value.to_string());
+        let time = if var.cached {
+            "cached".to_string()
+        } else {
+            var.duration
+                .map(|d| format!("{}ms", d.as_millis()))
+                .unwrap_or_default()
+        };
+        table.add_row(Row::from(vec![
+            Cell::new(key).fg(comfy_table::Color::Cyan),
+            Cell::new(value),
+            Cell::new(var.source.describe()),
+            Cell::new(time),
+        ]));
+    }
+
+    println!("{}", table);
+
+    Ok(())
+}
+
+/// Print a config value, optionally prefixed with the layer and file that set it
+fn print_config_entry(entry: &config_edit::ConfigEntry, with_key: bool, show_origin: bool) {
+    let value = config_edit::display_value(&entry.value);
+    let line = if with_key {
+        format!("{} = {}", entry.key, value)
+    } else {
+        value
+    };
+
+    if show_origin {
+        println!("{}:{}\t{}", entry.origin.layer, entry.origin.file.display(), line);
+    } else {
+        println!("{}", line);
+    }
+}
+
+fn parse_layer(layer: Option<&str>) -> Result<Option<model::ConfigLayer>> {
+    layer.map(str::parse).transpose()
+}
+
+/// Get a config value: the effective (merged) value, or the value set in one layer
+pub fn cmd_config_get(key: &str, layer: Option<&str>, show_origin: bool, load: &config::LoadOptions) -> Result<()> {
+    let entry = match parse_layer(layer)? {
+        Some(layer) => config_edit::get_layer_value(layer, key, load)?.map(|value| config_edit::ConfigEntry {
+            key: key.to_string(),
+            value,
+            origin: model::ConfigOrigin {
+                layer,
+                file: config_edit::layer_file(layer, load).unwrap_or_default(),
+            },
+        }),
+        None => config_edit::get_effective(key, load)?,
+    };
+
+    match entry {
+        Some(entry) => {
+            print_config_entry(&entry, false, show_origin);
+            Ok(())
+        }
+        None => anyhow::bail!("Config key '{}' is not set", key),
+    }
+}
+
+/// Set a config value in a layer (defaults to the project config)
+pub fn cmd_config_set(key: &str, value: &str, layer: Option<&str>, load: &config::LoadOptions) -> Result<()> {
+    let layer = parse_layer(layer)?.unwrap_or(model::ConfigLayer::Project);
+    let path = config_edit::set_value(layer, key, value, load)?;
+    println!("✓ Set {} in {}", key, path.display());
+    Ok(())
+}
+
+/// Remove a config value from a layer (defaults to the project config)
+pub fn cmd_config_unset(key: &str, layer: Option<&str>, load: &config::LoadOptions) -> Result<()> {
+    let layer = parse_layer(layer)?.unwrap_or(model::ConfigLayer::Project);
+    let path = config_edit::unset_value(layer, key, load)?;
+    println!("✓ Removed {} from {}", key, path.display());
+    Ok(())
+}
+
+/// Show every config value: the effective config, or everything set in one layer
+pub fn cmd_config_show(layer: Option<&str>, show_origin: bool, load: &config::LoadOptions) -> Result<()> {
+    let entries = match parse_layer(layer)? {
+        Some(layer) => config_edit::layer_entries(layer, load)?,
+        None => config_edit::effective_entries(load)?,
+    };
+
+    for entry in &entries {
+        print_config_entry(entry, true, show_origin);
+    }
+
+    Ok(())
+}
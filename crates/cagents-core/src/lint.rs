@@ -1,5 +1,6 @@
 // Linting and validation for config and templates
 
+use crate::config::LoadOptions;
 use anyhow::Result;
 use owo_colors::OwoColorize;
 use std::path::PathBuf;
@@ -117,28 +118,27 @@ impl LintResult {
     }
 }
 
-/// Known valid output targets
-const VALID_TARGETS: &[&str] = &["agents-md", "claude-md", "cursorrules"];
-
 /// Validate config file
-pub fn validate_config() -> Result<LintResult> {
+pub fn validate_config(options: &LoadOptions) -> Result<LintResult> {
     let mut result = LintResult::new();
-    let config_path = PathBuf::from(".cAGENTS/config.toml");
+    let config_path = options.project_config_path();
+    let config_file = config_path.display().to_string();
+    let config_file = config_file.as_str();
 
     // Check config exists
     if !config_path.exists() {
-        result.add_error(".cAGENTS/config.toml", "Config file not found");
+        result.add_error(config_file, "Config file not found");
         return Ok(result);
     }
 
     // Try to parse
-    match crate::config::load_config_with_precedence() {
+    match crate::config::load_config(options) {
         Ok(config) => {
             // Check templatesDir exists
             let templates_dir = PathBuf::from(".cAGENTS").join(&config.paths.templates_dir);
             if !templates_dir.exists() {
                 result.add_error(
-                    ".cAGENTS/config.toml",
+                    config_file,
                     &format!("templatesDir '{}' does not exist", config.paths.templates_dir)
                 );
             }
@@ -147,31 +147,45 @@ pub fn validate_config() -> Result<LintResult> {
             let output_root = PathBuf::from(&config.paths.output_root);
             if !output_root.exists() {
                 result.add_warning(
-                    ".cAGENTS/config.toml",
+                    config_file,
                     &format!("outputRoot '{}' does not exist", config.paths.output_root)
                 );
             }
 
-            // Validate output.targets values
+            // Validate output.targets values against the target registry
+            let registry = crate::targets::TargetRegistry::for_config(&config);
             if let Some(output) = &config.output {
                 if let Some(targets) = &output.targets {
                     for target in targets {
-                        if !VALID_TARGETS.contains(&target.as_str()) {
+                        if registry.get(target).is_none() {
                             result.add_error(
-                                ".cAGENTS/config.toml",
+                                config_file,
                                 &format!(
                                     "Unknown output target '{}'. Valid targets: {}",
                                     target,
-                                    VALID_TARGETS.join(", ")
+                                    registry.names().join(", ")
                                 )
                             );
                         }
                     }
                 }
             }
+
+            // Nested projects can't change where or what the root build writes
+            for dir in crate::nested::discover_nested_dirs()? {
+                for (path, setting) in crate::nested::unsupported_settings(&dir)? {
+                    result.add_error(
+                        &path.display().to_string(),
+                        &format!(
+                            "{} is not supported in nested projects; the root project's setting applies",
+                            setting
+                        )
+                    );
+                }
+            }
         }
         Err(e) => {
-            result.add_error(".cAGENTS/config.toml", &format!("{:#}", e));
+            result.add_error(config_file, &format!("{:#}", e));
         }
     }
 
@@ -179,15 +193,15 @@ pub fn validate_config() -> Result<LintResult> {
 }
 
 /// Validate templates
-pub fn validate_templates() -> Result<LintResult> {
+pub fn validate_templates(options: &LoadOptions) -> Result<LintResult> {
     let mut result = LintResult::new();
-    let config_path = PathBuf::from(".cAGENTS/config.toml");
+    let config_path = options.project_config_path();
 
     if !config_path.exists() {
         return Ok(result); // Config validation will catch this
     }
 
-    let config = match crate::config::load_config_with_precedence() {
+    let config = match crate::config::load_config(options) {
         Ok(c) => c,
         Err(_) => return Ok(result), // Config validation will catch parse errors
     };
@@ -209,6 +223,16 @@ pub fn validate_templates() -> Result<LintResult> {
                 );
             }
 
+            let registry = crate::targets::TargetRegistry::for_config(&config);
+
+            // Per-directory variable files may define variables for some outputs
+            let mut dir_vars = std::collections::HashSet::new();
+            for file in crate::variables::dir_variable_files() {
+                if let Ok(serde_json::Value::Object(values)) = crate::data_files::load_file(&file) {
+                    dir_vars.extend(values.keys().cloned());
+                }
+            }
+
             // Validate each template has required fields
             for rule in &rules {
                 let filename = rule.path.file_name()
@@ -226,13 +250,13 @@ pub fn validate_templates() -> Result<LintResult> {
                 if let Some(when) = &rule.frontmatter.when {
                     if let Some(targets) = &when.target {
                         for target in targets {
-                            if !VALID_TARGETS.contains(&target.as_str()) {
+                            if registry.get(target).is_none() {
                                 result.add_error(
                                     filename,
                                     &format!(
                                         "Invalid when.target value '{}'. Valid targets: {}",
                                         target,
-                                        VALID_TARGETS.join(", ")
+                                        registry.names().join(", ")
                                     )
                                 );
                             }
@@ -240,19 +264,69 @@ pub fn validate_templates() -> Result<LintResult> {
                     }
                 }
 
+                // Check when-clause variables against [variables.schema]
+                if let Some(when) = &rule.frontmatter.when {
+                    for message in check_when_against_schema(when, &config) {
+                        result.add_warning(filename, &message);
+                    }
+                }
+
                 // Check for undefined variables when using builtin:simple engine
                 let engine_spec = rule.frontmatter.engine.as_deref()
                     .or_else(|| config.defaults.as_ref().and_then(|d| d.engine.as_deref()));
 
+                // The rule's own vars (including a foreach item) are defined too
+                let mut rule_vars = dir_vars.clone();
+                if let Some(obj) = rule.frontmatter.vars.as_ref().and_then(|v| v.as_object()) {
+                    rule_vars.extend(obj.keys().cloned());
+                }
+
+                // So are its command variables, which must parse like [variables.command]
+                if let Some(command_vars) = &rule.frontmatter.command_vars {
+                    match command_vars.as_object() {
+                        Some(obj) => {
+                            for (key, spec) in obj {
+                                let parsed = crate::command_cache::CommandSpec::from_spec_in(key, spec, std::path::Path::new(""));
+                                if let Err(e) = parsed {
+                                    result.add_error(filename, &format!("Invalid commandVars: {:#}", e));
+                                }
+                            }
+                            rule_vars.extend(obj.keys().cloned());
+                        }
+                        None => result.add_error(filename, "commandVars must be a map of variable name to command"),
+                    }
+                }
+
                 if let Some(engine) = engine_spec {
                     if engine == "builtin:simple" {
                         // Extract variables from template body using {{var}} pattern
-                        if let Err(e) = validate_template_variables(&rule.body, &config, filename) {
+                        if let Err(e) = validate_template_variables(&rule.body, &config, &rule_vars, filename) {
                             result.add_error(filename, &e.to_string());
                         }
                     }
                 }
             }
+
+            // Per-rule targets name files after rules, so two rules may claim one file
+            let all_rules = crate::nested::discover_project_rules(&config, options).unwrap_or_default();
+            let all_rules: Vec<&crate::loader::Rule> = all_rules.iter().collect();
+            for target in crate::targets::configured_targets(&config) {
+                let Some(writer) = registry.get(&target) else {
+                    continue;
+                };
+                for (file, first, other) in crate::targets::rule_file_conflicts(writer, &all_rules) {
+                    result.add_error(
+                        &other.path.display().to_string(),
+                        &format!(
+                            "Rule {} writes {} for {}, like rule {}; rename one of them",
+                            crate::targets::describe_rule(other),
+                            file.display(),
+                            target,
+                            crate::targets::describe_rule(first)
+                        )
+                    );
+                }
+            }
         }
         Err(e) => {
             result.add_error("templates/", &format!("Failed to load templates: {}", e));
@@ -266,15 +340,17 @@ pub fn validate_templates() -> Result<LintResult> {
 fn validate_template_variables(
     template_body: &str,
     config: &crate::model::ProjectConfig,
+    extra_vars: &std::collections::HashSet<String>,
     _filename: &str,
 ) -> Result<()> {
     use regex::Regex;
 
-    let var_pattern = Regex::new(r"\{\{(\w+)\}\}").unwrap();
+    let var_pattern = Regex::new(r"\{\{(\w+)(?:\.\w+)*\}\}").unwrap();
     let mut undefined_vars = Vec::new();
 
-    // Collect available variables from config
+    // Collect available variables from config (plus the built-in facts)
     let mut available_vars = std::collections::HashSet::new();
+    available_vars.insert(crate::facts::NAMESPACE.to_string());
     if let Some(vars) = &config.variables {
         if let Some(static_vars) = &vars.static_ {
             if let Some(obj) = static_vars.as_object() {
@@ -283,15 +359,21 @@ fn validate_template_variables(
                 }
             }
         }
-        if let Some(cmd_vars) = &vars.command {
-            if let Some(obj) = cmd_vars.as_object() {
+        for section in [&vars.env, &vars.command, &vars.files] {
+            if let Some(obj) = section.as_ref().and_then(|v| v.as_object()) {
                 for key in obj.keys() {
                     available_vars.insert(key.clone());
                 }
             }
         }
+        // Declared variables always have a value (default, or required and prompted for)
+        if let Some(schema) = &vars.schema {
+            available_vars.extend(schema.keys().cloned());
+        }
     }
 
+    available_vars.extend(extra_vars.iter().cloned());
+
     // Find all {{variable}} patterns in template
     for cap in var_pattern.captures_iter(template_body) {
         let var_name = cap[1].to_string();
@@ -311,18 +393,68 @@ fn validate_template_variables(
     Ok(())
 }
 
+/// Warnings for when-clause keys and values that `[variables.schema]` doesn't allow
+///
+/// Only applies once a schema is declared; keys that are set as variables without a
+/// declaration are accepted.
+fn check_when_against_schema(
+    when: &crate::model::When,
+    config: &crate::model::ProjectConfig,
+) -> Vec<String> {
+    let mut messages = Vec::new();
+    let Some(vars) = &config.variables else {
+        return messages;
+    };
+    let Some(schema) = &vars.schema else {
+        return messages;
+    };
+
+    let is_set = |key: &str| {
+        [&vars.static_, &vars.env, &vars.command, &vars.files]
+            .iter()
+            .any(|section| section.as_ref().and_then(|v| v.get(key)).is_some())
+    };
+
+    let mut keys: Vec<_> = when.variables.keys().collect();
+    keys.sort();
+    for key in keys {
+        match schema.get(key) {
+            Some(declaration) => {
+                let values = match &when.variables[key] {
+                    serde_json::Value::Array(items) => items.clone(),
+                    value => vec![value.clone()],
+                };
+                for value in values {
+                    if let Err(e) = crate::variables::check_value(key, declaration, &value) {
+                        messages.push(format!("when.{}: {}", key, e));
+                    }
+                }
+            }
+            None if !is_set(key) => {
+                messages.push(format!(
+                    "when.{} uses a variable that is not declared in [variables.schema]",
+                    key
+                ));
+            }
+            None => {}
+        }
+    }
+
+    messages
+}
+
 /// Run all lint checks
-pub fn lint_all() -> Result<LintResult> {
+pub fn lint_all(options: &LoadOptions) -> Result<LintResult> {
     let mut result = LintResult::new();
 
     // Validate config
-    let config_result = validate_config()?;
+    let config_result = validate_config(options)?;
     let has_config_errors = config_result.has_errors();
     result.issues.extend(config_result.issues);
 
     // Validate templates (only if config is valid)
     if !has_config_errors {
-        let template_result = validate_templates()?;
+        let template_result = validate_templates(options)?;
         result.issues.extend(template_result.issues);
     }
 
@@ -1,6 +1,6 @@
-// discover templates, parse front-matter (YAML), return in-memory rules
+// discover templates, parse front-matter (YAML), expand `foreach`, return in-memory rules
 
-use crate::model::{ProjectConfig, RuleFrontmatter};
+use crate::model::{Foreach, ForeachSpec, ProjectConfig, RuleFrontmatter};
 use anyhow::{Context, Result};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -49,7 +49,7 @@ pub fn discover_rules(config: &ProjectConfig, base_dir: &Path) -> Result<Vec<Rul
             if let Some(filename) = path.file_name().and_then(|s| s.to_str()) {
                 if filename.ends_with(".md") {
                     let rule = parse_rule_file(&path)?;
-                    rules.push(rule);
+                    rules.extend(expand_foreach(rule, config, base_dir)?);
                 }
             }
         }
@@ -61,6 +61,114 @@ pub fn discover_rules(config: &ProjectConfig, base_dir: &Path) -> Result<Vec<Rul
     Ok(rules)
 }
 
+/// Expand a rule with `foreach:` into one synthetic rule per list item
+///
+/// Each instance binds the item as a frontmatter var (`as`, default `item`), and its
+/// name, description, globs and outputIn are rendered with `{{item...}}` placeholders.
+/// Instances whose name doesn't change per item get a `-<index>` suffix. Rules without
+/// `foreach` are returned unchanged.
+pub fn expand_foreach(rule: Rule, config: &ProjectConfig, base_dir: &Path) -> Result<Vec<Rule>> {
+    let Some(foreach) = rule.frontmatter.foreach.clone() else {
+        return Ok(vec![rule]);
+    };
+    let spec = match foreach {
+        Foreach::Variable(var) => ForeachSpec {
+            var: Some(var),
+            ..Default::default()
+        },
+        Foreach::Spec(spec) => spec,
+    };
+
+    let items = foreach_items(&spec, config, base_dir)
+        .with_context(|| format!("Failed to expand foreach in: {}", rule.path.display()))?;
+    let binding = spec.as_.as_deref().unwrap_or("item");
+
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(index, item)| {
+            let data = serde_json::json!({ binding: item });
+            let render = |text: &str| {
+                crate::adapters::builtin::render_simple(text, &data).with_context(|| {
+                    format!("Failed to expand foreach item {} in: {}", index, rule.path.display())
+                })
+            };
+
+            let mut instance = rule.clone();
+            let fm = &mut instance.frontmatter;
+            fm.foreach = None;
+
+            let base_name = fm.name.clone().unwrap_or_else(|| rule_stem(&rule.path));
+            let name = render(&base_name)?;
+            fm.name = Some(if name == base_name { format!("{}-{}", name, index) } else { name });
+            if let Some(description) = &fm.description {
+                fm.description = Some(render(description)?);
+            }
+            if let Some(globs) = &fm.globs {
+                fm.globs = Some(globs.iter().map(|g| render(g)).collect::<Result<_>>()?);
+            }
+            if let Some(output_in) = &fm.output_in {
+                fm.output_in = Some(render(output_in)?);
+            }
+
+            // The item is a rule var; the rule's own vars keep precedence
+            let mut vars = serde_json::Map::new();
+            vars.insert(binding.to_string(), data[binding].clone());
+            if let Some(own) = fm.vars.as_ref().and_then(|v| v.as_object()) {
+                vars.extend(own.clone());
+            }
+            fm.vars = Some(serde_json::Value::Object(vars));
+
+            Ok(instance)
+        })
+        .collect()
+}
+
+/// The list a `foreach` iterates over
+fn foreach_items(spec: &ForeachSpec, config: &ProjectConfig, base_dir: &Path) -> Result<Vec<serde_json::Value>> {
+    let source = match (&spec.var, &spec.file) {
+        (Some(var), None) => {
+            let vars = config.variables.as_ref();
+            match vars.and_then(|v| v.static_.as_ref()).and_then(|s| s.get(var)) {
+                Some(value) => value.clone(),
+                None => crate::data_files::data_files(config)?
+                    .get(var)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "foreach variable '{}' is not defined in [variables.static] or [variables.files]",
+                            var
+                        )
+                    })?
+                    .load()?,
+            }
+        }
+        (None, Some(file)) => {
+            let project_dir = base_dir.parent().unwrap_or(Path::new(""));
+            crate::data_files::load_file(&project_dir.join(file))?
+        }
+        _ => anyhow::bail!("foreach needs exactly one of 'var' or 'file'"),
+    };
+
+    let selected = match &spec.select {
+        Some(selector) => crate::data_files::select(&source, selector)?,
+        None => source,
+    };
+
+    match selected {
+        serde_json::Value::Array(items) => Ok(items),
+        other => anyhow::bail!("foreach expects a list, got {}", other),
+    }
+}
+
+/// File name without the template extensions (`service.hbs.md` -> `service`)
+fn rule_stem(path: &Path) -> String {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .and_then(|n| n.split('.').next())
+        .unwrap_or("rule")
+        .to_string()
+}
+
 /// Parse a single rule file, extracting YAML frontmatter and body
 fn parse_rule_file(path: &Path) -> Result<Rule> {
     let content = fs::read_to_string(path)
@@ -71,6 +179,12 @@ fn parse_rule_file(path: &Path) -> Result<Rule> {
     let frontmatter: RuleFrontmatter = serde_yaml::from_str(&frontmatter)
         .with_context(|| format!("Failed to parse frontmatter in: {}", path.display()))?;
 
+    // Claude Code picks skills by their description
+    let has_description = frontmatter.description.as_deref().is_some_and(|d| !d.trim().is_empty());
+    if frontmatter.skill == Some(true) && !has_description {
+        anyhow::bail!("Rule with skill: true must have a description: {}", path.display());
+    }
+
     Ok(Rule {
         frontmatter,
         body,
@@ -108,6 +222,76 @@ fn split_frontmatter(content: &str) -> Result<(String, String)> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model::{Paths, Variables};
+    use serde_json::json;
+
+    fn config_with_static(values: serde_json::Value) -> ProjectConfig {
+        ProjectConfig {
+            project: None,
+            paths: Paths {
+                templates_dir: "templates".to_string(),
+                output_root: ".".to_string(),
+                cursor_rules_dir: None,
+            },
+            defaults: None,
+            variables: Some(Variables {
+                static_: Some(values),
+                ..Default::default()
+            }),
+            execution: None,
+            output: None,
+            matrix: None,
+            variable_origins: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_expand_foreach_over_variable() {
+        let config = config_with_static(json!({
+            "services": [{"name": "api"}, {"name": "web"}]
+        }));
+        let rule = Rule {
+            frontmatter: RuleFrontmatter {
+                name: Some("service-{{service.name}}".to_string()),
+                globs: Some(vec!["services/{{service.name}}/**".to_string()]),
+                foreach: Some(Foreach::Spec(ForeachSpec {
+                    var: Some("services".to_string()),
+                    as_: Some("service".to_string()),
+                    ..Default::default()
+                })),
+                ..Default::default()
+            },
+            body: "# {{service.name}}".to_string(),
+            path: PathBuf::from(".cAGENTS/templates/service.md"),
+        };
+
+        let rules = expand_foreach(rule, &config, Path::new(".cAGENTS")).unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[1].frontmatter.name.as_deref(), Some("service-web"));
+        assert_eq!(rules[1].frontmatter.globs.as_ref().unwrap(), &vec!["services/web/**".to_string()]);
+        assert_eq!(rules[1].frontmatter.vars.as_ref().unwrap()["service"], json!({"name": "web"}));
+        assert!(rules[1].frontmatter.foreach.is_none());
+    }
+
+    #[test]
+    fn test_expand_foreach_suffixes_constant_names() {
+        let config = config_with_static(json!({"langs": ["rust", "go"]}));
+        let rule = Rule {
+            frontmatter: RuleFrontmatter {
+                foreach: Some(Foreach::Variable("langs".to_string())),
+                ..Default::default()
+            },
+            body: String::new(),
+            path: PathBuf::from(".cAGENTS/templates/lang.md"),
+        };
+
+        let names: Vec<_> = expand_foreach(rule, &config, Path::new(".cAGENTS"))
+            .unwrap()
+            .into_iter()
+            .map(|r| r.frontmatter.name.unwrap())
+            .collect();
+        assert_eq!(names, vec!["lang-0", "lang-1"]);
+    }
 
     #[test]
     fn test_split_frontmatter() {
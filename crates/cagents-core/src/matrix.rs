@@ -0,0 +1,294 @@
+// expand [matrix] variables into one build variant per combination
+
+use crate::model::Matrix;
+use crate::output_diff::normalize;
+use anyhow::Result;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// One build of the project: a combination of matrix values and where it is written
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    /// Matrix variable values for this combination (empty without a matrix)
+    pub variables: BTreeMap<String, String>,
+    /// Root directory that outputs of this variant are written under
+    pub output_root: PathBuf,
+    /// File name suffix for the "suffix" layout (e.g. "backend" -> AGENTS.backend.md)
+    pub suffix: Option<String>,
+}
+
+impl Variant {
+    /// Human-readable label, e.g. "role=backend, tier=api"
+    pub fn label(&self) -> Option<String> {
+        if self.variables.is_empty() {
+            return None;
+        }
+
+        Some(
+            self.variables
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+    }
+
+    /// File name for a target in this variant, applying the suffix if any
+    pub fn file_name(&self, file_name: &str) -> String {
+        match &self.suffix {
+            Some(suffix) => suffixed_file_name(file_name, suffix),
+            None => file_name.to_string(),
+        }
+    }
+
+    /// Whether an output file was written by this variant: it carries the variant's
+    /// suffix, or (directory layout) sits under its output root
+    pub fn owns(&self, path: &Path) -> bool {
+        match &self.suffix {
+            Some(suffix) => {
+                let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+                let ending = format!(".{}", suffix);
+                name.ends_with(&ending)
+                    || name.rfind('.').is_some_and(|idx| idx > 0 && name[..idx].ends_with(&ending))
+            }
+            None => normalize(path).starts_with(normalize(&self.output_root)),
+        }
+    }
+}
+
+/// Expand the matrix into build variants
+///
+/// Without a matrix there is a single variant writing to `output_root`.
+/// Variables pinned on the command line (`--var role=backend`) collapse their
+/// matrix axis to that single value.
+pub fn expand(
+    matrix: Option<&Matrix>,
+    output_root: &str,
+    pinned: &HashMap<String, String>,
+) -> Result<Vec<Variant>> {
+    let Some(matrix) = matrix else {
+        return Ok(vec![Variant {
+            variables: BTreeMap::new(),
+            output_root: PathBuf::from(output_root),
+            suffix: None,
+        }]);
+    };
+
+    let layout = matrix.layout.as_deref().unwrap_or("directory");
+    if layout != "directory" && layout != "suffix" {
+        anyhow::bail!(
+            "Unknown matrix.layout '{}'. Expected \"directory\" or \"suffix\"",
+            layout
+        );
+    }
+
+    if matrix.variables.is_empty() {
+        anyhow::bail!("[matrix.variables] is empty; add a variable or remove [matrix]");
+    }
+
+    let mut combinations: Vec<BTreeMap<String, String>> = vec![BTreeMap::new()];
+
+    for (name, values) in &matrix.variables {
+        if values.is_empty() {
+            anyhow::bail!("Matrix variable '{}' has no values", name);
+        }
+
+        let values = match pinned.get(name) {
+            Some(value) if values.contains(value) => vec![value.clone()],
+            Some(value) => anyhow::bail!(
+                "'{}' is not a value of matrix variable '{}' (allowed: {})",
+                value,
+                name,
+                values.join(", ")
+            ),
+            None => values.clone(),
+        };
+
+        combinations = combinations
+            .into_iter()
+            .flat_map(|combo| {
+                values.iter().map(move |value| {
+                    let mut next = combo.clone();
+                    next.insert(name.clone(), value.clone());
+                    next
+                })
+            })
+            .collect();
+    }
+
+    let variants = combinations
+        .into_iter()
+        .map(|variables| {
+            let name = variables.values().cloned().collect::<Vec<_>>().join("-");
+
+            if layout == "suffix" {
+                Variant {
+                    variables,
+                    output_root: PathBuf::from(output_root),
+                    suffix: Some(name),
+                }
+            } else {
+                let root = match &matrix.output_root {
+                    Some(pattern) => PathBuf::from(substitute(pattern, &variables)),
+                    None => Path::new(output_root).join(&name),
+                };
+                Variant {
+                    variables,
+                    output_root: root,
+                    suffix: None,
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // Names join only the values, so {a="x-y", b="z"} and {a="x", b="y-z"} would collide
+    for (index, variant) in variants.iter().enumerate() {
+        if let Some(other) = variants[..index]
+            .iter()
+            .find(|other| other.output_root == variant.output_root && other.suffix == variant.suffix)
+        {
+            anyhow::bail!(
+                "Matrix variants ({}) and ({}) write the same files; change a value so their names differ",
+                other.label().unwrap_or_default(),
+                variant.label().unwrap_or_default()
+            );
+        }
+    }
+
+    Ok(variants)
+}
+
+/// Variants of the full matrix that a build with `pinned` values skips
+///
+/// Their outputs from earlier builds are neither stale nor forgotten by the output cache.
+pub fn unbuilt(
+    matrix: Option<&Matrix>,
+    output_root: &str,
+    pinned: &HashMap<String, String>,
+) -> Result<Vec<Variant>> {
+    let built = expand(matrix, output_root, pinned)?;
+    Ok(expand(matrix, output_root, &HashMap::new())?
+        .into_iter()
+        .filter(|variant| !built.contains(variant))
+        .collect())
+}
+
+/// Replace `{name}` placeholders with matrix values
+fn substitute(pattern: &str, variables: &BTreeMap<String, String>) -> String {
+    let mut result = pattern.to_string();
+    for (name, value) in variables {
+        result = result.replace(&format!("{{{}}}", name), value);
+    }
+    result
+}
+
+/// Insert a suffix before the file extension: AGENTS.md -> AGENTS.backend.md
+/// Dotfiles without an extension get it appended: .cursorrules -> .cursorrules.backend
+pub fn suffixed_file_name(file_name: &str, suffix: &str) -> String {
+    match file_name.rfind('.') {
+        Some(idx) if idx > 0 => format!("{}.{}{}", &file_name[..idx], suffix, &file_name[idx..]),
+        _ => format!("{}.{}", file_name, suffix),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix(layout: Option<&str>, vars: &[(&str, &[&str])]) -> Matrix {
+        Matrix {
+            layout: layout.map(|s| s.to_string()),
+            output_root: None,
+            variables: vars
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.iter().map(|s| s.to_string()).collect()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_no_matrix_single_variant() {
+        let variants = expand(None, ".", &HashMap::new()).unwrap();
+        assert_eq!(variants.len(), 1);
+        assert_eq!(variants[0].output_root, PathBuf::from("."));
+        assert!(variants[0].label().is_none());
+        assert_eq!(variants[0].file_name("AGENTS.md"), "AGENTS.md");
+    }
+
+    #[test]
+    fn test_cartesian_product() {
+        let m = matrix(None, &[("role", &["frontend", "backend"]), ("tier", &["a", "b", "c"])]);
+        let variants = expand(Some(&m), ".", &HashMap::new()).unwrap();
+        assert_eq!(variants.len(), 6);
+        assert!(variants.iter().any(|v| v.output_root == Path::new("./backend-c")));
+    }
+
+    #[test]
+    fn test_pinned_variable_collapses_axis() {
+        let m = matrix(None, &[("role", &["frontend", "backend"])]);
+        let mut pinned = HashMap::new();
+        pinned.insert("role".to_string(), "backend".to_string());
+
+        let variants = expand(Some(&m), ".", &pinned).unwrap();
+        assert_eq!(variants.len(), 1);
+        assert_eq!(variants[0].variables.get("role").unwrap(), "backend");
+    }
+
+    #[test]
+    fn test_unbuilt_variants_own_their_files() {
+        let m = matrix(Some("suffix"), &[("role", &["frontend", "backend"])]);
+        let mut pinned = HashMap::new();
+        pinned.insert("role".to_string(), "backend".to_string());
+
+        let skipped = unbuilt(Some(&m), ".", &pinned).unwrap();
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped[0].owns(Path::new("./AGENTS.frontend.md")));
+        assert!(skipped[0].owns(Path::new("api/.cursorrules.frontend")));
+        assert!(!skipped[0].owns(Path::new("AGENTS.backend.md")));
+        assert!(unbuilt(Some(&m), ".", &HashMap::new()).unwrap().is_empty());
+
+        let m = matrix(None, &[("role", &["frontend", "backend"])]);
+        let skipped = unbuilt(Some(&m), ".", &pinned).unwrap();
+        assert!(skipped[0].owns(Path::new("frontend/api/CLAUDE.md")));
+        assert!(!skipped[0].owns(Path::new("./backend/AGENTS.md")));
+    }
+
+    #[test]
+    fn test_directory_layout_pattern() {
+        let mut m = matrix(Some("directory"), &[("role", &["frontend"])]);
+        m.output_root = Some("dist/{role}/agents".to_string());
+
+        let variants = expand(Some(&m), ".", &HashMap::new()).unwrap();
+        assert_eq!(variants[0].output_root, PathBuf::from("dist/frontend/agents"));
+    }
+
+    #[test]
+    fn test_suffix_layout() {
+        let m = matrix(Some("suffix"), &[("role", &["backend"])]);
+        let variants = expand(Some(&m), ".", &HashMap::new()).unwrap();
+        assert_eq!(variants[0].output_root, PathBuf::from("."));
+        assert_eq!(variants[0].file_name("AGENTS.md"), "AGENTS.backend.md");
+        assert_eq!(variants[0].file_name(".cursorrules"), ".cursorrules.backend");
+    }
+
+    #[test]
+    fn test_invalid_matrices_fail() {
+        let m = matrix(None, &[("role", &["frontend", "backend"])]);
+        let mut pinned = HashMap::new();
+        pinned.insert("role".to_string(), "qa".to_string());
+        let err = expand(Some(&m), ".", &pinned).unwrap_err().to_string();
+        assert!(err.contains("allowed: frontend, backend"), "{}", err);
+
+        assert!(expand(Some(&matrix(Some("suffix"), &[])), ".", &HashMap::new()).is_err());
+
+        let m = matrix(Some("suffix"), &[("a", &["x-y", "x"]), ("b", &["z", "y-z"])]);
+        let err = expand(Some(&m), ".", &HashMap::new()).unwrap_err().to_string();
+        assert!(err.contains("(a=x, b=y-z) write the same files"), "{}", err);
+    }
+
+    #[test]
+    fn test_unknown_layout_fails() {
+        let m = matrix(Some("zip"), &[("role", &["backend"])]);
+        assert!(expand(Some(&m), ".", &HashMap::new()).is_err());
+    }
+}
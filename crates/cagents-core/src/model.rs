@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
+use std::path::PathBuf;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
@@ -10,6 +11,63 @@ pub struct ProjectConfig {
     pub variables: Option<Variables>,
     pub execution: Option<Execution>,
     pub output: Option<Output>,
+    pub matrix: Option<Matrix>,
+    /// Which config file declared each variable (filled in by the config merge)
+    #[serde(skip)]
+    pub variable_origins: VariableOrigins,
+}
+
+/// Config file layer, in precedence order (later overrides earlier)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConfigLayer {
+    User,
+    Project,
+    Local,
+    /// `CAGENTS_*` environment variable overrides (the origin file is the variable name)
+    Env,
+}
+
+impl std::fmt::Display for ConfigLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ConfigLayer::User => "user",
+            ConfigLayer::Project => "project",
+            ConfigLayer::Local => "local",
+            ConfigLayer::Env => "env",
+        };
+        f.write_str(name)
+    }
+}
+
+impl std::str::FromStr for ConfigLayer {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "user" => Ok(ConfigLayer::User),
+            "project" => Ok(ConfigLayer::Project),
+            "local" => Ok(ConfigLayer::Local),
+            "env" => Ok(ConfigLayer::Env),
+            _ => anyhow::bail!("Unknown config layer '{}'. Expected user, project, local or env", s),
+        }
+    }
+}
+
+/// The config file a value was read from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigOrigin {
+    pub layer: ConfigLayer,
+    pub file: PathBuf,
+}
+
+/// Origin of each `[variables.*]` key, per variable kind
+#[derive(Debug, Clone, Default)]
+pub struct VariableOrigins {
+    pub static_: BTreeMap<String, ConfigOrigin>,
+    pub env: BTreeMap<String, ConfigOrigin>,
+    pub command: BTreeMap<String, ConfigOrigin>,
+    pub files: BTreeMap<String, ConfigOrigin>,
 }
 
 /// Partial config that can be loaded from individual config files
@@ -23,6 +81,37 @@ pub struct PartialProjectConfig {
     pub variables: Option<Variables>,
     pub execution: Option<Execution>,
     pub output: Option<Output>,
+    pub matrix: Option<Matrix>,
+    /// Named overlays selected with `--profile` / `CAGENTS_PROFILE`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profiles: Option<BTreeMap<String, Profile>>,
+    /// `[telemetry]` settings are read by cagents-telemetry; accepted here so shared files parse
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub telemetry: Option<serde_json::Value>,
+}
+
+/// `[profiles.<name>]`: settings layered on top of the regular config when the profile is active
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    pub paths: Option<PartialPaths>,
+    pub defaults: Option<Defaults>,
+    pub variables: Option<Variables>,
+    pub execution: Option<Execution>,
+    pub output: Option<Output>,
+}
+
+impl From<Profile> for PartialProjectConfig {
+    fn from(profile: Profile) -> Self {
+        PartialProjectConfig {
+            paths: profile.paths,
+            defaults: profile.defaults,
+            variables: profile.variables,
+            execution: profile.execution,
+            output: profile.output,
+            ..Default::default()
+        }
+    }
 }
 
 /// Partial paths - all fields optional for partial configs
@@ -67,6 +156,23 @@ pub struct Variables {
     pub static_: Option<serde_json::Value>,
     pub env: Option<serde_json::Value>,
     pub command: Option<serde_json::Value>,
+    /// Data files: `name = "path"` or `name = { path = "...", select = "$.a.b" }`
+    pub files: Option<serde_json::Value>,
+    /// Typed declarations, validated at load and by `lint`
+    pub schema: Option<BTreeMap<String, VariableSchema>>,
+}
+
+/// `[variables.schema.<name>]`: type, allowed values, default and documentation for a variable
+#[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct VariableSchema {
+    /// "string", "number", "boolean", "array" or "object"
+    #[serde(rename = "type")]
+    pub type_: Option<String>,
+    pub allowed: Option<Vec<serde_json::Value>>,
+    pub default: Option<serde_json::Value>,
+    pub description: Option<String>,
+    pub required: Option<bool>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -83,6 +189,66 @@ pub struct Execution {
 #[serde(deny_unknown_fields)]
 pub struct Output {
     pub targets: Option<Vec<String>>,
+    /// User-defined targets by name (`[output.custom.<name>]`)
+    pub custom: Option<BTreeMap<String, CustomTargetSpec>>,
+    /// List the skills written by `claude-md` at the end of CLAUDE.md
+    #[serde(rename = "listSkills")]
+    pub list_skills: Option<bool>,
+    /// Settings for the `claude-md` target (`[output.claude-md]`)
+    #[serde(rename = "claude-md")]
+    pub claude_md: Option<ClaudeMdOutput>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ClaudeMdOutput {
+    pub mode: Option<ClaudeMdMode>,
+}
+
+/// How `claude-md` writes CLAUDE.md where `agents-md` also writes AGENTS.md
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ClaudeMdMode {
+    /// The merged rules, like every other target
+    #[default]
+    Full,
+    /// `@AGENTS.md` plus the rules only CLAUDE.md gets
+    Import,
+    /// A symlink to AGENTS.md (`import` where CLAUDE.md has rules of its own)
+    Symlink,
+}
+
+/// A user-defined output target (`[output.custom.<name>]`)
+#[derive(Debug, Clone, Deserialize, Serialize, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CustomTargetSpec {
+    /// File written in each output directory, e.g. "AI_GUIDE.md" or "docs/agents/README.md"
+    pub filename: String,
+    /// Text before the rules (`{{var}}` placeholders are filled in)
+    pub header: Option<String>,
+    /// Text after the rules (`{{var}}` placeholders are filled in)
+    pub footer: Option<String>,
+    /// Only write the file at the output root
+    #[serde(rename = "rootOnly")]
+    pub root_only: Option<bool>,
+    /// Template around the merged rules; `{{content}}` marks where they go
+    pub wrapper: Option<String>,
+    /// Name shown in listings (defaults to the file name)
+    #[serde(rename = "displayName")]
+    pub display_name: Option<String>,
+}
+
+/// Variable matrix: build every combination of the listed variable values
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Matrix {
+    /// Output layout per combination: "directory" (default) or "suffix"
+    pub layout: Option<String>,
+    /// Output root pattern for the directory layout, e.g. "dist/{role}"
+    #[serde(rename = "outputRoot")]
+    pub output_root: Option<String>,
+    /// Variable name -> values to build
+    pub variables: BTreeMap<String, Vec<String>>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
@@ -95,12 +261,46 @@ pub struct RuleFrontmatter {
     pub order: Option<i32>,
     pub when: Option<When>,
     pub vars: Option<serde_json::Value>,
+    /// Command-backed variables, run only when the rule renders (see `variables::RuleCommands`)
+    #[serde(rename = "commandVars")]
+    pub command_vars: Option<serde_json::Value>,
     pub merge: Option<Merge>,
     pub links: Option<Vec<Link>>,
     pub targets: Option<Vec<String>>,
     pub extends: Option<Vec<String>>,
     #[serde(rename = "outputIn")]
     pub output_in: Option<String>,
+    /// Expand into one rule per list item (see `loader::expand_foreach`)
+    pub foreach: Option<Foreach>,
+    /// Emit as a Claude Code skill (`.claude/skills/<name>/SKILL.md`) instead of
+    /// merging into CLAUDE.md; requires a description
+    pub skill: Option<bool>,
+    /// Variables of the nested project the rule belongs to, seen by its `when` clause
+    /// (set by `nested::scope_rule`, never read from frontmatter)
+    #[serde(skip)]
+    pub scope_vars: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+/// `foreach:` either names a list variable or describes where the list comes from
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(untagged)]
+pub enum Foreach {
+    Variable(String),
+    Spec(ForeachSpec),
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct ForeachSpec {
+    /// `[variables.static]` or `[variables.files]` variable holding the list
+    pub var: Option<String>,
+    /// Data file (JSON/YAML/TOML) relative to the project
+    pub file: Option<String>,
+    /// JSONPath-like selector applied to the variable or file
+    pub select: Option<String>,
+    /// Name the item is bound to (default "item")
+    #[serde(rename = "as")]
+    pub as_: Option<String>,
 }
 
 impl RuleFrontmatter {
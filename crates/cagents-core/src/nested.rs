@@ -0,0 +1,326 @@
+// discover nested .cAGENTS projects (monorepo packages) and scope their rules to their subtree
+
+use crate::config::{self, LoadOptions};
+use crate::loader::{self, Rule};
+use crate::model::ProjectConfig;
+use crate::variables;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// A `.cAGENTS` project below the root project
+#[derive(Debug, Clone)]
+pub struct NestedProject {
+    /// Directory containing the nested `.cAGENTS/`, relative to the project root
+    pub dir: PathBuf,
+    /// Root config with every nested config on the path to `dir` merged on top
+    pub config: ProjectConfig,
+}
+
+/// Directories below the project root (current directory) that contain `.cAGENTS/config.toml`
+///
+/// Respects .gitignore and skips hidden directories, node_modules, target and dist.
+/// Sorted so parents come before the projects nested inside them.
+pub fn discover_nested_dirs() -> Result<Vec<PathBuf>> {
+    let mut dirs = Vec::new();
+
+    let walker = ignore::WalkBuilder::new(".")
+        .hidden(true)
+        .filter_entry(|e| {
+            let name = e.file_name().to_string_lossy();
+            name != "node_modules" && name != "target" && name != "dist"
+        })
+        .build();
+
+    for entry in walker {
+        let entry = entry?;
+        if !entry.file_type().is_some_and(|t| t.is_dir()) || entry.depth() == 0 {
+            continue;
+        }
+
+        if entry.path().join(".cAGENTS/config.toml").is_file() {
+            let dir = entry.path().strip_prefix(".").unwrap_or(entry.path());
+            dirs.push(dir.to_path_buf());
+        }
+    }
+
+    dirs.sort();
+    Ok(dirs)
+}
+
+/// Load every nested project with its merged config
+pub fn discover_nested_projects(options: &LoadOptions) -> Result<Vec<NestedProject>> {
+    let dirs = discover_nested_dirs()?;
+
+    dirs.iter()
+        .map(|dir| {
+            // Nested projects between the root and this one, outermost first
+            let chain: Vec<PathBuf> = dirs
+                .iter()
+                .filter(|other| dir.starts_with(other))
+                .cloned()
+                .collect();
+
+            let config = config::load_nested_config(&chain, options)
+                .with_context(|| format!("Failed to load nested project config in {}", dir.display()))?;
+
+            Ok(NestedProject {
+                dir: dir.clone(),
+                config,
+            })
+        })
+        .collect()
+}
+
+/// Settings in a nested project's own config files that only the root config controls
+///
+/// A root build writes every output with the root's `[output]`, `outputRoot` and
+/// `[matrix]`, so setting them in a nested project would have no effect. `outputRoot = "."`
+/// is accepted, so a package can also be used as a project on its own.
+pub fn unsupported_settings(dir: &Path) -> Result<Vec<(PathBuf, &'static str)>> {
+    let mut found = Vec::new();
+
+    for file in ["config.toml", "config.local.toml"] {
+        let path = dir.join(".cAGENTS").join(file);
+        if !path.is_file() {
+            continue;
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read config: {}", path.display()))?;
+        let partial = config::parse_partial_config(&content)
+            .with_context(|| format!("Failed to parse config: {}", path.display()))?;
+
+        if partial.output.is_some() {
+            found.push((path.clone(), "[output]"));
+        }
+        if partial
+            .paths
+            .as_ref()
+            .and_then(|p| p.output_root.as_deref())
+            .is_some_and(|root| root != ".")
+        {
+            found.push((path.clone(), "paths.outputRoot"));
+        }
+        if partial.matrix.is_some() {
+            found.push((path.clone(), "[matrix]"));
+        }
+    }
+
+    Ok(found)
+}
+
+/// Rules of the root project plus the rules of every nested project, scoped to their subtree
+pub fn discover_project_rules(config: &ProjectConfig, options: &LoadOptions) -> Result<Vec<Rule>> {
+    let mut rules = loader::discover_rules(config, Path::new(".cAGENTS"))?;
+
+    for project in discover_nested_projects(options)? {
+        rules.extend(discover_nested_rules(&project)?);
+    }
+
+    // Keep the combined list in order (stable, so root rules come first on ties)
+    rules.sort_by_key(|r| r.frontmatter.order.unwrap_or(50));
+
+    Ok(rules)
+}
+
+/// What a nested project adds to each of its rules (see [`scope_rule`])
+#[derive(Debug, Clone, Default)]
+pub struct RuleScope {
+    /// Directory containing the nested `.cAGENTS/`, relative to the project root
+    pub dir: PathBuf,
+    /// Template data for variables set by the nested configs, without command variables
+    pub data: serde_json::Map<String, serde_json::Value>,
+    /// `[variables.command]` entries of the nested configs, run when a rule renders
+    pub commands: serde_json::Map<String, serde_json::Value>,
+    /// The nested `defaults.engine`
+    pub engine: Option<String>,
+}
+
+impl RuleScope {
+    pub fn for_project(project: &NestedProject) -> Result<Self> {
+        let (data, commands) = nested_variables(&project.config, &project.dir)?;
+        Ok(Self {
+            dir: project.dir.clone(),
+            data,
+            commands,
+            engine: project.config.defaults.as_ref().and_then(|d| d.engine.clone()),
+        })
+    }
+}
+
+/// Rules of one nested project, rewritten to apply only inside its directory
+pub fn discover_nested_rules(project: &NestedProject) -> Result<Vec<Rule>> {
+    let base_dir = project.dir.join(".cAGENTS");
+
+    // A nested project may only override config without adding templates
+    if !base_dir.join(&project.config.paths.templates_dir).exists() {
+        return Ok(Vec::new());
+    }
+
+    let scope = RuleScope::for_project(project)?;
+    let rules = loader::discover_rules(&project.config, &base_dir)?
+        .into_iter()
+        .map(|rule| scope_rule(rule, &scope))
+        .collect();
+
+    Ok(rules)
+}
+
+/// Rewrite a nested rule so it applies within `scope.dir`
+///
+/// - globs are prefixed with the directory; rules without globs cover the whole subtree
+///   and are written to the nested project's directory
+/// - variables set by the nested configs become rule vars (the rule's own vars win) and
+///   are seen by the rule's `when` clause
+/// - nested command variables become `commandVars`, so they only run when the rule renders
+/// - the nested `defaults.engine` applies when the rule doesn't set one
+pub fn scope_rule(mut rule: Rule, scope: &RuleScope) -> Rule {
+    let prefix = scope.dir.to_string_lossy().replace('\\', "/");
+    let fm = &mut rule.frontmatter;
+
+    match fm.globs.as_mut().filter(|globs| !globs.is_empty()) {
+        Some(globs) => {
+            for glob in globs.iter_mut() {
+                *glob = format!("{}/{}", prefix, glob.trim_start_matches("./"));
+            }
+        }
+        None => {
+            fm.globs = Some(vec![format!("{}/**", prefix)]);
+            fm.output_in = Some("common-parent".to_string());
+        }
+    }
+
+    if !scope.data.is_empty() {
+        fm.vars = Some(under(&scope.data, fm.vars.as_ref()));
+        fm.scope_vars = Some(scope.data.clone());
+    }
+
+    if !scope.commands.is_empty() {
+        fm.command_vars = Some(under(&scope.commands, fm.command_vars.as_ref()));
+    }
+
+    if fm.engine.is_none() {
+        fm.engine = scope.engine.clone();
+    }
+
+    rule
+}
+
+/// `base` with the entries of `own` (a frontmatter map) on top
+fn under(
+    base: &serde_json::Map<String, serde_json::Value>,
+    own: Option<&serde_json::Value>,
+) -> serde_json::Value {
+    let mut merged = base.clone();
+    if let Some(own) = own.and_then(|v| v.as_object()) {
+        for (key, value) in own {
+            merged.insert(key.clone(), value.clone());
+        }
+    }
+    serde_json::Value::Object(merged)
+}
+
+/// Variables that were set by a nested project's own config files: template data for
+/// static, env and file variables, and the unresolved `[variables.command]` entries
+fn nested_variables(
+    config: &ProjectConfig,
+    dir: &Path,
+) -> Result<(serde_json::Map<String, serde_json::Value>, serde_json::Map<String, serde_json::Value>)> {
+    let mut scoped = config.clone();
+    let origins = &config.variable_origins;
+    let from_nested = |origin: Option<&crate::model::ConfigOrigin>| {
+        origin.is_some_and(|o| o.file.starts_with(dir))
+    };
+
+    let mut commands = serde_json::Map::new();
+    if let Some(vars) = scoped.variables.as_mut() {
+        for (values, origins) in [
+            (&mut vars.static_, &origins.static_),
+            (&mut vars.env, &origins.env),
+            (&mut vars.command, &origins.command),
+            (&mut vars.files, &origins.files),
+        ] {
+            if let Some(obj) = values.as_mut().and_then(|v| v.as_object_mut()) {
+                obj.retain(|key, _| from_nested(origins.get(key)));
+            }
+        }
+
+        // Commands are left to the rules, which run them only when they render
+        if let Some(serde_json::Value::Object(obj)) = vars.command.take() {
+            commands = obj;
+        }
+    }
+
+    Ok((variables::resolve_config_variables(&scoped)?.to_data(), commands))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::RuleFrontmatter;
+    use serde_json::json;
+
+    fn rule(globs: Option<Vec<&str>>) -> Rule {
+        Rule {
+            frontmatter: RuleFrontmatter {
+                name: Some("test".to_string()),
+                globs: globs.map(|g| g.into_iter().map(String::from).collect()),
+                ..Default::default()
+            },
+            body: String::new(),
+            path: PathBuf::from("packages/app/.cAGENTS/templates/test.md"),
+        }
+    }
+
+    fn scope(dir: &str) -> RuleScope {
+        RuleScope {
+            dir: PathBuf::from(dir),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_scope_rule_prefixes_globs() {
+        let scoped = scope_rule(rule(Some(vec!["src/**/*.ts", "./lib/*.ts"])), &scope("packages/app"));
+        assert_eq!(
+            scoped.frontmatter.globs.unwrap(),
+            vec!["packages/app/src/**/*.ts", "packages/app/lib/*.ts"]
+        );
+        assert!(scoped.frontmatter.output_in.is_none());
+    }
+
+    #[test]
+    fn test_scope_rule_without_globs_covers_subtree() {
+        let scoped = scope_rule(
+            rule(None),
+            &RuleScope {
+                engine: Some("builtin:simple".to_string()),
+                ..scope("packages/app")
+            },
+        );
+        assert_eq!(scoped.frontmatter.globs.unwrap(), vec!["packages/app/**"]);
+        assert_eq!(scoped.frontmatter.output_in.as_deref(), Some("common-parent"));
+        assert_eq!(scoped.frontmatter.engine.as_deref(), Some("builtin:simple"));
+    }
+
+    #[test]
+    fn test_scope_rule_vars_merge_under_rule_vars() {
+        let mut r = rule(None);
+        r.frontmatter.vars = Some(json!({"tone": "terse"}));
+
+        r.frontmatter.command_vars = Some(json!({"branch": "git branch --show-current"}));
+
+        let mut scope = scope("apps/web");
+        scope.data.insert("tone".to_string(), json!("friendly"));
+        scope.data.insert("team".to_string(), json!("web"));
+        scope.commands.insert("branch".to_string(), json!("echo main"));
+        scope.commands.insert("sha".to_string(), json!("git rev-parse HEAD"));
+
+        let scoped = scope_rule(r, &scope);
+        assert_eq!(scoped.frontmatter.vars.unwrap(), json!({"tone": "terse", "team": "web"}));
+        assert_eq!(scoped.frontmatter.scope_vars.unwrap()["tone"], json!("friendly"));
+        assert_eq!(
+            scoped.frontmatter.command_vars.unwrap(),
+            json!({"branch": "git branch --show-current", "sha": "git rev-parse HEAD"})
+        );
+    }
+}
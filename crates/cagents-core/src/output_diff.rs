@@ -0,0 +1,171 @@
+// compare rendered outputs with what's on disk: unified diffs for `build --dry-run`
+
+use anyhow::{Context, Result};
+use owo_colors::OwoColorize;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// How an output file differs from the file on disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Added,
+    Modified,
+    Removed,
+    Unchanged,
+}
+
+/// One output file, as on disk and as the build would leave it
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub path: PathBuf,
+    pub kind: ChangeKind,
+    /// Content on disk (empty if missing)
+    pub old: String,
+    /// Content after the build (empty if removed)
+    pub new: String,
+}
+
+impl FileChange {
+    /// Compare `expected` (None = the build removes the file) with `path` on disk
+    pub fn new(path: &Path, expected: Option<String>) -> Result<Self> {
+        let current = read_current(path)?;
+        let kind = match (&current, &expected) {
+            (None, Some(_)) => ChangeKind::Added,
+            (Some(_), None) => ChangeKind::Removed,
+            (Some(old), Some(new)) if old != new => ChangeKind::Modified,
+            _ => ChangeKind::Unchanged,
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            kind,
+            old: current.unwrap_or_default(),
+            new: expected.unwrap_or_default(),
+        })
+    }
+
+    /// Unified diff from the file on disk to the built file (empty if unchanged)
+    pub fn unified_diff(&self) -> String {
+        if self.kind == ChangeKind::Unchanged {
+            return String::new();
+        }
+
+        // git-style a/ and b/ prefixes for paths in the project, others as they are
+        let path = display_path(&self.path);
+        let side = |prefix: &str| match path.is_absolute() {
+            true => path.display().to_string(),
+            false => format!("{}/{}", prefix, path.display()),
+        };
+        let old_header = match self.kind {
+            ChangeKind::Added => "/dev/null".to_string(),
+            _ => side("a"),
+        };
+        let new_header = match self.kind {
+            ChangeKind::Removed => "/dev/null".to_string(),
+            _ => side("b"),
+        };
+
+        similar::TextDiff::from_lines(&self.old, &self.new)
+            .unified_diff()
+            .context_radius(3)
+            .missing_newline_hint(false)
+            .header(&old_header, &new_header)
+            .to_string()
+    }
+}
+
+/// A file's content as the diff sees it; symlinks are shown as their target
+///
+/// Returns None if nothing is at `path`.
+pub fn read_current(path: &Path) -> Result<Option<String>> {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return Ok(None);
+    };
+
+    if metadata.file_type().is_symlink() {
+        let target = fs::read_link(path).with_context(|| format!("Failed to read link {}", path.display()))?;
+        return Ok(Some(describe_link(&target)));
+    }
+
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(Some(String::from_utf8_lossy(&bytes).into_owned()))
+}
+
+/// How a symlink output appears in diffs
+pub fn describe_link(target: &Path) -> String {
+    format!("symlink -> {}\n", target.display())
+}
+
+/// `path` without `.` components, so `./api/AGENTS.md` and `api/AGENTS.md` compare equal
+pub fn normalize(path: &Path) -> PathBuf {
+    path.components().filter(|c| !matches!(c, Component::CurDir)).collect()
+}
+
+/// `path` as diffs and reports show it: relative to the project root (the current
+/// directory) if it is inside it, without `.` components
+pub fn display_path(path: &Path) -> PathBuf {
+    let path = normalize(path);
+    match std::env::current_dir() {
+        Ok(root) if path.is_absolute() => path.strip_prefix(&root).map(Path::to_path_buf).unwrap_or(path),
+        _ => path,
+    }
+}
+
+/// Print a unified diff (one file's) with added and removed lines colored
+pub fn print_diff(diff: &str) {
+    for (index, line) in diff.lines().enumerate() {
+        if index < 2 {
+            println!("{}", line.bold());
+        } else if line.starts_with('+') {
+            println!("{}", line.green());
+        } else if line.starts_with('-') {
+            println!("{}", line.red());
+        } else if line.starts_with("@@") {
+            println!("{}", line.cyan());
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_changes_against_disk() {
+        let temp = TempDir::new().unwrap();
+        let existing = temp.path().join("AGENTS.md");
+        fs::write(&existing, "# Rules\nold line\n").unwrap();
+
+        let modified = FileChange::new(&existing, Some("# Rules\nnew line\n".to_string())).unwrap();
+        assert_eq!(modified.kind, ChangeKind::Modified);
+        let diff = modified.unified_diff();
+        assert!(diff.contains("-old line\n+new line\n"));
+        assert!(diff.contains(&format!("+++ {}", existing.display())), "paths outside the project stay absolute");
+        assert!(!diff.contains("b//"));
+
+        let same = FileChange::new(&existing, Some("# Rules\nold line\n".to_string())).unwrap();
+        assert_eq!(same.kind, ChangeKind::Unchanged);
+        assert_eq!(same.unified_diff(), "");
+
+        let removed = FileChange::new(&existing, None).unwrap();
+        assert_eq!(removed.kind, ChangeKind::Removed);
+        assert!(removed.unified_diff().contains("+++ /dev/null"));
+
+        let added = FileChange::new(&temp.path().join("CLAUDE.md"), Some("new\n".to_string())).unwrap();
+        assert_eq!(added.kind, ChangeKind::Added);
+        assert!(added.unified_diff().contains("--- /dev/null"));
+    }
+
+    #[test]
+    fn test_project_paths_are_relative() {
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(display_path(&cwd.join("docs/AGENTS.md")), PathBuf::from("docs/AGENTS.md"));
+        assert_eq!(display_path(Path::new("./././AGENTS.md")), PathBuf::from("AGENTS.md"));
+
+        let added = FileChange::new(&cwd.join("no-such-dir/./AGENTS.md"), Some("new\n".to_string())).unwrap();
+        assert!(added.unified_diff().contains("+++ b/no-such-dir/AGENTS.md\n"));
+    }
+}
@@ -4,7 +4,7 @@ use crate::loader::Rule;
 use anyhow::Result;
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use globset::{Glob, GlobSetBuilder};
+use globset::{Glob, GlobMatcher, GlobSet, GlobSetBuilder};
 
 /// Build context from CLI args
 #[derive(Debug, Clone)]
@@ -67,6 +67,21 @@ impl BuildContext {
         }
     }
 
+    /// Check a rule's when clause, with the variables of its nested project (if any) on top
+    pub fn matches_rule(&self, rule: &Rule) -> bool {
+        let Some(scope) = &rule.frontmatter.scope_vars else {
+            return self.matches_when(&rule.frontmatter.when);
+        };
+
+        let mut context = self.clone();
+        for (key, value) in scope {
+            if let Some(s) = value.as_str() {
+                context.variables.insert(key.clone(), s.to_string());
+            }
+        }
+        context.matches_when(&rule.frontmatter.when)
+    }
+
     /// Check if a rule's when clause matches this context
     pub fn matches_when(&self, when: &Option<crate::model::When>) -> bool {
         let Some(when) = when else {
@@ -130,41 +145,40 @@ pub fn filter_rules_for_file(
 ) -> Result<Vec<Rule>> {
     let filtered: Vec<Rule> = rules
         .iter()
-        .filter(|rule| {
-            // First check if context matches
-            // No when clause = always matches context
-            if !context.matches_when(&rule.frontmatter.when) {
-                return false;
-            }
+        .filter(|rule| context.matches_rule(rule) && rule_matches_file(rule, file_path))
+        .cloned()
+        .collect();
 
-            // Now check file/glob matching
-            // If no globs or empty globs, apply to all files
-            let Some(globs) = &rule.frontmatter.globs else {
-                return true; // No globs = applies to all files
-            };
+    Ok(filtered)
+}
 
-            if globs.is_empty() {
-                return true; // Empty globs = applies to all files
-            }
+/// Whether a rule's globs cover `file_path` (rules without globs cover every file)
+pub fn rule_matches_file(rule: &Rule, file_path: &Path) -> bool {
+    let Some(globs) = rule.frontmatter.globs.as_ref().filter(|globs| !globs.is_empty()) else {
+        return true;
+    };
 
-            // Has globs - check if file matches
-            let mut builder = GlobSetBuilder::new();
-            for pattern in globs {
-                if let Ok(glob) = Glob::new(pattern) {
-                    builder.add(glob);
-                }
-            }
-
-            if let Ok(globset) = builder.build() {
-                return globset.is_match(file_path);
-            }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in globs {
+        if let Ok(glob) = Glob::new(pattern) {
+            builder.add(glob);
+        }
+    }
 
-            false
-        })
-        .cloned()
-        .collect();
+    builder.build().is_ok_and(|globset| globset.is_match(file_path))
+}
 
-    Ok(filtered)
+/// Directory of the output that holds `rule` for `file_path`, as [`plan_outputs`] places it
+///
+/// `file_path` is relative to the project root and matched by the rule.
+pub fn output_dir_for_file(rule: &Rule, file_path: &Path) -> PathBuf {
+    let Some(globs) = rule.frontmatter.globs.as_ref().filter(|globs| !globs.is_empty()) else {
+        return PathBuf::from(".");
+    };
+    match RuleGlobs::new(globs) {
+        Ok(rule_globs) => rule_globs.output_dir(&rule.frontmatter.get_output_strategy(), file_path, false),
+        Err(_) => parent_dir(file_path),
+    }
 }
 
 /// Plan outputs by grouping rules into target directories
@@ -186,6 +200,9 @@ pub fn plan_outputs(
         outputs.insert(PathBuf::from("."), root_rules);
     }
 
+    // Files and directories of the project, walked the first time a rule needs them
+    let mut entries: Option<Vec<(PathBuf, bool)>> = None;
+
     // Then, for each rule with globs, find which directories it applies to
     for rule in rules {
         // Skip if no globs (already handled in root)
@@ -200,26 +217,23 @@ pub fn plan_outputs(
 
         // Determine output strategy using outputIn field
         let strategy = rule.frontmatter.get_output_strategy();
+        if !["common-parent", "parent", "matched"].contains(&strategy.as_str()) {
+            eprintln!("Warning: Unknown outputIn value '{}', using 'parent'", strategy);
+        }
+        let rule_globs = RuleGlobs::new(globs)?;
 
-        let dirs = match strategy.as_str() {
-            "common-parent" => {
-                // Find common parent directory across all glob matches
-                find_common_parent_directory(globs)
-            }
-            "parent" => {
-                // Find all directories that contain matching files
-                find_matching_directories(project_root, globs)?
-            }
-            "matched" => {
-                // For directory globs (trailing slash), output IN the matched directory
-                // For file globs, fall back to parent
-                find_matched_or_parent_directories(project_root, globs)?
-            }
-            _ => {
-                // Unknown strategy, fall back to parent
-                eprintln!("Warning: Unknown outputIn value '{}', using 'parent'", strategy);
-                find_matching_directories(project_root, globs)?
+        let dirs: HashSet<PathBuf> = if strategy == "common-parent" {
+            HashSet::from([common_parent_directory(globs)])
+        } else {
+            if entries.is_none() {
+                entries = Some(project_entries(project_root)?);
             }
+            entries
+                .iter()
+                .flatten()
+                .filter(|(path, is_dir)| rule_globs.places(&strategy, path, *is_dir))
+                .map(|(path, is_dir)| rule_globs.output_dir(&strategy, path, *is_dir))
+                .collect()
         };
 
         for dir in dirs {
@@ -233,76 +247,125 @@ pub fn plan_outputs(
     Ok(outputs)
 }
 
-/// Find common parent directory from glob patterns
-/// Used when outputIn is "common-parent"
-fn find_common_parent_directory(globs: &[String]) -> HashSet<PathBuf> {
-    if globs.is_empty() {
-        return HashSet::new();
+/// A rule's globs, compiled for placing its outputs
+struct RuleGlobs<'a> {
+    globs: &'a [String],
+    /// Every glob, matched against files
+    all: GlobSet,
+    /// Globs without a trailing slash
+    files: GlobSet,
+    /// Globs with a trailing slash (without it), matched against directories
+    dirs: Vec<GlobMatcher>,
+}
+
+impl<'a> RuleGlobs<'a> {
+    fn new(globs: &'a [String]) -> Result<Self> {
+        let mut all = GlobSetBuilder::new();
+        let mut files = GlobSetBuilder::new();
+        let mut dirs = Vec::new();
+        for pattern in globs {
+            all.add(Glob::new(pattern)?);
+            match pattern.strip_suffix('/') {
+                Some(dir) => dirs.push(Glob::new(dir)?.compile_matcher()),
+                None => {
+                    files.add(Glob::new(pattern)?);
+                }
+            }
+        }
+
+        Ok(Self {
+            globs,
+            all: all.build()?,
+            files: files.build()?,
+            dirs,
+        })
+    }
+
+    /// Whether `outputIn = strategy` places an output for this file or directory
+    fn places(&self, strategy: &str, path: &Path, is_dir: bool) -> bool {
+        match (strategy, is_dir) {
+            ("matched", true) => self.dirs.iter().any(|g| g.is_match(path)),
+            ("matched", false) => self.files.is_match(path),
+            (_, true) => false,
+            (_, false) => self.all.is_match(path),
+        }
+    }
+
+    /// Output directory for a file or directory the rule matches
+    ///
+    /// - `common-parent`: the directory shared by all globs (see [`common_parent_directory`])
+    /// - `matched`: a matched directory itself; for a file, the deepest directory around it
+    ///   matched by a directory glob, else the file's directory
+    /// - `parent` (and unknown values): the file's directory
+    fn output_dir(&self, strategy: &str, path: &Path, is_dir: bool) -> PathBuf {
+        match strategy {
+            "common-parent" => common_parent_directory(self.globs),
+            "matched" if is_dir => path.to_path_buf(),
+            "matched" => path
+                .ancestors()
+                .skip(1)
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .find(|dir| self.dirs.iter().any(|g| g.is_match(dir)))
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| parent_dir(path)),
+            _ => parent_dir(path),
+        }
     }
+}
+
+/// Directory containing a project-relative path (`"."` for files at the root)
+fn parent_dir(path: &Path) -> PathBuf {
+    path.parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
 
+/// Find common parent directory from glob patterns
+/// Used when outputIn is "common-parent"
+fn common_parent_directory(globs: &[String]) -> PathBuf {
     // Extract directory parts from each glob (before wildcards)
-    let dir_parts: Vec<Vec<String>> = globs
+    let dir_parts: Vec<Vec<&str>> = globs
         .iter()
         .map(|g| {
-            // Remove wildcard parts and extract concrete directory path
-            let cleaned = g
-                .split('/')
+            g.split('/')
                 .take_while(|part| !part.contains('*'))
                 .filter(|part| !part.is_empty())
-                .map(|s| s.to_string())
-                .collect();
-            cleaned
+                .collect()
         })
         .collect();
 
     // If any pattern starts with wildcards, use root
-    if dir_parts.iter().any(|parts| parts.is_empty()) {
-        return vec![PathBuf::from(".")].into_iter().collect();
-    }
+    let Some(first) = dir_parts.first().filter(|_| dir_parts.iter().all(|parts| !parts.is_empty())) else {
+        return PathBuf::from(".");
+    };
 
     // Find common prefix across all directory parts
-    let mut common_prefix = Vec::new();
-    let first = &dir_parts[0];
-
-    for (i, part) in first.iter().enumerate() {
-        if dir_parts.iter().all(|p| p.get(i).map(|s| s.as_str()) == Some(part.as_str())) {
-            common_prefix.push(part.clone());
-        } else {
-            break;
-        }
-    }
+    let common_prefix: Vec<&str> = first
+        .iter()
+        .enumerate()
+        .take_while(|(i, part)| dir_parts.iter().all(|p| p.get(*i) == Some(*part)))
+        .map(|(_, part)| *part)
+        .collect();
 
     if common_prefix.is_empty() {
-        vec![PathBuf::from(".")].into_iter().collect()
+        PathBuf::from(".")
     } else {
-        vec![PathBuf::from(common_prefix.join("/"))].into_iter().collect()
+        PathBuf::from(common_prefix.join("/"))
     }
 }
 
-/// Find all directories that contain files matching the given glob patterns
-fn find_matching_directories(project_root: &Path, globs: &[String]) -> Result<HashSet<PathBuf>> {
+/// Every file and directory below the project root (relative to it, with whether it is
+/// a directory), skipping hidden directories and common build/dependency directories
+fn project_entries(project_root: &Path) -> Result<Vec<(PathBuf, bool)>> {
     use walkdir::WalkDir;
 
-    let mut builder = GlobSetBuilder::new();
-    for pattern in globs {
-        let glob = Glob::new(pattern)?;
-        builder.add(glob);
-    }
-    let globset = builder.build()?;
-
-    let mut directories = HashSet::new();
-
-    // Walk the project directory
+    let mut entries = Vec::new();
     for entry in WalkDir::new(project_root)
+        .min_depth(1)
         .follow_links(false)
         .into_iter()
         .filter_entry(|e| {
-            // Don't filter the root directory itself
-            if e.path() == project_root {
-                return true;
-            }
-
-            // Skip hidden directories and common ignore patterns
             let name = e.file_name().to_string_lossy();
             !name.starts_with('.')
                 && name != "node_modules"
@@ -311,111 +374,11 @@ fn find_matching_directories(project_root: &Path, globs: &[String]) -> Result<Ha
         })
     {
         let entry = entry?;
-
-        if !entry.file_type().is_file() {
-            continue;
-        }
-
-        // Get path relative to project root
-        let rel_path = entry.path().strip_prefix(project_root)
-            .unwrap_or(entry.path());
-
-        // Check if this file matches any glob
-        if globset.is_match(rel_path) {
-            // Add the parent directory
-            if let Some(parent) = rel_path.parent() {
-                directories.insert(parent.to_path_buf());
-            } else {
-                // File is in the root directory
-                directories.insert(PathBuf::from("."));
-            }
-        }
-    }
-
-    Ok(directories)
-}
-
-/// Find matched directories (for trailing slash globs) or parent directories (for file globs)
-/// This implements the "matched" output strategy
-fn find_matched_or_parent_directories(project_root: &Path, globs: &[String]) -> Result<HashSet<PathBuf>> {
-    use walkdir::WalkDir;
-
-    let mut directories = HashSet::new();
-
-    for pattern in globs {
-        // Check if this is a directory glob (trailing slash)
-        let is_dir_glob = pattern.ends_with('/');
-
-        if is_dir_glob {
-            // Directory glob - find directories matching the pattern
-            let dir_pattern = pattern.trim_end_matches('/');
-            let glob = Glob::new(dir_pattern)?;
-
-            for entry in WalkDir::new(project_root)
-                .follow_links(false)
-                .into_iter()
-                .filter_entry(|e| {
-                    if e.path() == project_root {
-                        return true;
-                    }
-                    let name = e.file_name().to_string_lossy();
-                    !name.starts_with('.')
-                        && name != "node_modules"
-                        && name != "target"
-                        && name != "dist"
-                })
-                .filter_map(|e| e.ok())
-            {
-                if !entry.file_type().is_dir() {
-                    continue;
-                }
-
-                let rel_path = entry.path().strip_prefix(project_root)
-                    .unwrap_or(entry.path());
-
-                if glob.compile_matcher().is_match(rel_path) {
-                    directories.insert(rel_path.to_path_buf());
-                }
-            }
-        } else {
-            // File glob - fall back to parent strategy
-            let glob = Glob::new(pattern)?;
-            let globset = GlobSetBuilder::new().add(glob).build()?;
-
-            for entry in WalkDir::new(project_root)
-                .follow_links(false)
-                .into_iter()
-                .filter_entry(|e| {
-                    if e.path() == project_root {
-                        return true;
-                    }
-                    let name = e.file_name().to_string_lossy();
-                    !name.starts_with('.')
-                        && name != "node_modules"
-                        && name != "target"
-                        && name != "dist"
-                })
-                .filter_map(|e| e.ok())
-            {
-                if !entry.file_type().is_file() {
-                    continue;
-                }
-
-                let rel_path = entry.path().strip_prefix(project_root)
-                    .unwrap_or(entry.path());
-
-                if globset.is_match(rel_path) {
-                    if let Some(parent) = rel_path.parent() {
-                        directories.insert(parent.to_path_buf());
-                    } else {
-                        directories.insert(PathBuf::from("."));
-                    }
-                }
-            }
-        }
+        let rel_path = entry.path().strip_prefix(project_root).unwrap_or(entry.path());
+        entries.push((rel_path.to_path_buf(), entry.file_type().is_dir()));
     }
 
-    Ok(directories)
+    Ok(entries)
 }
 
 #[cfg(test)]
@@ -742,4 +705,60 @@ mod tests {
         let filtered = filter_rules_for_file(&[rule], &file_path, &ctx).unwrap();
         assert_eq!(filtered.len(), 0, "Should not match when app_env variable doesn't match");
     }
+
+    #[test]
+    fn test_output_dir_for_file_follows_output_in() {
+        let rule = |globs: &[&str], output_in: Option<&str>| Rule {
+            frontmatter: RuleFrontmatter {
+                globs: Some(globs.iter().map(|g| g.to_string()).collect()),
+                output_in: output_in.map(str::to_string),
+                ..Default::default()
+            },
+            body: "test".to_string(),
+            path: PathBuf::from("test.md"),
+        };
+        let file = Path::new("packages/api/src/routes/users.ts");
+
+        assert_eq!(output_dir_for_file(&rule(&["packages/api/**"], None), file), PathBuf::from("packages/api"));
+        assert_eq!(output_dir_for_file(&rule(&["**/*.ts"], Some("parent")), file), PathBuf::from("packages/api/src/routes"));
+        assert_eq!(output_dir_for_file(&rule(&["packages/api/"], Some("matched")), file), PathBuf::from("packages/api"));
+        assert_eq!(output_dir_for_file(&rule(&[], None), file), PathBuf::from("."));
+    }
+
+    #[test]
+    fn test_plan_outputs_and_output_dir_for_file_agree() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let root = tmp.path();
+        for file in ["build.rs", "src/main.rs", "packages/api/src/lib.rs"] {
+            std::fs::create_dir_all(root.join(file).parent().unwrap()).unwrap();
+            std::fs::write(root.join(file), "").unwrap();
+        }
+
+        let rule = |globs: &[&str], output_in: &str| Rule {
+            frontmatter: RuleFrontmatter {
+                name: Some(output_in.to_string()),
+                globs: Some(globs.iter().map(|g| g.to_string()).collect()),
+                output_in: Some(output_in.to_string()),
+                ..Default::default()
+            },
+            body: "test".to_string(),
+            path: PathBuf::from("test.md"),
+        };
+        let rules = [rule(&["**/*.rs"], "parent"), rule(&["packages/*/", "**/*.rs"], "matched")];
+        let ctx = BuildContext::new(None, None, None);
+        let outputs = plan_outputs(&rules, &ctx, root).unwrap();
+
+        // Each file's rules are written to an output the plan contains
+        for rule in &rules {
+            for file in ["build.rs", "src/main.rs", "packages/api/src/lib.rs"] {
+                let dir = output_dir_for_file(rule, Path::new(file));
+                let planned = outputs.get(&dir).is_some_and(|rules| {
+                    rules.iter().any(|r| r.frontmatter.name == rule.frontmatter.name)
+                });
+                assert!(planned, "{} for {:?} goes to unplanned {}", file, rule.frontmatter.output_in, dir.display());
+            }
+        }
+        assert!(outputs.contains_key(Path::new(".")), "root files plan the \".\" output");
+        assert!(!outputs.contains_key(Path::new("")));
+    }
 }
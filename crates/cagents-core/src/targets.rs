@@ -0,0 +1,357 @@
+// output targets: the `Target` trait and the registry build, lint, cleanup, preview and git helpers consult
+
+use crate::loader::Rule;
+use crate::model::{CustomTargetSpec, ProjectConfig};
+use crate::writers;
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::path::{Component, Path, PathBuf};
+
+/// Target used when `[output] targets` isn't set
+pub const DEFAULT_TARGET: &str = "agents-md";
+
+/// An output format `cagents build` writes
+///
+/// Adding a target means implementing this trait and registering it in
+/// [`TargetRegistry::builtin`].
+pub trait Target: Send + Sync {
+    /// Name used in `[output] targets` and `when.target`
+    fn name(&self) -> &str;
+
+    /// Human-readable name for listings
+    fn display_name(&self) -> &str;
+
+    /// File written in each output directory (for per-rule targets, a pattern
+    /// matching the files written)
+    fn file_name(&self) -> &str;
+
+    /// Auto-generated notice placed before the rules
+    fn header(&self) -> &str;
+
+    /// Only written at the output root, never in nested directories
+    fn root_only(&self) -> bool {
+        false
+    }
+
+    /// Adjust the merged rules before the header is added
+    fn transform(&self, merged: &str) -> String {
+        merged.to_string()
+    }
+
+    /// Full file content for the merged rules
+    fn format(&self, merged: &str) -> String {
+        format!("{}{}", self.header(), self.transform(merged))
+    }
+
+    /// Full file content for the merged rules, with the output's template data
+    /// available (used by targets whose header or wrapper has placeholders)
+    fn render(&self, merged: &str, _data: &Value) -> Result<String> {
+        Ok(self.format(merged))
+    }
+
+    /// Writes rules to their own files (see [`Target::rule_file`]) instead of
+    /// merging the rules of each directory
+    fn per_rule(&self) -> bool {
+        false
+    }
+
+    /// Per-rule targets: the file for `rule`, relative to the output root;
+    /// `None` merges the rule into `file_name` like other targets
+    fn rule_file(&self, _rule: &Rule) -> Option<PathBuf> {
+        None
+    }
+
+    /// Per-rule targets: full file content for one rendered rule
+    fn format_rule(&self, _rule: &Rule, rendered: &str) -> String {
+        self.format(rendered)
+    }
+
+    /// Per-rule targets: text appended to the merged rules of a directory,
+    /// listing the `rules` of that directory written to their own files
+    fn rule_index(&self, _rules: &[&Rule]) -> String {
+        String::new()
+    }
+
+    /// Files to remove under `output_root` once the target is dropped from config
+    ///
+    /// Per-rule targets whose `file_name` is a pattern have none: their rule files are
+    /// removed through the build's file tracking.
+    fn cleanup_paths(&self, output_root: &Path) -> Vec<PathBuf> {
+        if self.per_rule() && self.file_name().contains('*') {
+            return Vec::new();
+        }
+        vec![output_root.join(self.file_name())]
+    }
+
+    /// `.gitignore` patterns matching the files this target writes
+    fn gitignore_patterns(&self) -> Vec<String> {
+        let file_name = self.file_name().to_string();
+        if self.root_only() {
+            vec![file_name]
+        } else {
+            vec![file_name.clone(), format!("**/{}", file_name)]
+        }
+    }
+}
+
+/// The output targets available to a project
+pub struct TargetRegistry {
+    targets: Vec<Box<dyn Target>>,
+}
+
+impl TargetRegistry {
+    /// Targets shipped with cagents
+    pub fn builtin() -> Self {
+        Self::builtin_with(writers::cursor_mdc::DEFAULT_DIR, false)
+    }
+
+    fn builtin_with(cursor_rules_dir: &str, list_skills: bool) -> Self {
+        Self {
+            targets: vec![
+                Box::new(writers::agents_md::AgentsMd),
+                Box::new(writers::claude_md::ClaudeMd { list_skills }),
+                Box::new(writers::cursorrules::Cursorrules),
+                Box::new(writers::cursor_mdc::CursorRules::new(cursor_rules_dir)),
+                Box::new(writers::copilot::Copilot),
+                Box::new(writers::windsurf::Windsurf),
+                Box::new(writers::cline::Cline),
+                Box::new(writers::gemini_md::GeminiMd),
+            ],
+        }
+    }
+
+    /// Built-in targets (using the config's `cursorRulesDir` and `listSkills`)
+    /// plus the config's `[output.custom.<name>]` targets
+    pub fn for_config(config: &ProjectConfig) -> Self {
+        let cursor_rules_dir = config
+            .paths
+            .cursor_rules_dir
+            .as_deref()
+            .unwrap_or(writers::cursor_mdc::DEFAULT_DIR);
+        let list_skills = config.output.as_ref().and_then(|o| o.list_skills).unwrap_or(false);
+        let mut registry = Self::builtin_with(cursor_rules_dir, list_skills);
+        for (name, spec) in custom_specs(config) {
+            registry.targets.push(Box::new(CustomTarget {
+                name: name.clone(),
+                spec: spec.clone(),
+            }));
+        }
+        registry
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Target> {
+        self.targets.iter().find(|t| t.name() == name).map(|t| t.as_ref())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Target> {
+        self.targets.iter().map(|t| t.as_ref())
+    }
+
+    /// Every registered target name, in registration order
+    pub fn names(&self) -> Vec<&str> {
+        self.iter().map(|t| t.name()).collect()
+    }
+}
+
+/// A target defined in config under `[output.custom.<name>]`
+pub struct CustomTarget {
+    name: String,
+    spec: CustomTargetSpec,
+}
+
+/// Marks where the merged rules go in a custom target's `wrapper`
+const CONTENT_PLACEHOLDER: &str = "{{content}}";
+
+impl Target for CustomTarget {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn display_name(&self) -> &str {
+        self.spec.display_name.as_deref().unwrap_or(&self.spec.filename)
+    }
+
+    fn file_name(&self) -> &str {
+        &self.spec.filename
+    }
+
+    fn header(&self) -> &str {
+        self.spec.header.as_deref().unwrap_or("")
+    }
+
+    fn root_only(&self) -> bool {
+        self.spec.root_only.unwrap_or(false)
+    }
+
+    fn transform(&self, merged: &str) -> String {
+        match &self.spec.wrapper {
+            Some(wrapper) => wrapper.replacen(CONTENT_PLACEHOLDER, merged, 1),
+            None => merged.to_string(),
+        }
+    }
+
+    fn format(&self, merged: &str) -> String {
+        let footer = self.spec.footer.as_deref().unwrap_or("");
+        format!("{}{}{}", self.header(), self.transform(merged), footer)
+    }
+
+    fn render(&self, merged: &str, data: &Value) -> Result<String> {
+        // Placeholders are filled around the rules, never inside them
+        let fill = |text: &str| {
+            crate::adapters::builtin::render_simple(text, data)
+                .with_context(|| format!("Failed to render custom target '{}'", self.name))
+        };
+
+        let body = match &self.spec.wrapper {
+            Some(wrapper) => {
+                let (before, after) = wrapper.split_once(CONTENT_PLACEHOLDER).unwrap_or((wrapper, ""));
+                format!("{}{}{}", fill(before)?, merged, fill(after)?)
+            }
+            None => merged.to_string(),
+        };
+        let header = fill(self.header())?;
+        let footer = fill(self.spec.footer.as_deref().unwrap_or(""))?;
+        Ok(format!("{}{}{}", header, body, footer))
+    }
+}
+
+fn custom_specs(config: &ProjectConfig) -> impl Iterator<Item = (&String, &CustomTargetSpec)> {
+    config
+        .output
+        .as_ref()
+        .and_then(|o| o.custom.as_ref())
+        .into_iter()
+        .flatten()
+}
+
+/// Check `[output.custom.<name>]` entries: no clash with a built-in target, a
+/// relative file name inside the output directory, and a wrapper with `{{content}}`
+pub fn validate_custom_targets(config: &ProjectConfig) -> Result<()> {
+    let builtin = TargetRegistry::builtin();
+    for (name, spec) in custom_specs(config) {
+        if builtin.get(name).is_some() {
+            anyhow::bail!("[output.custom.{}] conflicts with the built-in '{}' target", name, name);
+        }
+
+        let path = Path::new(&spec.filename);
+        let inside = path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+        if spec.filename.trim().is_empty() || !inside {
+            anyhow::bail!(
+                "[output.custom.{}] filename '{}' must be a relative path inside the output directory",
+                name,
+                spec.filename
+            );
+        }
+
+        if let Some(wrapper) = &spec.wrapper {
+            if !wrapper.contains(CONTENT_PLACEHOLDER) {
+                anyhow::bail!(
+                    "[output.custom.{}] wrapper must contain {} where the rules go",
+                    name,
+                    CONTENT_PLACEHOLDER
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Output targets from config (default to ["agents-md"])
+pub fn configured_targets(config: &ProjectConfig) -> Vec<String> {
+    config
+        .output
+        .as_ref()
+        .and_then(|o| o.targets.as_ref())
+        .cloned()
+        .unwrap_or_else(|| vec![DEFAULT_TARGET.to_string()])
+}
+
+/// Whether two rules are the same rule: rules are cloned for every output directory,
+/// and `foreach` items share their template's path
+pub fn same_rule(a: &Rule, b: &Rule) -> bool {
+    a.path == b.path && a.frontmatter.name == b.frontmatter.name
+}
+
+/// Per-rule files that more than one rule would write: `(file, first rule, other rule)`
+pub fn rule_file_conflicts<'a>(target: &dyn Target, rules: &[&'a Rule]) -> Vec<(PathBuf, &'a Rule, &'a Rule)> {
+    let mut files: std::collections::BTreeMap<PathBuf, &Rule> = std::collections::BTreeMap::new();
+    let mut conflicts = Vec::new();
+
+    for rule in rules {
+        let Some(file) = target.per_rule().then(|| target.rule_file(rule)).flatten() else {
+            continue;
+        };
+        match files.get(&file) {
+            Some(first) if !same_rule(first, rule) => conflicts.push((file, *first, *rule)),
+            Some(_) => {}
+            None => {
+                files.insert(file, rule);
+            }
+        }
+    }
+
+    conflicts
+}
+
+/// `'name' (path)`, for messages about a rule
+pub fn describe_rule(rule: &Rule) -> String {
+    format!(
+        "'{}' ({})",
+        rule.frontmatter.name.as_deref().unwrap_or("unnamed"),
+        rule.path.display()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_targets() {
+        let registry = TargetRegistry::builtin();
+        assert_eq!(
+            registry.names(),
+            vec!["agents-md", "claude-md", "cursorrules", "cursor-rules", "copilot", "windsurf", "cline", "gemini-md"]
+        );
+        assert!(registry.get("unknown").is_none());
+
+        let claude = registry.get("claude-md").unwrap();
+        assert_eq!(claude.file_name(), "CLAUDE.md");
+        assert!(claude.format("# Rules").ends_with("# Rules"));
+        assert_eq!(
+            claude.gitignore_patterns(),
+            vec!["CLAUDE.md", "**/CLAUDE.md", ".claude/skills/*/SKILL.md"]
+        );
+        assert_eq!(
+            claude.cleanup_paths(Path::new("out")),
+            vec![PathBuf::from("out/CLAUDE.md")]
+        );
+    }
+
+    #[test]
+    fn test_custom_target_renders_around_rules() {
+        let target = CustomTarget {
+            name: "guide".to_string(),
+            spec: CustomTargetSpec {
+                filename: "docs/AI_GUIDE.md".to_string(),
+                header: Some("# {{project}} guide\n\n".to_string()),
+                footer: Some("\n-- {{team}}\n".to_string()),
+                wrapper: Some("<rules>\n{{content}}\n</rules>".to_string()),
+                root_only: Some(true),
+                ..Default::default()
+            },
+        };
+        let data = serde_json::json!({"project": "acme", "team": "platform"});
+
+        // Rule text is inserted as-is, even if it looks like a placeholder
+        let rendered = target.render("Use {{literal}} braces", &data).unwrap();
+        assert_eq!(
+            rendered,
+            "# acme guide\n\n<rules>\nUse {{literal}} braces\n</rules>\n-- platform\n"
+        );
+        assert_eq!(target.gitignore_patterns(), vec!["docs/AI_GUIDE.md"]);
+        assert_eq!(target.display_name(), "docs/AI_GUIDE.md");
+    }
+}
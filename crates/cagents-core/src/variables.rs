@@ -0,0 +1,807 @@
+// resolve template variables (config static/files/env/command, directory files, CLI, frontmatter) with provenance
+
+use crate::model::{ConfigOrigin, ProjectConfig, VariableSchema};
+use anyhow::{Context, Result};
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// How a variable's value was produced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VariableKind {
+    Static,
+    /// Loaded from `[variables.files]`
+    File,
+    Env,
+    Command,
+    /// `default` from `[variables.schema]`
+    Default,
+    /// Per-directory variable files (`.cAGENTS/vars/<dir>.toml`, `<dir>/agents.vars.toml`)
+    Directory,
+    Frontmatter,
+    Cli,
+    /// A `[matrix]` value of the variant being built
+    Matrix,
+    /// Built-in `cagents.*` project facts
+    Builtin,
+}
+
+impl std::fmt::Display for VariableKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            VariableKind::Static => "static",
+            VariableKind::File => "file",
+            VariableKind::Env => "env",
+            VariableKind::Command => "command",
+            VariableKind::Default => "default",
+            VariableKind::Directory => "directory",
+            VariableKind::Frontmatter => "frontmatter",
+            VariableKind::Cli => "cli",
+            VariableKind::Matrix => "matrix",
+            VariableKind::Builtin => "builtin",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Where a resolved variable came from
+#[derive(Debug, Clone)]
+pub struct VariableSource {
+    pub kind: VariableKind,
+    /// Config file and layer (config variables only)
+    pub origin: Option<ConfigOrigin>,
+    /// Rule that declared the variable (frontmatter `vars` and `commandVars` only)
+    pub rule: Option<String>,
+}
+
+impl VariableSource {
+    pub fn new(kind: VariableKind) -> Self {
+        Self {
+            kind,
+            origin: None,
+            rule: None,
+        }
+    }
+
+    /// Short source name: "config.static", "config.command", "config.default", "cli",
+    /// "frontmatter" or "frontmatter.command" (a rule's `commandVars`)
+    pub fn name(&self) -> String {
+        match self.kind {
+            VariableKind::Static
+            | VariableKind::File
+            | VariableKind::Env
+            | VariableKind::Command
+            | VariableKind::Default
+                if self.rule.is_none() =>
+            {
+                format!("config.{}", self.kind)
+            }
+            VariableKind::Command => "frontmatter.command".to_string(),
+            _ => self.kind.to_string(),
+        }
+    }
+
+    /// Human-readable description, e.g. "command (project: .cAGENTS/config.toml)"
+    pub fn describe(&self) -> String {
+        if let Some(origin) = &self.origin {
+            format!("{} ({}: {})", self.kind, origin.layer, origin.file.display())
+        } else if let Some(rule) = &self.rule {
+            format!("{} ({})", self.kind, rule)
+        } else {
+            self.kind.to_string()
+        }
+    }
+
+    /// JSON representation used by `context --json` and `vars --json`
+    pub fn to_json(&self) -> Value {
+        let mut obj = Map::new();
+        obj.insert("source".to_string(), Value::String(self.name()));
+        obj.insert("kind".to_string(), Value::String(self.kind.to_string()));
+        if let Some(origin) = &self.origin {
+            obj.insert("layer".to_string(), Value::String(origin.layer.to_string()));
+            obj.insert(
+                "file".to_string(),
+                Value::String(origin.file.display().to_string()),
+            );
+        }
+        if let Some(rule) = &self.rule {
+            obj.insert("rule".to_string(), Value::String(rule.clone()));
+        }
+        Value::Object(obj)
+    }
+}
+
+/// A variable value together with its provenance
+#[derive(Debug, Clone)]
+pub struct ResolvedVariable {
+    pub value: Value,
+    pub source: VariableSource,
+    /// Time spent executing the command (command variables only)
+    pub duration: Option<Duration>,
+    /// Declaration from `[variables.schema]`, if any
+    pub schema: Option<VariableSchema>,
+    /// Command result served from `.cAGENTS/.command-cache`
+    pub cached: bool,
+}
+
+impl ResolvedVariable {
+    /// A value from `source`, without timing or a schema declaration
+    pub fn new(value: Value, source: VariableSource) -> Self {
+        Self {
+            value,
+            source,
+            duration: None,
+            schema: None,
+            cached: false,
+        }
+    }
+
+    /// JSON entry with value, source details, timing and declaration (if any)
+    pub fn to_json(&self) -> Value {
+        let mut entry = self.source.to_json();
+        if let Some(obj) = entry.as_object_mut() {
+            obj.insert("value".to_string(), self.value.clone());
+            if let Some(duration) = self.duration {
+                obj.insert(
+                    "duration_ms".to_string(),
+                    Value::from(duration.as_millis() as u64),
+                );
+            }
+            if self.cached {
+                obj.insert("cached".to_string(), Value::Bool(true));
+            }
+            if let Some(schema) = &self.schema {
+                if let Some(description) = &schema.description {
+                    obj.insert("description".to_string(), Value::String(description.clone()));
+                }
+                if let Some(type_) = &schema.type_ {
+                    obj.insert("type".to_string(), Value::String(type_.clone()));
+                }
+                if let Some(allowed) = &schema.allowed {
+                    obj.insert("allowed".to_string(), Value::Array(allowed.clone()));
+                }
+            }
+        }
+        entry
+    }
+}
+
+/// All resolved variables, keyed by name
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedVariables {
+    entries: BTreeMap<String, ResolvedVariable>,
+}
+
+impl ResolvedVariables {
+    pub fn insert(&mut self, name: impl Into<String>, variable: ResolvedVariable) {
+        self.entries.insert(name.into(), variable);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ResolvedVariable> {
+        self.entries.get(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &ResolvedVariable)> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Override with `--var` values from the command line
+    pub fn apply_cli(&mut self, cli_vars: &Map<String, Value>) {
+        for (key, value) in cli_vars {
+            self.insert(
+                key.clone(),
+                ResolvedVariable::new(value.clone(), VariableSource::new(VariableKind::Cli)),
+            );
+        }
+    }
+
+    /// Attach `[variables.schema]` declarations and check values against them
+    ///
+    /// Returns the required variables that have no value.
+    pub fn apply_schema(&mut self, config: &ProjectConfig) -> Result<Vec<String>> {
+        let Some(schema) = config.variables.as_ref().and_then(|v| v.schema.as_ref()) else {
+            return Ok(Vec::new());
+        };
+
+        let mut missing = Vec::new();
+        for (name, declaration) in schema {
+            match self.entries.get_mut(name) {
+                Some(variable) => {
+                    check_value(name, declaration, &variable.value)
+                        .with_context(|| format!("from {}", variable.source.describe()))?;
+                    variable.schema = Some(declaration.clone());
+                }
+                None if declaration.required == Some(true) => missing.push(name.clone()),
+                None => {}
+            }
+        }
+
+        Ok(missing)
+    }
+
+    /// Plain template data (name -> value)
+    pub fn to_data(&self) -> Map<String, Value> {
+        self.entries
+            .iter()
+            .map(|(k, v)| (k.clone(), v.value.clone()))
+            .collect()
+    }
+}
+
+/// Resolve `[variables.static]`, `[variables.files]`, `[variables.env]` and `[variables.command]`
+///
+/// Later kinds override earlier ones on name collisions: static < files < env < command.
+///
+/// A data file that can't be read or selected from is an error.
+pub fn resolve_config_variables(config: &ProjectConfig) -> Result<ResolvedVariables> {
+    resolve_config_variables_with(config, false)
+}
+
+/// Like [`resolve_config_variables`]; with `read_only`, the command cache isn't saved
+pub fn resolve_config_variables_with(config: &ProjectConfig, read_only: bool) -> Result<ResolvedVariables> {
+    let mut resolved = ResolvedVariables::default();
+
+    let Some(vars) = &config.variables else {
+        return Ok(resolved);
+    };
+    let origins = &config.variable_origins;
+
+    let source = |kind: VariableKind, origin: Option<&ConfigOrigin>| VariableSource {
+        kind,
+        origin: origin.cloned(),
+        rule: None,
+    };
+
+    if let Some(obj) = vars.static_.as_ref().and_then(|v| v.as_object()) {
+        for (key, value) in obj {
+            resolved.insert(
+                key.clone(),
+                ResolvedVariable::new(value.clone(), source(VariableKind::Static, origins.static_.get(key))),
+            );
+        }
+    }
+
+    // Data files are re-read on every resolve, so edits show up in the next build
+    if vars.files.is_some() {
+        for (key, file) in crate::data_files::data_files(config)? {
+            let value = file.load().with_context(|| {
+                format!("Failed to load data file {} for variable '{}'", file.path.display(), key)
+            })?;
+            resolved.insert(
+                key.clone(),
+                ResolvedVariable::new(value, source(VariableKind::File, origins.files.get(&key))),
+            );
+        }
+    }
+
+    // Env variables map a template variable to the environment variable to read
+    if let Some(obj) = vars.env.as_ref().and_then(|v| v.as_object()) {
+        for (key, value) in obj {
+            let Some(env_name) = value.as_str() else {
+                continue;
+            };
+            if let Ok(env_value) = std::env::var(env_name) {
+                resolved.insert(
+                    key.clone(),
+                    ResolvedVariable::new(Value::String(env_value), source(VariableKind::Env, origins.env.get(key))),
+                );
+            }
+        }
+    }
+
+    if vars.command.is_some() {
+        let specs = crate::command_cache::command_specs(config).unwrap_or_else(|e| {
+            eprintln!("Warning: {:#}", e);
+            Default::default()
+        });
+        let mut runner = match read_only {
+            true => CommandRunner::read_only(config),
+            false => CommandRunner::new(config),
+        };
+
+        for (key, spec) in specs {
+            let source = source(VariableKind::Command, origins.command.get(&key));
+            let variable = runner.run(&key, &spec, source);
+            resolved.insert(key, variable);
+        }
+    }
+
+    // Declared defaults fill in variables nothing else set
+    if let Some(schema) = &vars.schema {
+        for (key, declaration) in schema {
+            if let (None, Some(default)) = (resolved.get(key), &declaration.default) {
+                resolved.insert(
+                    key.clone(),
+                    ResolvedVariable::new(default.clone(), VariableSource::new(VariableKind::Default)),
+                );
+            }
+        }
+    }
+
+    Ok(resolved)
+}
+
+/// Variable file next to the files it applies to
+pub const LOCAL_VARS_FILE: &str = "agents.vars.toml";
+
+/// Variables for an output directory (relative to the project root)
+///
+/// Applies, from the root down to `dir`, `.cAGENTS/vars/<ancestor>.toml` and then
+/// `<ancestor>/agents.vars.toml`, so deeper and local files win.
+pub fn resolve_dir_variables(dir: &Path) -> Result<ResolvedVariables> {
+    let mut resolved = ResolvedVariables::default();
+
+    for ancestor in ancestors_from_root(dir) {
+        let mut files = Vec::new();
+        if ancestor.as_os_str() != "." {
+            let mut central = Path::new(".cAGENTS/vars").join(&ancestor);
+            central.set_extension("toml");
+            files.push(central);
+        }
+        files.push(ancestor.join(LOCAL_VARS_FILE));
+
+        for file in files.into_iter().filter(|f| f.is_file()) {
+            let values = crate::data_files::load_file(&file)?;
+            let Value::Object(values) = values else {
+                continue;
+            };
+            let origin = ConfigOrigin {
+                layer: crate::model::ConfigLayer::Project,
+                file: file.strip_prefix(".").unwrap_or(&file).to_path_buf(),
+            };
+            for (key, value) in values {
+                let source = VariableSource {
+                    kind: VariableKind::Directory,
+                    origin: Some(origin.clone()),
+                    rule: None,
+                };
+                resolved.insert(key, ResolvedVariable::new(value, source));
+            }
+        }
+    }
+
+    Ok(resolved)
+}
+
+/// Every per-directory variable file in the project (`.cAGENTS/vars/**/*.toml` and
+/// `agents.vars.toml` files outside ignored directories)
+pub fn dir_variable_files() -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = walkdir::WalkDir::new(".cAGENTS/vars")
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file() && e.path().extension().is_some_and(|ext| ext == "toml"))
+        .map(|e| e.into_path())
+        .collect();
+
+    files.extend(
+        ignore::WalkBuilder::new(".")
+            .build()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name() == LOCAL_VARS_FILE && e.file_type().is_some_and(|t| t.is_file()))
+            .map(|e| e.into_path()),
+    );
+
+    files
+}
+
+/// "." followed by each directory on the way to `dir`: ".", "packages", "packages/api"
+fn ancestors_from_root(dir: &Path) -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::from(".")];
+    let mut current = PathBuf::new();
+    for component in dir.components() {
+        if let std::path::Component::Normal(part) = component {
+            current.push(part);
+            dirs.push(current.clone());
+        }
+    }
+    dirs
+}
+
+const VARIABLE_TYPES: &[&str] = &["string", "number", "boolean", "array", "object"];
+
+/// Validate `[variables.schema]` declarations and the static values they describe
+pub fn validate_schema(config: &ProjectConfig) -> Result<()> {
+    let Some(vars) = &config.variables else {
+        return Ok(());
+    };
+    let Some(schema) = &vars.schema else {
+        return Ok(());
+    };
+
+    for (name, declaration) in schema {
+        if let Some(type_) = &declaration.type_ {
+            if !VARIABLE_TYPES.contains(&type_.as_str()) {
+                anyhow::bail!(
+                    "Unknown type '{}' for variable '{}'. Expected one of: {}",
+                    type_,
+                    name,
+                    VARIABLE_TYPES.join(", ")
+                );
+            }
+        }
+
+        if let Some(default) = &declaration.default {
+            check_value(name, declaration, default).context("in [variables.schema] default")?;
+        }
+
+        if let Some(value) = vars.static_.as_ref().and_then(|v| v.get(name)) {
+            check_value(name, declaration, value).context("in [variables.static]")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Check a value against a declaration's type and allowed values
+///
+/// Env, command and `--var` values are always strings, so numbers and booleans
+/// are also accepted in their string form ("42", "true").
+pub fn check_value(name: &str, declaration: &VariableSchema, value: &Value) -> Result<()> {
+    if let Some(type_) = &declaration.type_ {
+        let matches = match type_.as_str() {
+            "string" => value.is_string(),
+            "number" => value.is_number() || value.as_str().is_some_and(|s| s.parse::<f64>().is_ok()),
+            "boolean" => value.is_boolean() || matches!(value.as_str(), Some("true" | "false")),
+            "array" => value.is_array(),
+            "object" => value.is_object(),
+            _ => true,
+        };
+        if !matches {
+            anyhow::bail!("Variable '{}' must be of type {}, got {}", name, type_, value);
+        }
+    }
+
+    if let Some(allowed) = &declaration.allowed {
+        let text = value_text(value);
+        if !allowed.iter().any(|a| value_text(a) == text) {
+            let allowed: Vec<String> = allowed.iter().map(value_text).collect();
+            anyhow::bail!(
+                "Variable '{}' has value '{}', expected one of: {}",
+                name,
+                text,
+                allowed.join(", ")
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Strings without quotes, everything else as JSON
+fn value_text(value: &Value) -> String {
+    value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string())
+}
+
+/// Runs command variables with the `[execution]` shell, honouring `allowCommands`
+/// and reusing `.cAGENTS/.command-cache` results (saved when the runner is dropped)
+pub struct CommandRunner {
+    shell: String,
+    allowed: bool,
+    cache: crate::command_cache::CommandCache,
+    /// Use cached results but never write the cache (`build --dry-run` / `--check`)
+    read_only: bool,
+}
+
+impl CommandRunner {
+    pub fn new(config: &ProjectConfig) -> Self {
+        let execution = config.execution.as_ref();
+        Self {
+            shell: execution
+                .and_then(|e| e.shell.clone())
+                .unwrap_or_else(|| "bash".to_string()),
+            allowed: execution.and_then(|e| e.allow_commands).unwrap_or(true),
+            cache: crate::command_cache::CommandCache::load(),
+            read_only: false,
+        }
+    }
+
+    /// A runner that leaves `.cAGENTS/.command-cache` untouched
+    pub fn read_only(config: &ProjectConfig) -> Self {
+        let mut runner = Self::new(config);
+        runner.read_only = true;
+        runner
+    }
+
+    /// Run (or reuse) a command variable; empty when commands are disabled or fail
+    pub fn run(
+        &mut self,
+        name: &str,
+        spec: &crate::command_cache::CommandSpec,
+        source: VariableSource,
+    ) -> ResolvedVariable {
+        if !self.allowed {
+            eprintln!(
+                "Warning: Skipping command variable '{}' ([execution] allowCommands = false)",
+                name
+            );
+            return ResolvedVariable::new(Value::String(String::new()), source);
+        }
+
+        let start = Instant::now();
+        let cached = spec
+            .is_cached()
+            .then(|| self.cache.get(&self.shell, spec).map(str::to_string))
+            .flatten();
+
+        let (output, from_cache) = match cached {
+            Some(value) => (value, true),
+            None => match execute_command(&self.shell, &spec.command) {
+                Ok(output) => {
+                    if spec.is_cached() {
+                        self.cache.insert(&self.shell, spec, &output);
+                    }
+                    (output, false)
+                }
+                Err(e) => {
+                    eprintln!("Warning: Failed to execute command '{}': {}", spec.command, e);
+                    (String::new(), false)
+                }
+            },
+        };
+
+        ResolvedVariable {
+            duration: Some(start.elapsed()),
+            cached: from_cache,
+            ..ResolvedVariable::new(Value::String(output), source)
+        }
+    }
+}
+
+impl Drop for CommandRunner {
+    fn drop(&mut self) {
+        if self.read_only {
+            return;
+        }
+        if let Err(e) = self.cache.save() {
+            eprintln!("Warning: {:#}", e);
+        }
+    }
+}
+
+/// Frontmatter `commandVars`, run the first time their rule renders and reused
+/// for every other output of the same command
+///
+/// Results are shared by the rules of one project that declare the same variable with
+/// the same command, such as the `[variables.command]` entries of a nested project.
+pub struct RuleCommands {
+    runner: RefCell<CommandRunner>,
+    results: RefCell<HashMap<(PathBuf, String, String), ResolvedVariable>>,
+}
+
+impl RuleCommands {
+    pub fn new(config: &ProjectConfig) -> Self {
+        Self::with_runner(CommandRunner::new(config))
+    }
+
+    pub fn with_runner(runner: CommandRunner) -> Self {
+        Self {
+            runner: RefCell::new(runner),
+            results: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// The rule's command variables, running each command at most once
+    pub fn resolve(&self, rule: &crate::loader::Rule) -> Result<ResolvedVariables> {
+        let mut resolved = ResolvedVariables::default();
+        let Some(obj) = rule.frontmatter.command_vars.as_ref() else {
+            return Ok(resolved);
+        };
+        let obj = obj
+            .as_object()
+            .with_context(|| format!("commandVars must be a map in {}", rule.path.display()))?;
+
+        let rule_name = rule.frontmatter.name.clone().unwrap_or_else(|| "unnamed".to_string());
+        let project_dir = rule_project_dir(&rule.path);
+
+        for (key, spec) in obj {
+            let memo_key = (project_dir.clone(), key.clone(), spec.to_string());
+            if let Some(variable) = self.results.borrow().get(&memo_key) {
+                let mut variable = variable.clone();
+                variable.source.rule = Some(rule_name.clone());
+                resolved.insert(key.clone(), variable);
+                continue;
+            }
+
+            let spec = crate::command_cache::CommandSpec::from_spec_in(key, spec, &project_dir)
+                .with_context(|| format!("Invalid commandVars in {}", rule.path.display()))?;
+            let mut source = VariableSource::new(VariableKind::Command);
+            source.rule = Some(rule_name.clone());
+
+            let variable = self.runner.borrow_mut().run(key, &spec, source);
+            resolved.insert(key.clone(), variable.clone());
+            self.results.borrow_mut().insert(memo_key, variable);
+        }
+
+        Ok(resolved)
+    }
+}
+
+/// Project a template belongs to: the parent of its `.cAGENTS/` directory
+fn rule_project_dir(template: &Path) -> PathBuf {
+    template
+        .ancestors()
+        .find(|dir| dir.file_name().is_some_and(|n| n == ".cAGENTS"))
+        .and_then(|dir| dir.parent())
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
+/// Execute a shell command and return its stdout as a trimmed string
+pub fn execute_command(shell: &str, command: &str) -> Result<String> {
+    let output = Command::new(shell)
+        .arg("-c")
+        .arg(command)
+        .output()
+        .with_context(|| format!("Failed to execute command: {}", command))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Command failed with exit code {:?}: {}",
+            output.status.code(),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ConfigLayer, Paths, Variables};
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    fn config_with(vars: Variables) -> ProjectConfig {
+        ProjectConfig {
+            project: None,
+            paths: Paths {
+                templates_dir: "templates".to_string(),
+                output_root: ".".to_string(),
+                cursor_rules_dir: None,
+            },
+            defaults: None,
+            variables: Some(vars),
+            execution: None,
+            output: None,
+            matrix: None,
+            variable_origins: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_static_variable_origin() {
+        let mut config = config_with(Variables {
+            static_: Some(json!({"owner": "Jordan"})),
+            ..Default::default()
+        });
+        config.variable_origins.static_.insert(
+            "owner".to_string(),
+            ConfigOrigin {
+                layer: ConfigLayer::Local,
+                file: PathBuf::from(".cAGENTS/config.local.toml"),
+            },
+        );
+
+        let resolved = resolve_config_variables(&config).unwrap();
+        let owner = resolved.get("owner").unwrap();
+        assert_eq!(owner.value, json!("Jordan"));
+        assert_eq!(owner.source.name(), "config.static");
+        assert_eq!(
+            owner.source.describe(),
+            "static (local: .cAGENTS/config.local.toml)"
+        );
+    }
+
+    #[test]
+    fn test_command_overrides_static_and_is_timed() {
+        let config = config_with(Variables {
+            static_: Some(json!({"branch": "main"})),
+            command: Some(json!({"branch": "echo feature"})),
+            ..Default::default()
+        });
+
+        let resolved = resolve_config_variables(&config).unwrap();
+        let branch = resolved.get("branch").unwrap();
+        assert_eq!(branch.value, json!("feature"));
+        assert_eq!(branch.source.kind, VariableKind::Command);
+        assert!(branch.duration.is_some());
+    }
+
+    #[test]
+    fn test_cli_overrides_config() {
+        let config = config_with(Variables {
+            static_: Some(json!({"role": "frontend"})),
+            ..Default::default()
+        });
+
+        let mut resolved = resolve_config_variables(&config).unwrap();
+        let mut cli = Map::new();
+        cli.insert("role".to_string(), json!("backend"));
+        resolved.apply_cli(&cli);
+
+        let role = resolved.get("role").unwrap();
+        assert_eq!(role.value, json!("backend"));
+        assert_eq!(role.source.name(), "cli");
+        assert_eq!(resolved.to_data().get("role").unwrap(), "backend");
+    }
+
+    fn declared(type_: &str, allowed: Option<Value>) -> VariableSchema {
+        VariableSchema {
+            type_: Some(type_.to_string()),
+            allowed: allowed.map(|a| a.as_array().unwrap().clone()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_check_value_types_and_allowed() {
+        let number = declared("number", None);
+        assert!(check_value("n", &number, &json!(3)).is_ok());
+        assert!(check_value("n", &number, &json!("3.5")).is_ok());
+        assert!(check_value("n", &number, &json!("many")).is_err());
+
+        let env = declared("string", Some(json!(["dev", "prod"])));
+        assert!(check_value("env", &env, &json!("prod")).is_ok());
+        let err = check_value("env", &env, &json!("staging")).unwrap_err();
+        assert!(err.to_string().contains("expected one of: dev, prod"));
+    }
+
+    #[test]
+    fn test_schema_default_and_missing_required() {
+        let mut schema = BTreeMap::new();
+        schema.insert(
+            "tone".to_string(),
+            VariableSchema {
+                default: Some(json!("friendly")),
+                ..Default::default()
+            },
+        );
+        schema.insert(
+            "team".to_string(),
+            VariableSchema {
+                required: Some(true),
+                description: Some("Owning team".to_string()),
+                ..Default::default()
+            },
+        );
+        let config = config_with(Variables {
+            schema: Some(schema),
+            ..Default::default()
+        });
+
+        let mut resolved = resolve_config_variables(&config).unwrap();
+        let missing = resolved.apply_schema(&config).unwrap();
+        assert_eq!(missing, vec!["team".to_string()]);
+
+        let tone = resolved.get("tone").unwrap();
+        assert_eq!(tone.value, json!("friendly"));
+        assert_eq!(tone.source.name(), "config.default");
+    }
+
+    #[test]
+    fn test_validate_schema_rejects_bad_static_value() {
+        let mut schema = BTreeMap::new();
+        schema.insert("env".to_string(), declared("string", Some(json!(["dev", "prod"]))));
+        let config = config_with(Variables {
+            static_: Some(json!({"env": "qa"})),
+            schema: Some(schema),
+            ..Default::default()
+        });
+
+        let err = validate_schema(&config).unwrap_err();
+        assert!(format!("{:#}", err).contains("[variables.static]"));
+    }
+}
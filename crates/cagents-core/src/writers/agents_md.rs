@@ -1,24 +1,53 @@
 // write AGENTS.md per target directory (root + nested)
 
+use crate::output_diff::normalize;
+use crate::targets::Target;
 use anyhow::{Context, Result};
 use std::fs;
 use std::path::{Path, PathBuf};
 
-/// Write merged content to AGENTS.md at the specified path
-pub fn write_agents_md(output_dir: &Path, content: &str, _is_root: bool) -> Result<()> {
-    let output_path = output_dir.join("AGENTS.md");
+/// Output file name for the agents-md target
+pub const FILE_NAME: &str = "AGENTS.md";
 
-    // Prepend auto-update header
-    let header = r#"<!--
+/// Auto-generated notice at the top of the file
+pub const HEADER: &str = r#"<!--
 **IMPORTANT**: This project uses **cAGENTS** to provide generated context and instructions for AI coding agents.
 This file is auto-generated. Do not edit it directly.
 -->
 
 "#;
 
-    let full_content = format!("{}{}", header, content);
+/// The `agents-md` output target
+pub struct AgentsMd;
+
+impl Target for AgentsMd {
+    fn name(&self) -> &str {
+        "agents-md"
+    }
+
+    fn display_name(&self) -> &str {
+        "AGENTS.md"
+    }
+
+    fn file_name(&self) -> &str {
+        FILE_NAME
+    }
 
-    fs::write(&output_path, full_content)
+    fn header(&self) -> &str {
+        HEADER
+    }
+}
+
+/// Prepend the auto-generated header to merged content
+pub fn format_agents_md(content: &str) -> String {
+    format!("{}{}", HEADER, content)
+}
+
+/// Write merged content to AGENTS.md at the specified path
+pub fn write_agents_md(output_dir: &Path, content: &str, _is_root: bool) -> Result<()> {
+    let output_path = output_dir.join(FILE_NAME);
+
+    fs::write(&output_path, format_agents_md(content))
         .with_context(|| format!("Failed to write AGENTS.md to {}", output_path.display()))?;
 
     Ok(())
@@ -29,6 +58,12 @@ This file is auto-generated. Do not edit it directly.
 pub struct OutputTracking {
     pub directories: Vec<PathBuf>,
     pub targets: Vec<String>,
+    /// Every file written by the last build (older caches don't have this)
+    #[serde(default)]
+    pub files: Vec<PathBuf>,
+    /// Content fingerprints of the data files the build read (`[variables.files]`)
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub inputs: std::collections::BTreeMap<PathBuf, String>,
 }
 
 /// Load previously written output tracking from cache file
@@ -92,13 +127,27 @@ pub fn save_output_tracking(paths: &[PathBuf]) -> Result<()> {
     Ok(())
 }
 
-/// Save full tracking information (directories + targets)
-pub fn save_full_tracking(directories: &[PathBuf], targets: &[String]) -> Result<()> {
+/// Save full tracking information (directories + targets + written files + data file fingerprints)
+pub fn save_full_tracking(
+    directories: &[PathBuf],
+    targets: &[String],
+    files: &[PathBuf],
+    inputs: std::collections::BTreeMap<PathBuf, String>,
+) -> Result<()> {
     let tracking_file = PathBuf::from(".cAGENTS/.output-cache");
 
+    // Stored without `.` components, so paths compare equal across builds
     let tracking = OutputTracking {
-        directories: directories.to_vec(),
+        directories: directories
+            .iter()
+            .map(|dir| match normalize(dir) {
+                dir if dir.as_os_str().is_empty() => PathBuf::from("."),
+                dir => dir,
+            })
+            .collect(),
         targets: targets.to_vec(),
+        files: files.iter().map(|file| normalize(file)).collect(),
+        inputs,
     };
 
     let json = serde_json::to_string_pretty(&tracking)?;
@@ -107,69 +156,92 @@ pub fn save_full_tracking(directories: &[PathBuf], targets: &[String]) -> Result
     Ok(())
 }
 
+/// Whether anything is at `path`, counting symlinks whose target is gone
+fn path_exists(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok()
+}
+
 /// Clean up old AGENTS.md files that are no longer generated
 pub fn cleanup_old_outputs(current_outputs: &[PathBuf]) -> Result<usize> {
+    Ok(remove_outputs(&old_outputs(current_outputs)?))
+}
+
+/// AGENTS.md files in directories the previous build wrote to and this one won't
+pub fn old_outputs(current_outputs: &[PathBuf]) -> Result<Vec<PathBuf>> {
     let previous_outputs = load_output_tracking()?;
-    let mut cleaned_count = 0;
 
-    for old_path in previous_outputs {
-        // Skip if this path is still in current outputs
-        if current_outputs.contains(&old_path) {
-            continue;
-        }
+    Ok(previous_outputs
+        .into_iter()
+        .filter(|old_path| !current_outputs.contains(old_path))
+        .map(|old_path| old_path.join("AGENTS.md"))
+        .filter(|agents_path| path_exists(agents_path))
+        .collect())
+}
 
-        // Remove the old AGENTS.md file
-        let agents_path = old_path.join("AGENTS.md");
-        if agents_path.exists() {
-            if let Err(e) = fs::remove_file(&agents_path) {
-                eprintln!("  Warning: Could not remove old {}: {}", agents_path.display(), e);
-            } else {
-                cleaned_count += 1;
-            }
-        }
-    }
+/// Clean up files written by the previous build that the current build no longer writes
+pub fn cleanup_stale_files(current_files: &[PathBuf]) -> Result<usize> {
+    Ok(remove_outputs(&stale_files(current_files)?))
+}
+
+/// Files written by the previous build that the current build won't write
+pub fn stale_files(current_files: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let Some(prev) = load_full_tracking()? else {
+        return Ok(Vec::new());
+    };
 
-    Ok(cleaned_count)
+    Ok(prev
+        .files
+        .into_iter()
+        .filter(|old_file| {
+            !current_files.iter().any(|file| normalize(file) == normalize(old_file)) && path_exists(old_file)
+        })
+        .collect())
 }
 
 /// Clean up output files for targets that are no longer in config
-pub fn cleanup_old_target_files(current_targets: &[String], output_root: &Path) -> Result<usize> {
-    let previous_tracking = load_full_tracking()?;
-    let mut cleaned_count = 0;
+pub fn cleanup_old_target_files(
+    current_targets: &[String],
+    output_root: &Path,
+    registry: &crate::targets::TargetRegistry,
+) -> Result<usize> {
+    Ok(remove_outputs(&old_target_files(current_targets, output_root, registry)?))
+}
 
+/// Output files of targets the previous build wrote that are no longer in config
+pub fn old_target_files(
+    current_targets: &[String],
+    output_root: &Path,
+    registry: &crate::targets::TargetRegistry,
+) -> Result<Vec<PathBuf>> {
     // If no previous tracking, nothing to clean up
-    let Some(prev) = previous_tracking else {
-        return Ok(0);
+    let Some(prev) = load_full_tracking()? else {
+        return Ok(Vec::new());
     };
 
-    // Find targets that were removed
-    let removed_targets: Vec<&String> = prev.targets.iter()
+    // Files of each target that was removed
+    Ok(prev
+        .targets
+        .iter()
         .filter(|t| !current_targets.contains(t))
-        .collect();
+        .filter_map(|t| registry.get(t))
+        .flat_map(|target| target.cleanup_paths(output_root))
+        .filter(|file_path| path_exists(file_path))
+        .collect())
+}
 
-    if removed_targets.is_empty() {
-        return Ok(0);
-    }
+/// Remove previously generated files, returning how many were removed
+pub fn remove_outputs(paths: &[PathBuf]) -> usize {
+    let mut cleaned_count = 0;
 
-    // For each removed target, clean up its files
-    for target in removed_targets {
-        let file_path = match target.as_str() {
-            "agents-md" => output_root.join("AGENTS.md"),
-            "claude-md" => output_root.join("CLAUDE.md"),
-            "cursorrules" => output_root.join(".cursorrules"),
-            _ => continue,
-        };
-
-        if file_path.exists() {
-            if let Err(e) = fs::remove_file(&file_path) {
-                eprintln!("  Warning: Could not remove old {}: {}", file_path.display(), e);
-            } else {
-                cleaned_count += 1;
-            }
+    for path in paths {
+        if let Err(e) = fs::remove_file(path) {
+            eprintln!("  Warning: Could not remove old {}: {}", path.display(), e);
+        } else {
+            cleaned_count += 1;
         }
     }
 
-    Ok(cleaned_count)
+    cleaned_count
 }
 
 #[cfg(test)]
@@ -1,24 +1,128 @@
-// Write CLAUDE.md output format
+// Write CLAUDE.md output format, plus .claude/skills/<name>/SKILL.md for skill rules
 
+use crate::loader::Rule;
+use crate::targets::Target;
+use crate::writers::{one_line, rule_file_stem, yaml_string};
 use anyhow::{Context, Result};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-/// Write merged content to CLAUDE.md at the specified path
-pub fn write_claude_md(output_dir: &Path, content: &str, _is_root: bool) -> Result<()> {
-    let output_path = output_dir.join("CLAUDE.md");
+/// Output file name for the claude-md target
+pub const FILE_NAME: &str = "CLAUDE.md";
 
-    // Prepend auto-update header
-    let header = r#"<!--
+/// Auto-generated notice at the top of the file
+pub const HEADER: &str = r#"<!--
 **IMPORTANT**: This project uses **cAGENTS** to provide generated context and instructions for AI coding agents.
 This file is auto-generated. Do not edit it directly.
 -->
 
 "#;
 
-    let full_content = format!("{}{}", header, content);
+/// Directory skills are written to, relative to the output root
+pub const SKILLS_DIR: &str = ".claude/skills";
+
+/// The `claude-md` output target
+///
+/// Rules with `skill: true` become skills instead of being merged into CLAUDE.md.
+pub struct ClaudeMd {
+    /// List the directory's skills at the end of CLAUDE.md (`[output] listSkills`)
+    pub list_skills: bool,
+}
+
+impl Target for ClaudeMd {
+    fn name(&self) -> &str {
+        "claude-md"
+    }
+
+    fn display_name(&self) -> &str {
+        "CLAUDE.md (Claude Code)"
+    }
+
+    fn file_name(&self) -> &str {
+        FILE_NAME
+    }
+
+    fn header(&self) -> &str {
+        HEADER
+    }
+
+    fn per_rule(&self) -> bool {
+        true
+    }
 
-    fs::write(&output_path, full_content)
+    fn rule_file(&self, rule: &Rule) -> Option<PathBuf> {
+        (rule.frontmatter.skill == Some(true))
+            .then(|| PathBuf::from(SKILLS_DIR).join(skill_name(rule)).join("SKILL.md"))
+    }
+
+    fn format_rule(&self, rule: &Rule, rendered: &str) -> String {
+        format_skill(rule, rendered)
+    }
+
+    fn rule_index(&self, rules: &[&Rule]) -> String {
+        if !self.list_skills || rules.is_empty() {
+            return String::new();
+        }
+        let skills: String = rules
+            .iter()
+            .map(|rule| {
+                let description = one_line(rule.frontmatter.description.as_deref().unwrap_or(""));
+                format!("- `{}`: {}\n", skill_name(rule), description)
+            })
+            .collect();
+        format!("\n\n## Skills\n\nSkills in `{}/`:\n\n{}", SKILLS_DIR, skills)
+    }
+
+    fn gitignore_patterns(&self) -> Vec<String> {
+        vec![
+            FILE_NAME.to_string(),
+            format!("**/{}", FILE_NAME),
+            format!("{}/*/SKILL.md", SKILLS_DIR),
+        ]
+    }
+}
+
+/// `import` mode body: an import of the directory's AGENTS.md, then `rest`
+/// (the rules only CLAUDE.md gets)
+pub fn format_import(agents_file: &str, rest: &str) -> String {
+    if rest.is_empty() {
+        format!("@{}\n", agents_file)
+    } else {
+        format!("@{}\n\n{}", agents_file, rest)
+    }
+}
+
+/// Skill name: the rule's name in lowercase, with anything but letters, digits and `-` replaced
+pub fn skill_name(rule: &Rule) -> String {
+    rule_file_stem(rule)
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '-' })
+        .collect()
+}
+
+/// A SKILL.md: `name` and `description` frontmatter, then the rendered body
+pub fn format_skill(rule: &Rule, rendered: &str) -> String {
+    let description = yaml_string(rule.frontmatter.description.as_deref().unwrap_or(""));
+    format!(
+        "---\nname: {}\ndescription: {}\n---\n{}{}",
+        skill_name(rule),
+        description,
+        HEADER,
+        rendered
+    )
+}
+
+/// Prepend the auto-generated header to merged content
+pub fn format_claude_md(content: &str) -> String {
+    format!("{}{}", HEADER, content)
+}
+
+/// Write merged content to CLAUDE.md at the specified path
+pub fn write_claude_md(output_dir: &Path, content: &str, _is_root: bool) -> Result<()> {
+    let output_path = output_dir.join(FILE_NAME);
+
+    fs::write(&output_path, format_claude_md(content))
         .with_context(|| format!("Failed to write CLAUDE.md to {}", output_path.display()))?;
 
     Ok(())
@@ -27,8 +131,45 @@ This file is auto-generated. Do not edit it directly.
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::model::RuleFrontmatter;
     use tempfile::TempDir;
 
+    fn skill_rule(name: &str) -> Rule {
+        Rule {
+            frontmatter: RuleFrontmatter {
+                name: Some(name.to_string()),
+                description: Some("Release steps,\nrun when publishing".to_string()),
+                skill: Some(true),
+                ..Default::default()
+            },
+            body: String::new(),
+            path: PathBuf::from("release.md"),
+        }
+    }
+
+    #[test]
+    fn test_format_import() {
+        assert_eq!(format_import("AGENTS.md", ""), "@AGENTS.md\n");
+        assert_eq!(format_import("AGENTS.md", "# Claude only"), "@AGENTS.md\n\n# Claude only");
+    }
+
+    #[test]
+    fn test_skill_rules_get_skill_files() {
+        let rule = skill_rule("Release Process");
+        let target = ClaudeMd { list_skills: true };
+        assert_eq!(
+            target.rule_file(&rule),
+            Some(PathBuf::from(".claude/skills/release-process/SKILL.md"))
+        );
+        assert!(format_skill(&rule, "Steps").starts_with(
+            "---\nname: release-process\ndescription: \"Release steps, run when publishing\"\n---\n"
+        ));
+        assert!(target
+            .rule_index(&[&rule])
+            .ends_with("- `release-process`: Release steps, run when publishing\n"));
+        assert_eq!(ClaudeMd { list_skills: false }.rule_index(&[&rule]), "");
+    }
+
     #[test]
     fn test_write_claude_md() {
         let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,89 @@
+// emit .clinerules/*.md: one Cline rule per template, conditional on its globs
+
+use crate::loader::Rule;
+use crate::targets::Target;
+use crate::writers::rule_file_stem;
+use std::path::PathBuf;
+
+/// Directory the rules are written to, relative to the output root
+pub const RULES_DIR: &str = ".clinerules";
+
+/// Auto-generated notice at the top of each rule's body
+pub const HEADER: &str = "<!-- This file is auto-generated by cAGENTS. Do not edit it directly. -->\n\n";
+
+/// The `cline` target: one rule file per template in `.clinerules/`
+pub struct Cline;
+
+impl Target for Cline {
+    fn name(&self) -> &str {
+        "cline"
+    }
+
+    fn display_name(&self) -> &str {
+        "Cline rules (.clinerules/)"
+    }
+
+    fn file_name(&self) -> &str {
+        ".clinerules/*.md"
+    }
+
+    fn header(&self) -> &str {
+        HEADER
+    }
+
+    fn per_rule(&self) -> bool {
+        true
+    }
+
+    fn rule_file(&self, rule: &Rule) -> Option<PathBuf> {
+        Some(PathBuf::from(RULES_DIR).join(format!("{}.md", rule_file_stem(rule))))
+    }
+
+    fn format_rule(&self, rule: &Rule, rendered: &str) -> String {
+        format_cline_rule(rule, rendered)
+    }
+
+    fn gitignore_patterns(&self) -> Vec<String> {
+        vec![self.file_name().to_string()]
+    }
+}
+
+/// A Cline rule: rules with globs get `paths` frontmatter so Cline only
+/// applies them to matching files; rules without globs are always active
+pub fn format_cline_rule(rule: &Rule, rendered: &str) -> String {
+    let globs = rule.frontmatter.globs.clone().unwrap_or_default();
+    if globs.is_empty() {
+        return format!("{}{}", HEADER, rendered);
+    }
+
+    let paths: String = globs.iter().map(|g| format!("  - \"{}\"\n", g)).collect();
+    format!("---\npaths:\n{}---\n{}{}", paths, HEADER, rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::RuleFrontmatter;
+
+    fn rule(globs: Option<Vec<&str>>) -> Rule {
+        Rule {
+            frontmatter: RuleFrontmatter {
+                name: Some("db".to_string()),
+                globs: globs.map(|g| g.into_iter().map(str::to_string).collect()),
+                ..Default::default()
+            },
+            body: String::new(),
+            path: PathBuf::from("db.md"),
+        }
+    }
+
+    #[test]
+    fn test_cline_paths_from_globs() {
+        let conditional = format_cline_rule(&rule(Some(vec!["db/**", "migrations/*.sql"])), "Use transactions");
+        assert!(conditional.starts_with("---\npaths:\n  - \"db/**\"\n  - \"migrations/*.sql\"\n---\n"));
+        assert!(conditional.ends_with("Use transactions"));
+
+        assert_eq!(format_cline_rule(&rule(None), "Always"), format!("{}Always", HEADER));
+        assert_eq!(Cline.rule_file(&rule(None)), Some(PathBuf::from(".clinerules/db.md")));
+    }
+}
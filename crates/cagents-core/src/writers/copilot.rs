@@ -0,0 +1,110 @@
+// emit GitHub Copilot instructions: .github/copilot-instructions.md + .github/instructions/*.instructions.md
+
+use crate::loader::Rule;
+use crate::targets::Target;
+use crate::writers::{rule_file_stem, yaml_string};
+use std::path::PathBuf;
+
+/// Repository-wide instructions (rules without globs), relative to the output root
+pub const FILE_NAME: &str = ".github/copilot-instructions.md";
+
+/// Path-specific instruction files (rules with globs), relative to the output root
+pub const INSTRUCTIONS_DIR: &str = ".github/instructions";
+
+/// Auto-generated notice at the top of each file's body
+pub const HEADER: &str = r#"<!--
+**IMPORTANT**: This project uses **cAGENTS** to provide generated context and instructions for AI coding agents.
+This file is auto-generated. Do not edit it directly.
+-->
+
+"#;
+
+/// The `copilot` target: rules without globs are merged into
+/// `copilot-instructions.md`, each rule with globs gets an `.instructions.md`
+/// file applying to those globs
+pub struct Copilot;
+
+impl Target for Copilot {
+    fn name(&self) -> &str {
+        "copilot"
+    }
+
+    fn display_name(&self) -> &str {
+        "GitHub Copilot instructions"
+    }
+
+    fn file_name(&self) -> &str {
+        FILE_NAME
+    }
+
+    fn header(&self) -> &str {
+        HEADER
+    }
+
+    fn per_rule(&self) -> bool {
+        true
+    }
+
+    fn rule_file(&self, rule: &Rule) -> Option<PathBuf> {
+        let has_globs = rule.frontmatter.globs.as_ref().is_some_and(|g| !g.is_empty());
+        has_globs.then(|| PathBuf::from(INSTRUCTIONS_DIR).join(format!("{}.instructions.md", rule_file_stem(rule))))
+    }
+
+    fn format_rule(&self, rule: &Rule, rendered: &str) -> String {
+        format_instructions(rule, rendered)
+    }
+
+    fn gitignore_patterns(&self) -> Vec<String> {
+        vec![
+            FILE_NAME.to_string(),
+            format!("{}/*.instructions.md", INSTRUCTIONS_DIR),
+        ]
+    }
+}
+
+/// A path-specific instructions file: `applyTo` (the rule's globs, comma-separated)
+/// and `description` frontmatter, then the rendered body
+pub fn format_instructions(rule: &Rule, rendered: &str) -> String {
+    let globs = rule.frontmatter.globs.clone().unwrap_or_default();
+    let mut frontmatter = format!("applyTo: \"{}\"\n", globs.join(","));
+    if let Some(description) = &rule.frontmatter.description {
+        frontmatter.push_str(&format!("description: {}\n", yaml_string(description)));
+    }
+
+    format!("---\n{}---\n{}{}", frontmatter, HEADER, rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::RuleFrontmatter;
+
+    fn rule(globs: Option<Vec<&str>>) -> Rule {
+        Rule {
+            frontmatter: RuleFrontmatter {
+                name: Some("api rules".to_string()),
+                description: Some("API \"layer\" conventions".to_string()),
+                globs: globs.map(|g| g.into_iter().map(str::to_string).collect()),
+                ..Default::default()
+            },
+            body: String::new(),
+            path: PathBuf::from("api.md"),
+        }
+    }
+
+    #[test]
+    fn test_rules_with_globs_get_instruction_files() {
+        let with_globs = rule(Some(vec!["src/api/**", "tests/api/**"]));
+        assert_eq!(
+            Copilot.rule_file(&with_globs),
+            Some(PathBuf::from(".github/instructions/api-rules.instructions.md"))
+        );
+        assert_eq!(Copilot.rule_file(&rule(None)), None);
+
+        let output = format_instructions(&with_globs, "Validate input");
+        assert!(output.starts_with(
+            "---\napplyTo: \"src/api/**,tests/api/**\"\ndescription: \"API \\\"layer\\\" conventions\"\n---\n"
+        ));
+        assert!(output.ends_with("Validate input"));
+    }
+}
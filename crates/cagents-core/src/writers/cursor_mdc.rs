@@ -1,78 +1,122 @@
-// emit .cursor/rules/*.mdc mirroring front-matter and body
+// emit .cursor/rules/*.mdc: one Cursor rule per template, frontmatter derived from ours
 
 use crate::loader::Rule;
-use anyhow::{Context, Result};
-use std::fs;
-use std::path::Path;
-
-/// Write rules to Cursor .mdc format
-pub fn write_cursor_rules(cursor_dir: &Path, rules: &[Rule], rendered_bodies: &[String]) -> Result<()> {
-    // Create .cursor/rules directory
-    let rules_dir = cursor_dir.join("rules");
-    fs::create_dir_all(&rules_dir)
-        .with_context(|| format!("Failed to create {}", rules_dir.display()))?;
-
-    // Write each rule that targets cursor
-    for (rule, rendered) in rules.iter().zip(rendered_bodies.iter()) {
-        // Check if this rule targets cursor
-        let targets_cursor = rule.frontmatter.targets.as_ref()
-            .map(|t| t.contains(&"cursor".to_string()))
-            .unwrap_or(false);
-
-        if !targets_cursor {
-            continue;
-        }
+use crate::targets::Target;
+use crate::writers::{rule_file_stem, yaml_string};
+use std::path::PathBuf;
+
+/// Default `paths.cursorRulesDir`, relative to the output root
+pub const DEFAULT_DIR: &str = ".cursor/rules";
 
-        // Generate filename
-        let name = rule.frontmatter.name.as_deref().unwrap_or("rule");
-        let filename = format!("{}.md", name);
+/// Auto-generated notice placed after the `.mdc` frontmatter
+pub const HEADER: &str = "<!-- This file is auto-generated by cAGENTS. Do not edit it directly. -->\n\n";
 
-        // Cursor .mdc format is just markdown with optional metadata comments
-        let mut content = String::new();
+/// The `cursor-rules` target: one `.mdc` file per rule in `cursorRulesDir`
+pub struct CursorRules {
+    dir: PathBuf,
+    /// `<dir>/*.mdc`, matching every file the target writes
+    pattern: String,
+}
 
-        // Add metadata comment if useful
-        if let Some(desc) = &rule.frontmatter.description {
-            content.push_str(&format!("<!-- Description: {} -->\n\n", desc));
+impl CursorRules {
+    pub fn new(dir: &str) -> Self {
+        let dir = dir.trim_end_matches('/');
+        Self {
+            dir: PathBuf::from(dir),
+            pattern: format!("{}/*.mdc", dir),
         }
+    }
+}
+
+impl Target for CursorRules {
+    fn name(&self) -> &str {
+        "cursor-rules"
+    }
 
-        // Add rendered content
-        content.push_str(rendered);
+    fn display_name(&self) -> &str {
+        "Cursor rules (.mdc)"
+    }
+
+    fn file_name(&self) -> &str {
+        &self.pattern
+    }
+
+    fn header(&self) -> &str {
+        HEADER
+    }
+
+    fn per_rule(&self) -> bool {
+        true
+    }
 
-        fs::write(rules_dir.join(&filename), content)
-            .with_context(|| format!("Failed to write {}", filename))?;
+    fn rule_file(&self, rule: &Rule) -> Option<PathBuf> {
+        Some(self.dir.join(format!("{}.mdc", rule_file_stem(rule))))
     }
 
-    Ok(())
+    fn format_rule(&self, rule: &Rule, rendered: &str) -> String {
+        format_mdc(rule, rendered)
+    }
+
+    fn gitignore_patterns(&self) -> Vec<String> {
+        vec![self.pattern.clone()]
+    }
+}
+
+/// A Cursor rule: `description`, `globs` and `alwaysApply` frontmatter, then the rendered body
+///
+/// Rules without globs apply everywhere they were selected, so they become
+/// `alwaysApply: true`; rules with globs are attached when a matching file is in context.
+pub fn format_mdc(rule: &Rule, rendered: &str) -> String {
+    let description = rule.frontmatter.description.as_deref().map(yaml_string).unwrap_or_default();
+    let globs = rule.frontmatter.globs.clone().unwrap_or_default();
+
+    format!(
+        "---\ndescription: {}\nglobs: {}\nalwaysApply: {}\n---\n{}{}",
+        description,
+        globs.join(", "),
+        globs.is_empty(),
+        HEADER,
+        rendered
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::model::RuleFrontmatter;
-    use std::path::PathBuf;
-    use tempfile::TempDir;
-
-    #[test]
-    fn test_write_cursor_rules() {
-        let tmp = TempDir::new().unwrap();
 
-        let rule = Rule {
+    fn rule(name: &str, globs: Option<Vec<&str>>) -> Rule {
+        Rule {
             frontmatter: RuleFrontmatter {
-                name: Some("test-rule".to_string()),
-                description: Some("Test description".to_string()),
-                targets: Some(vec!["cursor".to_string()]),
+                name: Some(name.to_string()),
+                description: Some("TypeScript\nconventions".to_string()),
+                globs: globs.map(|g| g.into_iter().map(str::to_string).collect()),
                 ..Default::default()
             },
             body: "# Test\nContent".to_string(),
             path: PathBuf::from("test.md"),
-        };
-
-        let rendered = vec!["# Test\nRendered content".to_string()];
+        }
+    }
 
-        write_cursor_rules(&tmp.path().join(".cursor"), &[rule], &rendered).unwrap();
+    #[test]
+    fn test_mdc_frontmatter_from_rule() {
+        let output = format_mdc(&rule("ts", Some(vec!["src/**/*.ts", "tests/**"])), "Rendered content");
+        assert!(output.starts_with(
+            "---\ndescription: \"TypeScript conventions\"\nglobs: src/**/*.ts, tests/**\nalwaysApply: false\n---\n"
+        ));
+        assert!(output.ends_with("Rendered content"));
+
+        let always = format_mdc(&rule("base", None), "Everywhere");
+        assert!(always.contains("globs: \nalwaysApply: true\n"));
+    }
 
-        let output = fs::read_to_string(tmp.path().join(".cursor/rules/test-rule.md")).unwrap();
-        assert!(output.contains("Test description"));
-        assert!(output.contains("Rendered content"));
+    #[test]
+    fn test_rule_file_in_rules_dir() {
+        let target = CursorRules::new(".cursor/rules/");
+        assert_eq!(
+            target.rule_file(&rule("api/service rules", None)),
+            Some(PathBuf::from(".cursor/rules/api-service-rules.mdc"))
+        );
+        assert_eq!(target.gitignore_patterns(), vec![".cursor/rules/*.mdc"]);
     }
 }
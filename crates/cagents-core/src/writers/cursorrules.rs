@@ -1,22 +1,50 @@
 // Write .cursorrules output format
 
+use crate::targets::Target;
 use anyhow::{Context, Result};
 use std::fs;
 use std::path::Path;
 
-/// Write merged content to .cursorrules at the specified path
-pub fn write_cursorrules(output_dir: &Path, content: &str) -> Result<()> {
-    let output_path = output_dir.join(".cursorrules");
+/// Output file name for the cursorrules target
+pub const FILE_NAME: &str = ".cursorrules";
 
-    // Prepend auto-update header
-    let header = r#"# IMPORTANT: This project uses cAGENTS to provide generated context and instructions for AI coding agents.
+/// Auto-generated notice at the top of the file
+pub const HEADER: &str = r#"# IMPORTANT: This project uses cAGENTS to provide generated context and instructions for AI coding agents.
 # This file is auto-generated. Do not edit it directly.
 
 "#;
 
-    let full_content = format!("{}{}", header, content);
+/// The `cursorrules` output target
+pub struct Cursorrules;
+
+impl Target for Cursorrules {
+    fn name(&self) -> &str {
+        "cursorrules"
+    }
+
+    fn display_name(&self) -> &str {
+        ".cursorrules (Cursor legacy)"
+    }
+
+    fn file_name(&self) -> &str {
+        FILE_NAME
+    }
+
+    fn header(&self) -> &str {
+        HEADER
+    }
+}
+
+/// Prepend the auto-generated header to merged content
+pub fn format_cursorrules(content: &str) -> String {
+    format!("{}{}", HEADER, content)
+}
+
+/// Write merged content to .cursorrules at the specified path
+pub fn write_cursorrules(output_dir: &Path, content: &str) -> Result<()> {
+    let output_path = output_dir.join(FILE_NAME);
 
-    fs::write(&output_path, full_content)
+    fs::write(&output_path, format_cursorrules(content))
         .with_context(|| format!("Failed to write .cursorrules to {}", output_path.display()))?;
 
     Ok(())
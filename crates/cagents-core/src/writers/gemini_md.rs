@@ -0,0 +1,35 @@
+// Write GEMINI.md output format
+
+use crate::targets::Target;
+
+/// Output file name for the gemini-md target
+pub const FILE_NAME: &str = "GEMINI.md";
+
+/// Auto-generated notice at the top of the file
+pub const HEADER: &str = r#"<!--
+**IMPORTANT**: This project uses **cAGENTS** to provide generated context and instructions for AI coding agents.
+This file is auto-generated. Do not edit it directly.
+-->
+
+"#;
+
+/// The `gemini-md` output target (Gemini CLI), written at the root and in nested directories
+pub struct GeminiMd;
+
+impl Target for GeminiMd {
+    fn name(&self) -> &str {
+        "gemini-md"
+    }
+
+    fn display_name(&self) -> &str {
+        "GEMINI.md (Gemini CLI)"
+    }
+
+    fn file_name(&self) -> &str {
+        FILE_NAME
+    }
+
+    fn header(&self) -> &str {
+        HEADER
+    }
+}
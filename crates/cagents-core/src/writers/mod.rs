@@ -1,4 +1,35 @@
 pub mod agents_md;
 pub mod cursor_mdc;
 pub mod claude_md;
+pub mod cline;
+pub mod copilot;
 pub mod cursorrules;
+pub mod gemini_md;
+pub mod windsurf;
+
+use crate::loader::Rule;
+
+/// File name for a rule's own file (per-rule targets): its name with anything but
+/// letters, digits, `-`, `_` and `.` replaced
+pub(crate) fn rule_file_stem(rule: &Rule) -> String {
+    let name = rule.frontmatter.name.clone().unwrap_or_else(|| {
+        rule.path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| "rule".to_string())
+    });
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '-' })
+        .collect()
+}
+
+/// `text` on a single line, with runs of whitespace collapsed (for frontmatter descriptions)
+pub(crate) fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// `text` as a double-quoted YAML scalar on one line (`"`, `\\` escaped), so descriptions
+/// containing `: `, `#` or quotes stay plain strings
+pub(crate) fn yaml_string(text: &str) -> String {
+    format!("\"{}\"", one_line(text).replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -0,0 +1,103 @@
+// emit .windsurf/rules/*.md: one Windsurf rule per template with trigger metadata
+
+use crate::loader::Rule;
+use crate::targets::Target;
+use crate::writers::{rule_file_stem, yaml_string};
+use std::path::PathBuf;
+
+/// Directory the rules are written to, relative to the output root
+pub const RULES_DIR: &str = ".windsurf/rules";
+
+/// Auto-generated notice placed after the rule frontmatter
+pub const HEADER: &str = "<!-- This file is auto-generated by cAGENTS. Do not edit it directly. -->\n\n";
+
+/// The `windsurf` target: one rule file per template in `.windsurf/rules`
+pub struct Windsurf;
+
+impl Target for Windsurf {
+    fn name(&self) -> &str {
+        "windsurf"
+    }
+
+    fn display_name(&self) -> &str {
+        "Windsurf rules"
+    }
+
+    fn file_name(&self) -> &str {
+        ".windsurf/rules/*.md"
+    }
+
+    fn header(&self) -> &str {
+        HEADER
+    }
+
+    fn per_rule(&self) -> bool {
+        true
+    }
+
+    fn rule_file(&self, rule: &Rule) -> Option<PathBuf> {
+        Some(PathBuf::from(RULES_DIR).join(format!("{}.md", rule_file_stem(rule))))
+    }
+
+    fn format_rule(&self, rule: &Rule, rendered: &str) -> String {
+        format_windsurf_rule(rule, rendered)
+    }
+
+    fn gitignore_patterns(&self) -> Vec<String> {
+        vec![self.file_name().to_string()]
+    }
+}
+
+/// A Windsurf rule: `trigger`, `globs` and `description` frontmatter, then the rendered body
+///
+/// Rules with globs use the `glob` trigger; rules without apply everywhere
+/// they were selected, so they are `always_on`.
+pub fn format_windsurf_rule(rule: &Rule, rendered: &str) -> String {
+    let globs = rule.frontmatter.globs.clone().unwrap_or_default();
+    let mut frontmatter = if globs.is_empty() {
+        "trigger: always_on\n".to_string()
+    } else {
+        format!("trigger: glob\nglobs: {}\n", globs.join(", "))
+    };
+    if let Some(description) = &rule.frontmatter.description {
+        frontmatter.push_str(&format!("description: {}\n", yaml_string(description)));
+    }
+
+    format!("---\n{}---\n{}{}", frontmatter, HEADER, rendered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::RuleFrontmatter;
+
+    fn rule(globs: Option<Vec<&str>>) -> Rule {
+        Rule {
+            frontmatter: RuleFrontmatter {
+                name: Some("web ui".to_string()),
+                description: Some("Frontend\nconventions".to_string()),
+                globs: globs.map(|g| g.into_iter().map(str::to_string).collect()),
+                ..Default::default()
+            },
+            body: String::new(),
+            path: PathBuf::from("web.md"),
+        }
+    }
+
+    #[test]
+    fn test_windsurf_trigger_from_globs() {
+        let glob = format_windsurf_rule(&rule(Some(vec!["web/**/*.tsx", "web/**/*.css"])), "Use hooks");
+        assert!(glob.starts_with(
+            "---\ntrigger: glob\nglobs: web/**/*.tsx, web/**/*.css\ndescription: \"Frontend conventions\"\n---\n"
+        ));
+        assert!(glob.ends_with("Use hooks"));
+
+        let always = format_windsurf_rule(&rule(None), "Everywhere");
+        assert!(always.starts_with("---\ntrigger: always_on\ndescription:"));
+
+        assert_eq!(
+            Windsurf.rule_file(&rule(None)),
+            Some(PathBuf::from(".windsurf/rules/web-ui.md"))
+        );
+    }
+}
@@ -0,0 +1,171 @@
+// Test `build --var` overrides and [matrix] variant builds
+
+use cagents_core::BuildOptions;
+use serial_test::serial;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+fn write_role_templates() {
+    fs::create_dir_all(".cAGENTS/templates").unwrap();
+
+    fs::write(".cAGENTS/templates/common.md", r#"---
+name: common
+---
+# Common Rules for {{role}}
+"#).unwrap();
+
+    fs::write(".cAGENTS/templates/backend.md", r#"---
+name: backend
+when:
+  role: ["backend"]
+---
+# Backend Rules
+"#).unwrap();
+
+    fs::write(".cAGENTS/templates/frontend.md", r#"---
+name: frontend
+when:
+  role: ["frontend"]
+---
+# Frontend Rules
+"#).unwrap();
+}
+
+#[test]
+#[serial]
+fn test_build_var_affects_data_and_when() {
+    let tmp = TempDir::new().unwrap();
+    let _guard = ChangeDir::new(tmp.path());
+
+    write_role_templates();
+    fs::write(".cAGENTS/config.toml", r#"
+[paths]
+templatesDir = "templates"
+outputRoot = "."
+
+[defaults]
+engine = "builtin:simple"
+
+[variables.static]
+role = "frontend"
+"#).unwrap();
+
+    cagents_core::cmd_build_with_options(BuildOptions {
+        vars: vec!["role=backend".to_string()],
+        ..Default::default()
+    }).unwrap();
+
+    let agents = fs::read_to_string("AGENTS.md").unwrap();
+    assert!(agents.contains("Common Rules for backend"), "--var should override config data");
+    assert!(agents.contains("Backend Rules"), "--var should select when: role=backend");
+    assert!(!agents.contains("Frontend Rules"));
+}
+
+#[test]
+#[serial]
+fn test_matrix_directory_layout() {
+    let tmp = TempDir::new().unwrap();
+    let _guard = ChangeDir::new(tmp.path());
+
+    write_role_templates();
+    fs::write(".cAGENTS/config.toml", r#"
+[paths]
+templatesDir = "templates"
+outputRoot = "."
+
+[defaults]
+engine = "builtin:simple"
+
+[matrix]
+outputRoot = "dist/{role}"
+
+[matrix.variables]
+role = ["frontend", "backend"]
+"#).unwrap();
+
+    cagents_core::cmd_build(None, false).unwrap();
+
+    let frontend = fs::read_to_string("dist/frontend/AGENTS.md").unwrap();
+    assert!(frontend.contains("Common Rules for frontend"));
+    assert!(frontend.contains("Frontend Rules"));
+    assert!(!frontend.contains("Backend Rules"));
+
+    let backend = fs::read_to_string("dist/backend/AGENTS.md").unwrap();
+    assert!(backend.contains("Backend Rules"));
+    assert!(!backend.contains("Frontend Rules"));
+
+    assert!(!PathBuf::from("AGENTS.md").exists(), "Matrix builds should not write the default root");
+}
+
+#[test]
+#[serial]
+fn test_matrix_suffix_layout_and_cleanup() {
+    let tmp = TempDir::new().unwrap();
+    let _guard = ChangeDir::new(tmp.path());
+
+    write_role_templates();
+    fs::write(".cAGENTS/config.toml", r#"
+[paths]
+templatesDir = "templates"
+outputRoot = "."
+
+[defaults]
+engine = "builtin:simple"
+
+[output]
+targets = ["agents-md", "claude-md"]
+
+[matrix]
+layout = "suffix"
+
+[matrix.variables]
+role = ["frontend", "backend"]
+"#).unwrap();
+
+    cagents_core::cmd_build(None, false).unwrap();
+
+    assert!(PathBuf::from("AGENTS.frontend.md").exists());
+    assert!(PathBuf::from("AGENTS.backend.md").exists());
+    assert!(PathBuf::from("CLAUDE.backend.md").exists());
+    let backend = fs::read_to_string("AGENTS.backend.md").unwrap();
+    assert!(backend.contains("Backend Rules"));
+
+    // Pinning the matrix variable builds only that variant and keeps the rest
+    cagents_core::cmd_build_with_options(BuildOptions {
+        vars: vec!["role=backend".to_string()],
+        ..Default::default()
+    }).unwrap();
+
+    assert!(PathBuf::from("AGENTS.backend.md").exists());
+    assert!(PathBuf::from("AGENTS.frontend.md").exists(), "Skipped variant outputs should be kept");
+
+    // Dropping a value from the matrix cleans up its variant
+    let config = fs::read_to_string(".cAGENTS/config.toml").unwrap();
+    fs::write(".cAGENTS/config.toml", config.replace(r#"["frontend", "backend"]"#, r#"["backend"]"#)).unwrap();
+    cagents_core::cmd_build(None, false).unwrap();
+
+    assert!(PathBuf::from("AGENTS.backend.md").exists());
+    assert!(!PathBuf::from("AGENTS.frontend.md").exists(), "Stale variant outputs should be removed");
+    assert!(!PathBuf::from("CLAUDE.frontend.md").exists());
+}
+
+/// Helper to change directory and restore on drop
+struct ChangeDir {
+    original: std::path::PathBuf,
+}
+
+impl ChangeDir {
+    fn new(path: &std::path::Path) -> Self {
+        let original = env::current_dir().unwrap();
+        env::set_current_dir(path).unwrap();
+        Self { original }
+    }
+}
+
+impl Drop for ChangeDir {
+    fn drop(&mut self) {
+        let _ = env::set_current_dir(&self.original);
+    }
+}
@@ -0,0 +1,112 @@
+// Test layered config get/set/unset with format-preserving edits
+
+use cagents_core::config_edit;
+use cagents_core::model::ConfigLayer;
+use serial_test::serial;
+use serde_json::json;
+use std::env;
+use std::fs;
+use tempfile::TempDir;
+
+const PROJECT_CONFIG: &str = r#"# Project settings
+[paths]
+templatesDir = "templates" # where templates live
+outputRoot = "."
+
+[variables.static]
+owner = "Jordan"
+"#;
+
+#[test]
+#[serial]
+fn test_set_preserves_comments_and_formatting() {
+    let tmp = TempDir::new().unwrap();
+    let _guard = ChangeDir::new(tmp.path());
+    fs::create_dir_all(".cAGENTS").unwrap();
+    fs::write(".cAGENTS/config.toml", PROJECT_CONFIG).unwrap();
+
+    config_edit::set_value(ConfigLayer::Project, "output.targets", r#"["agents-md", "claude-md"]"#, &Default::default()).unwrap();
+    config_edit::set_value(ConfigLayer::Project, "variables.static.owner", "Sam", &Default::default()).unwrap();
+
+    let content = fs::read_to_string(".cAGENTS/config.toml").unwrap();
+    assert!(content.contains("# Project settings"));
+    assert!(content.contains(r#"templatesDir = "templates" # where templates live"#));
+    assert!(content.contains(r#"owner = "Sam""#));
+    assert!(content.contains(r#"targets = ["agents-md", "claude-md"]"#));
+
+    assert_eq!(
+        config_edit::get_layer_value(ConfigLayer::Project, "output.targets", &Default::default()).unwrap(),
+        Some(json!(["agents-md", "claude-md"]))
+    );
+}
+
+#[test]
+#[serial]
+fn test_set_rejects_unknown_keys() {
+    let tmp = TempDir::new().unwrap();
+    let _guard = ChangeDir::new(tmp.path());
+    fs::create_dir_all(".cAGENTS").unwrap();
+    fs::write(".cAGENTS/config.toml", PROJECT_CONFIG).unwrap();
+
+    assert!(config_edit::set_value(ConfigLayer::Project, "paths.outputroot", "dist", &Default::default()).is_err());
+    assert_eq!(fs::read_to_string(".cAGENTS/config.toml").unwrap(), PROJECT_CONFIG);
+}
+
+#[test]
+#[serial]
+fn test_effective_value_and_origin() {
+    let tmp = TempDir::new().unwrap();
+    let _guard = ChangeDir::new(tmp.path());
+    fs::create_dir_all(".cAGENTS").unwrap();
+    fs::write(".cAGENTS/config.toml", PROJECT_CONFIG).unwrap();
+
+    // Local layer doesn't exist yet: set creates it
+    config_edit::set_value(ConfigLayer::Local, "variables.static.owner", "Alex", &Default::default()).unwrap();
+
+    let owner = config_edit::get_effective("variables.static.owner", &Default::default()).unwrap().unwrap();
+    assert_eq!(owner.value, json!("Alex"));
+    assert_eq!(owner.origin.layer, ConfigLayer::Local);
+
+    let templates = config_edit::get_effective("paths.templatesDir", &Default::default()).unwrap().unwrap();
+    assert_eq!(templates.value, json!("templates"));
+    assert_eq!(templates.origin.layer, ConfigLayer::Project);
+
+    assert!(config_edit::get_effective("paths.cursorRulesDir", &Default::default()).unwrap().is_none());
+}
+
+#[test]
+#[serial]
+fn test_unset_removes_empty_tables() {
+    let tmp = TempDir::new().unwrap();
+    let _guard = ChangeDir::new(tmp.path());
+    fs::create_dir_all(".cAGENTS").unwrap();
+    fs::write(".cAGENTS/config.toml", PROJECT_CONFIG).unwrap();
+
+    config_edit::unset_value(ConfigLayer::Project, "variables.static.owner", &Default::default()).unwrap();
+
+    let content = fs::read_to_string(".cAGENTS/config.toml").unwrap();
+    assert!(!content.contains("owner"));
+    assert!(!content.contains("[variables"));
+    assert!(content.contains("# Project settings"));
+
+    assert!(config_edit::unset_value(ConfigLayer::Project, "variables.static.owner", &Default::default()).is_err());
+}
+
+/// Helper to change directory and restore on drop
+struct ChangeDir {
+    original: std::path::PathBuf,
+}
+
+impl ChangeDir {
+    fn new(path: &std::path::Path) -> Self {
+        let original = env::current_dir().unwrap();
+        env::set_current_dir(path).unwrap();
+        Self { original }
+    }
+}
+
+impl Drop for ChangeDir {
+    fn drop(&mut self) {
+        let _ = env::set_current_dir(&self.original);
+    }
+}
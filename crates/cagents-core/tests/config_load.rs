@@ -113,3 +113,216 @@ project = "test-project"
         "test-project"
     );
 }
+
+#[test]
+#[serial]
+fn test_profile_overlays_config() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let config_dir = temp_dir.path().join(".cAGENTS");
+    fs::create_dir_all(&config_dir).expect("Failed to create .cAGENTS dir");
+
+    fs::write(
+        config_dir.join("config.toml"),
+        r#"
+[paths]
+templatesDir = "templates"
+outputRoot = "."
+
+[variables.static]
+env = "dev"
+owner = "Alice"
+
+[output]
+targets = ["agents-md"]
+
+[profiles.ci.paths]
+outputRoot = "dist"
+
+[profiles.ci.variables.static]
+env = "ci"
+
+[profiles.release.output]
+targets = ["agents-md", "claude-md"]
+"#,
+    )
+    .expect("Failed to write project config");
+
+    // Local base config is still overridden by the profile
+    fs::write(
+        config_dir.join("config.local.toml"),
+        r#"
+[variables.static]
+env = "local"
+"#,
+    )
+    .expect("Failed to write local config");
+
+    let original_dir = std::env::current_dir().expect("Failed to get current dir");
+    std::env::set_current_dir(temp_dir.path()).expect("Failed to change dir");
+
+    let without_profile = load_config_with_precedence();
+    std::env::set_var("CAGENTS_PROFILE", "ci");
+    let ci = load_config_with_precedence();
+    std::env::set_var("CAGENTS_PROFILE", "missing");
+    let missing = load_config_with_precedence();
+    std::env::remove_var("CAGENTS_PROFILE");
+
+    std::env::set_current_dir(original_dir).expect("Failed to restore dir");
+
+    let config = without_profile.expect("Failed to load config without profile");
+    assert_eq!(config.paths.output_root, ".");
+    let vars = config.variables.unwrap().static_.unwrap();
+    assert_eq!(vars["env"], "local");
+
+    let config = ci.expect("Failed to load config with ci profile");
+    assert_eq!(config.paths.output_root, "dist");
+    assert_eq!(config.paths.templates_dir, "templates");
+    let vars = config.variables.unwrap().static_.unwrap();
+    assert_eq!(vars["env"], "ci");
+    assert_eq!(vars["owner"], "Alice");
+    assert_eq!(config.output.unwrap().targets.unwrap(), vec!["agents-md"]);
+
+    let err = missing.expect_err("Unknown profile should fail").to_string();
+    assert!(err.contains("Profile 'missing' not found"));
+    assert!(err.contains("ci, release"));
+}
+
+#[test]
+#[serial]
+fn test_load_options_select_config_file_and_profile() {
+    use cagents_core::config::{load_config, LoadOptions};
+
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let config_path = temp_dir.path().join("custom.toml");
+    fs::write(
+        &config_path,
+        r#"
+[paths]
+templatesDir = "templates"
+outputRoot = "."
+
+[profiles.ci.paths]
+outputRoot = "dist"
+"#,
+    )
+    .expect("Failed to write config");
+
+    // Neither the file nor the profile comes from the environment
+    let options = LoadOptions {
+        config: Some(config_path.clone()),
+        profile: Some("ci".to_string()),
+    };
+    let config = load_config(&options).expect("Failed to load config");
+    assert_eq!(config.paths.output_root, "dist");
+    assert_eq!(options.project_config_path(), config_path);
+
+    let options = LoadOptions {
+        config: Some(temp_dir.path().join("missing.toml")),
+        profile: None,
+    };
+    let err = load_config(&options).expect_err("Missing config file should fail").to_string();
+    assert!(err.contains("missing.toml"));
+}
+
+#[test]
+fn test_env_override_key_mapping() {
+    use cagents_core::config::env_override_key;
+
+    assert_eq!(env_override_key("CAGENTS_PATHS__OUTPUT_ROOT").as_deref(), Some("paths.outputRoot"));
+    assert_eq!(env_override_key("CAGENTS_EXECUTION__TIMEOUT_MS").as_deref(), Some("execution.timeoutMs"));
+    assert_eq!(env_override_key("CAGENTS_VAR_role").as_deref(), Some("variables.static.role"));
+    assert_eq!(env_override_key("CAGENTS_VAR_ROLE").as_deref(), Some("variables.static.role"));
+    assert_eq!(env_override_key("CAGENTS_TEST"), None);
+    assert_eq!(env_override_key("CAGENTS_PROFILE"), None);
+    assert_eq!(env_override_key("CAGENTS_TELEMETRY_DISABLED"), None);
+    assert_eq!(env_override_key("CAGENTS_SOME_TOOL__SETTING"), None);
+}
+
+#[test]
+#[serial]
+fn test_env_overrides_are_final_layer() {
+    use cagents_core::model::ConfigLayer;
+
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let config_dir = temp_dir.path().join(".cAGENTS");
+    fs::create_dir_all(&config_dir).expect("Failed to create .cAGENTS dir");
+
+    fs::write(
+        config_dir.join("config.toml"),
+        r#"
+[paths]
+templatesDir = "templates"
+outputRoot = "."
+
+[defaults]
+order = 50
+
+[variables.static]
+role = "frontend"
+
+[profiles.ci.paths]
+outputRoot = "ci-out"
+"#,
+    )
+    .expect("Failed to write project config");
+
+    let original_dir = std::env::current_dir().expect("Failed to get current dir");
+    std::env::set_current_dir(temp_dir.path()).expect("Failed to change dir");
+
+    std::env::set_var("CAGENTS_PROFILE", "ci");
+    std::env::set_var("CAGENTS_PATHS__OUTPUT_ROOT", "dist");
+    std::env::set_var("CAGENTS_OUTPUT__TARGETS", "agents-md,claude-md");
+    std::env::set_var("CAGENTS_DEFAULTS__ORDER", "10");
+    std::env::set_var("CAGENTS_VAR_role", "backend");
+    let result = load_config_with_precedence();
+
+    // Invalid overrides are skipped with a warning, unrelated variables ignored
+    std::env::set_var("CAGENTS_PATHS__NOPE", "x");
+    std::env::set_var("CAGENTS_SOME_TOOL__SETTING", "x");
+    let with_stray = load_config_with_precedence();
+
+    for name in [
+        "CAGENTS_PROFILE",
+        "CAGENTS_PATHS__OUTPUT_ROOT",
+        "CAGENTS_OUTPUT__TARGETS",
+        "CAGENTS_DEFAULTS__ORDER",
+        "CAGENTS_VAR_role",
+        "CAGENTS_PATHS__NOPE",
+        "CAGENTS_SOME_TOOL__SETTING",
+    ] {
+        std::env::remove_var(name);
+    }
+    std::env::set_current_dir(original_dir).expect("Failed to restore dir");
+
+    let config = result.expect("Failed to load config with env overrides");
+    assert_eq!(config.paths.output_root, "dist", "env overrides win over profiles");
+    assert_eq!(config.output.unwrap().targets.unwrap(), vec!["agents-md", "claude-md"]);
+    assert_eq!(config.defaults.unwrap().order, Some(10));
+    assert_eq!(config.variables.unwrap().static_.unwrap()["role"], "backend");
+
+    let origin = config.variable_origins.static_.get("role").unwrap();
+    assert_eq!(origin.layer, ConfigLayer::Env);
+    assert_eq!(origin.file, PathBuf::from("CAGENTS_VAR_role"));
+
+    let config = with_stray.expect("Stray overrides should not fail config loading");
+    assert_eq!(config.paths.output_root, "dist");
+}
+
+#[test]
+fn test_find_project_root_searches_upward() {
+    use cagents_core::config::find_project_root;
+
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let root = temp_dir.path();
+    fs::create_dir_all(root.join(".cAGENTS")).unwrap();
+    fs::write(root.join(".cAGENTS/config.toml"), "").unwrap();
+    fs::create_dir_all(root.join("packages/app/src")).unwrap();
+
+    assert_eq!(find_project_root(&root.join("packages/app/src")), Some(root.to_path_buf()));
+    assert_eq!(find_project_root(root), Some(root.to_path_buf()));
+
+    // A .cAGENTS directory without config.toml is not a project
+    let other = TempDir::new().expect("Failed to create temp dir");
+    fs::create_dir_all(other.path().join(".cAGENTS")).unwrap();
+    assert_eq!(find_project_root(other.path()), None);
+}
@@ -26,7 +26,7 @@ targets = ["agents-md", "claude-md", "cursorrules"]
 "#).unwrap();
 
     // Run ignore_outputs
-    cagents_core::helpers::git::ignore_outputs().unwrap();
+    cagents_core::helpers::git::ignore_outputs(&Default::default()).unwrap();
 
     let gitignore = fs::read_to_string(".gitignore").unwrap();
 
@@ -54,7 +54,7 @@ engine = "builtin:simple"
 "#).unwrap();
 
     // Run ignore_outputs
-    cagents_core::helpers::git::ignore_outputs().unwrap();
+    cagents_core::helpers::git::ignore_outputs(&Default::default()).unwrap();
 
     let gitignore = fs::read_to_string(".gitignore").unwrap();
 
@@ -84,7 +84,7 @@ CLAUDE.md
 "#).unwrap();
 
     // Run unignore_outputs
-    cagents_core::helpers::git::unignore_outputs().unwrap();
+    cagents_core::helpers::git::unignore_outputs(&Default::default()).unwrap();
 
     let gitignore = fs::read_to_string(".gitignore").unwrap();
 
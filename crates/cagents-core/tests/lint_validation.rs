@@ -26,7 +26,7 @@ targets = ["agents-md", "invalid-target", "claude-md"]
 "#).unwrap();
 
     // Run lint
-    let result = cagents_core::lint::lint_all().unwrap();
+    let result = cagents_core::lint::lint_all(&Default::default()).unwrap();
 
     // Should have error for invalid target
     assert!(result.has_errors(), "Should have errors for invalid target");
@@ -65,7 +65,7 @@ when:
 "#).unwrap();
 
     // Run lint
-    let result = cagents_core::lint::lint_all().unwrap();
+    let result = cagents_core::lint::lint_all(&Default::default()).unwrap();
 
     // Should have error for invalid when.target
     assert!(result.has_errors(), "Should have errors for invalid when.target values");
@@ -106,7 +106,7 @@ when:
 "#).unwrap();
 
     // Run lint
-    let result = cagents_core::lint::lint_all().unwrap();
+    let result = cagents_core::lint::lint_all(&Default::default()).unwrap();
 
     // Should have no errors related to targets
     let has_target_error = result.issues.iter().any(|i| {
@@ -138,7 +138,7 @@ targets = ["agents-md", "foo", "claude-md", "bar", "cursorrules"]
 "#).unwrap();
 
     // Run lint
-    let result = cagents_core::lint::lint_all().unwrap();
+    let result = cagents_core::lint::lint_all(&Default::default()).unwrap();
 
     // Should catch both invalid targets
     assert!(result.has_errors(), "Should have errors");
@@ -154,6 +154,37 @@ targets = ["agents-md", "foo", "claude-md", "bar", "cursorrules"]
     assert!(has_bar, "Should catch 'bar' as invalid target");
 }
 
+#[test]
+#[serial]
+fn test_lint_rejects_output_settings_in_nested_projects() {
+    let tmp = TempDir::new().unwrap();
+    let _guard = ChangeDir::new(tmp.path());
+
+    fs::create_dir_all(".cAGENTS/templates").unwrap();
+    fs::write(".cAGENTS/config.toml", r#"
+[paths]
+templatesDir = "templates"
+outputRoot = "."
+
+[defaults]
+engine = "builtin:simple"
+"#).unwrap();
+    fs::create_dir_all("packages/web/.cAGENTS").unwrap();
+    fs::write("packages/web/.cAGENTS/config.toml", r#"
+[paths]
+outputRoot = "dist"
+
+[output]
+targets = ["claude-md"]
+"#).unwrap();
+
+    let result = cagents_core::lint::lint_all(&Default::default()).unwrap();
+
+    let messages: Vec<&str> = result.issues.iter().map(|i| i.message.as_str()).collect();
+    assert!(messages.iter().any(|m| m.starts_with("[output] is not supported in nested projects")), "{:?}", messages);
+    assert!(messages.iter().any(|m| m.starts_with("paths.outputRoot is not supported")), "{:?}", messages);
+}
+
 /// Helper to change directory and restore on drop
 struct ChangeDir {
     original: std::path::PathBuf,
@@ -9,7 +9,7 @@ fn test_validate_config_missing() {
     let tmp = TempDir::new().unwrap();
     let _guard = ChangeDir::new(tmp.path());
 
-    let result = validate_config().unwrap();
+    let result = validate_config(&Default::default()).unwrap();
     assert!(result.has_errors());
     assert!(result.error_count() > 0);
 }
@@ -28,7 +28,7 @@ templatesDir = "templates"
 outputRoot = "."
 "#).unwrap();
 
-    let result = validate_config().unwrap();
+    let result = validate_config(&Default::default()).unwrap();
     assert!(!result.has_errors());
 }
 
@@ -41,7 +41,7 @@ fn test_validate_config_invalid_toml() {
     fs::create_dir_all(".cAGENTS").unwrap();
     fs::write(".cAGENTS/config.toml", "invalid {{ toml").unwrap();
 
-    let result = validate_config().unwrap();
+    let result = validate_config(&Default::default()).unwrap();
     assert!(result.has_errors());
     assert!(result.issues[0].message.contains("Failed to parse"));
 }
@@ -60,7 +60,7 @@ templatesDir = "templates"
 outputRoot = "."
 "#).unwrap();
 
-    let result = validate_templates().unwrap();
+    let result = validate_templates(&Default::default()).unwrap();
     // Should not error (config validation catches missing dir)
     assert!(!result.has_errors());
 }
@@ -72,7 +72,7 @@ fn test_lint_all_combines_results() {
     let _guard = ChangeDir::new(tmp.path());
 
     // No config at all
-    let result = lint_all().unwrap();
+    let result = lint_all(&Default::default()).unwrap();
     assert!(result.has_errors());
 }
 
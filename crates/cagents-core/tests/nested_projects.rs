@@ -0,0 +1,134 @@
+// Test nested .cAGENTS projects (monorepo packages) built from the root
+
+use serial_test::serial;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::TempDir;
+
+fn write_monorepo() {
+    fs::create_dir_all(".cAGENTS/templates").unwrap();
+    fs::write(".cAGENTS/config.toml", r#"
+[paths]
+templatesDir = "templates"
+outputRoot = "."
+
+[defaults]
+engine = "builtin:simple"
+
+[variables.static]
+team = "platform"
+tone = "formal"
+"#).unwrap();
+    fs::write(".cAGENTS/templates/org.md", r#"---
+name: org
+---
+# Org Rules ({{team}})
+"#).unwrap();
+
+    fs::create_dir_all("packages/web/.cAGENTS/templates").unwrap();
+    fs::create_dir_all("packages/web/src").unwrap();
+    fs::write("packages/web/src/app.ts", "export {}").unwrap();
+    fs::write("packages/web/.cAGENTS/config.toml", r#"
+[variables.static]
+team = "web"
+
+[variables.command]
+framework = "echo svelte"
+"#).unwrap();
+    fs::write("packages/web/.cAGENTS/templates/web.md", r#"---
+name: web
+---
+# Web Rules ({{team}}, {{tone}}, {{framework}})
+"#).unwrap();
+    fs::write("packages/web/.cAGENTS/templates/team.md", r#"---
+name: web-team
+when:
+  team: ["web"]
+---
+# Web Team Rules
+"#).unwrap();
+    fs::write("packages/web/.cAGENTS/templates/ts.md", r#"---
+name: web-ts
+globs: ["src/**/*.ts"]
+---
+# Web TypeScript Rules
+"#).unwrap();
+
+    // A nested project that only overrides config
+    fs::create_dir_all("packages/api/src").unwrap();
+    fs::write("packages/api/src/main.rs", "fn main() {}").unwrap();
+    fs::create_dir_all("packages/api/.cAGENTS").unwrap();
+    fs::write("packages/api/.cAGENTS/config.toml", r#"
+[variables.static]
+team = "api"
+
+[variables.command]
+marker = "touch api-command-ran && echo yes"
+"#).unwrap();
+}
+
+#[test]
+#[serial]
+fn test_build_includes_nested_projects() {
+    let tmp = TempDir::new().unwrap();
+    let _guard = ChangeDir::new(tmp.path());
+    write_monorepo();
+
+    cagents_core::cmd_build(None, false).unwrap();
+
+    let root = fs::read_to_string("AGENTS.md").unwrap();
+    assert!(root.contains("Org Rules (platform)"));
+    assert!(!root.contains("Web Rules"), "nested rules stay in their subtree");
+
+    let web = fs::read_to_string("packages/web/AGENTS.md").unwrap();
+    assert!(web.contains("Web Rules (web, formal, svelte)"), "nested config merges on top of the root: {}", web);
+    assert!(web.contains("Web Team Rules"), "when clauses see nested variables: {}", web);
+
+    let web_src = fs::read_to_string("packages/web/src/AGENTS.md").unwrap();
+    assert!(web_src.contains("Web TypeScript Rules"));
+
+    assert!(!PathBuf::from("packages/api/AGENTS.md").exists(), "config-only projects add no rules");
+    assert!(!PathBuf::from("api-command-ran").exists(), "nested commands only run for rules that render");
+
+    // One tracking file at the root
+    assert!(PathBuf::from(".cAGENTS/.output-cache").exists());
+    assert!(!PathBuf::from("packages/web/.cAGENTS/.output-cache").exists());
+}
+
+#[test]
+#[serial]
+fn test_nested_projects_config_chain() {
+    let tmp = TempDir::new().unwrap();
+    let _guard = ChangeDir::new(tmp.path());
+    write_monorepo();
+
+    let projects = cagents_core::nested::discover_nested_projects(&Default::default()).unwrap();
+    let dirs: Vec<_> = projects.iter().map(|p| p.dir.clone()).collect();
+    assert_eq!(dirs, vec![PathBuf::from("packages/api"), PathBuf::from("packages/web")]);
+
+    let web = &projects[1];
+    let vars = web.config.variables.as_ref().unwrap().static_.as_ref().unwrap();
+    assert_eq!(vars["team"], "web");
+    assert_eq!(vars["tone"], "formal");
+    assert_eq!(web.config.paths.templates_dir, "templates");
+}
+
+/// Helper to change directory and restore on drop
+struct ChangeDir {
+    original: std::path::PathBuf,
+}
+
+impl ChangeDir {
+    fn new(path: &std::path::Path) -> Self {
+        let original = env::current_dir().unwrap();
+        env::set_current_dir(path).unwrap();
+        Self { original }
+    }
+}
+
+impl Drop for ChangeDir {
+    fn drop(&mut self) {
+        let _ = env::set_current_dir(&self.original);
+    }
+}